@@ -0,0 +1,514 @@
+//! Sending one template to many recipients
+//!
+//! A [`Broadcast`] sends a template to every recipient of an iterator or
+//! channel, with per-recipient components. Sends run concurrently up to a
+//! bound and are paced by a [`RateLimiter`] to the phone number's throughput;
+//! throttled and transient failures are retried, but a send that may have
+//! reached WhatsApp is never repeated. The returned
+//! [`BroadcastHandle`] pauses, resumes and cancels the broadcast, reports
+//! progress while it runs and collects one [`BroadcastResult`] per recipient.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{mpsc, watch, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+
+use crate::client::WhatsAppClient;
+use crate::error::{ErrorKind, WhatsAppError, WhatsAppResult};
+use crate::rate_limiter::RateLimiter;
+use crate::types::messages::{Component, SendTemplateMessage};
+use crate::types::profile::ThroughputLevel;
+use crate::util::phone::wa_id_key;
+
+/// Messages per second a phone number with standard throughput may send.
+pub const STANDARD_THROUGHPUT: u32 = 80;
+
+/// Messages per second a phone number upgraded to high throughput may send.
+pub const HIGH_THROUGHPUT: u32 = 1000;
+
+const DEFAULT_CONCURRENCY: usize = 32;
+
+
+#[derive(Debug, Clone)]
+pub struct BroadcastRecipient {
+
+    pub to: String,
+
+    /// Components filling the template's variables for this recipient.
+    pub components: Option<Vec<Component>>,
+}
+
+impl BroadcastRecipient {
+    pub fn new(to: &str) -> Self {
+        Self {
+            to: to.to_string(),
+            components: None,
+        }
+    }
+
+    pub fn with_components(mut self, components: Vec<Component>) -> Self {
+        self.components = Some(components);
+        self
+    }
+}
+
+impl From<&str> for BroadcastRecipient {
+    fn from(to: &str) -> Self {
+        Self::new(to)
+    }
+}
+
+impl From<String> for BroadcastRecipient {
+    fn from(to: String) -> Self {
+        Self { to, components: None }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BroadcastState {
+
+    Running,
+
+    /// No new sends start; sends already in flight complete.
+    Paused,
+
+    /// No new sends start; recipients not yet taken are left alone.
+    Cancelled,
+
+    Finished,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct BroadcastProgress {
+
+    pub state: BroadcastState,
+
+    /// Number of recipients, when the iterator knows it up front.
+    pub total: Option<usize>,
+
+    /// Recipients taken whose send has not completed, including sends waiting
+    /// for a rate limit slot, a retry or the broadcast to be resumed.
+    pub in_flight: usize,
+
+    pub sent: usize,
+
+    pub failed: usize,
+
+    /// Sends that may or may not have been delivered; see `BroadcastOutcome::Unconfirmed`.
+    pub unconfirmed: usize,
+
+    pub skipped: usize,
+}
+
+impl BroadcastProgress {
+    fn new(total: Option<usize>) -> Self {
+        Self {
+            state: BroadcastState::Running,
+            total,
+            in_flight: 0,
+            sent: 0,
+            failed: 0,
+            unconfirmed: 0,
+            skipped: 0,
+        }
+    }
+
+    /// Recipients that have a result.
+    pub fn completed(&self) -> usize {
+        self.sent + self.failed + self.unconfirmed + self.skipped
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+
+    /// The recipient already appeared earlier in the broadcast.
+    Duplicate,
+
+    /// The broadcast reached its messaging limit before this recipient.
+    MessagingLimitReached,
+
+    /// The broadcast was cancelled before this recipient's message was sent.
+    Cancelled,
+}
+
+
+#[derive(Debug)]
+pub enum BroadcastOutcome {
+
+    Sent {
+        message_id: String,
+    },
+
+    /// Failed for good, after any retries.
+    Failed(WhatsAppError),
+
+    /// The request may have reached WhatsApp, but no response confirmed it,
+    /// e.g. after a timeout. Not retried, so the recipient never gets the
+    /// template twice; check the message status webhooks before sending again.
+    Unconfirmed(WhatsAppError),
+
+    Skipped(SkipReason),
+}
+
+impl BroadcastOutcome {
+    pub fn error_kind(&self) -> Option<ErrorKind> {
+        match self {
+            Self::Failed(error) | Self::Unconfirmed(error) => Some(error.kind()),
+            _ => None,
+        }
+    }
+}
+
+
+#[derive(Debug)]
+pub struct BroadcastResult {
+
+    /// Position of the recipient in the input.
+    pub index: usize,
+
+    pub to: String,
+
+    pub outcome: BroadcastOutcome,
+}
+
+
+#[derive(Debug)]
+pub struct BroadcastReport {
+
+    pub progress: BroadcastProgress,
+
+    /// One result per recipient taken from the input, in input order.
+    pub results: Vec<BroadcastResult>,
+}
+
+impl BroadcastReport {
+    pub fn failures(&self) -> impl Iterator<Item = &BroadcastResult> {
+        self.results
+            .iter()
+            .filter(|result| matches!(result.outcome, BroadcastOutcome::Failed(_)))
+    }
+}
+
+
+pub struct Broadcast {
+    client: WhatsAppClient,
+    template_name: String,
+    language_code: String,
+    concurrency: usize,
+    rate_limiter: Arc<RateLimiter>,
+    messaging_limit: Option<usize>,
+}
+
+impl Broadcast {
+    /// Uses standard throughput and the client's retry settings.
+    pub fn new(client: WhatsAppClient, template_name: &str, language_code: &str) -> Self {
+        let rate_limiter = Arc::new(Self::rate_limiter(&client, STANDARD_THROUGHPUT));
+        Self {
+            client,
+            template_name: template_name.to_string(),
+            language_code: language_code.to_string(),
+            concurrency: DEFAULT_CONCURRENCY,
+            rate_limiter,
+            messaging_limit: None,
+        }
+    }
+
+    fn rate_limiter(client: &WhatsAppClient, messages_per_second: u32) -> RateLimiter {
+        let config = client.config();
+        RateLimiter::new(
+            messages_per_second.saturating_mul(60),
+            config.retry_after_too_many_requests,
+            config.max_retries,
+            config.retry_delay_ms,
+        )
+    }
+
+    /// Maximum number of sends in flight at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn messages_per_second(mut self, messages_per_second: u32) -> Self {
+        self.rate_limiter = Arc::new(Self::rate_limiter(&self.client, messages_per_second));
+        self
+    }
+
+    /// Sends to at most this many distinct recipients; the rest are skipped.
+    pub fn messaging_limit(mut self, limit: usize) -> Self {
+        self.messaging_limit = Some(limit);
+        self
+    }
+
+    /// Applies the phone number's throughput level and messaging limit tier.
+    ///
+    /// The tier limits business-initiated conversations over a rolling 24
+    /// hours, including those opened outside this broadcast, so the broadcast
+    /// is capped at the tier's limit minus `opened_last_24h`, the
+    /// conversations already opened in that time. A lower `messaging_limit`
+    /// set before is kept.
+    pub async fn with_phone_number_limits(mut self, opened_last_24h: usize) -> WhatsAppResult<Self> {
+        let phone_number = self.client.get_phone_number().await?;
+
+        if let Some(throughput) = phone_number.throughput {
+            if throughput.level == ThroughputLevel::High {
+                self = self.messages_per_second(HIGH_THROUGHPUT);
+            }
+        }
+        if let Some(limit) = phone_number.messaging_limit_tier.as_deref().and_then(messaging_tier_limit) {
+            let remaining = limit.saturating_sub(opened_last_24h);
+            self.messaging_limit = Some(self.messaging_limit.map_or(remaining, |limit| limit.min(remaining)));
+        }
+
+        Ok(self)
+    }
+
+    /// Starts sending to `recipients` in the background.
+    pub fn start<I>(self, recipients: I) -> BroadcastHandle
+    where
+        I: IntoIterator,
+        I::Item: Into<BroadcastRecipient> + 'static,
+        I::IntoIter: Send + 'static,
+    {
+        let recipients = recipients.into_iter();
+        let total = match recipients.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(lower),
+            _ => None,
+        };
+        self.spawn(Recipients::Iter(Box::new(recipients.map(Into::into))), total)
+    }
+
+    /// Starts sending to recipients as they arrive on `recipients`, until the
+    /// channel is closed.
+    pub fn start_channel(self, recipients: mpsc::Receiver<BroadcastRecipient>) -> BroadcastHandle {
+        self.spawn(Recipients::Channel(recipients), None)
+    }
+
+    fn spawn(self, recipients: Recipients, total: Option<usize>) -> BroadcastHandle {
+        let (progress, _) = watch::channel(BroadcastProgress::new(total));
+        let progress = Arc::new(progress);
+        let task = tokio::spawn(Arc::new(self).run(recipients, Arc::clone(&progress)));
+
+        BroadcastHandle { progress, task }
+    }
+
+    async fn run(self: Arc<Self>, mut recipients: Recipients, progress: Arc<watch::Sender<BroadcastProgress>>) -> BroadcastReport {
+        let mut state = progress.subscribe();
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = JoinSet::new();
+        let mut seen = HashSet::new();
+        let mut index = 0;
+
+        loop {
+            let permit = match Arc::clone(&semaphore).acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => break,
+            };
+            if !wait_while_paused(&mut state).await {
+                break;
+            }
+            // A channel may stay idle for long; stop waiting on it once cancelled.
+            let recipient = tokio::select! {
+                biased;
+                _ = state.wait_for(|progress| progress.state == BroadcastState::Cancelled) => None,
+                recipient = recipients.next() => recipient,
+            };
+            let recipient = match recipient {
+                Some(recipient) => recipient,
+                None => break,
+            };
+            let position = index;
+            index += 1;
+
+            let key = wa_id_key(&recipient.to);
+            let skip = if seen.contains(&key) {
+                Some(SkipReason::Duplicate)
+            } else if self.messaging_limit.is_some_and(|limit| seen.len() >= limit) {
+                Some(SkipReason::MessagingLimitReached)
+            } else {
+                seen.insert(key);
+                None
+            };
+            if let Some(reason) = skip {
+                progress.send_modify(|progress| progress.skipped += 1);
+                results.lock().unwrap().push(BroadcastResult {
+                    index: position,
+                    to: recipient.to,
+                    outcome: BroadcastOutcome::Skipped(reason),
+                });
+                continue;
+            }
+
+            progress.send_modify(|progress| progress.in_flight += 1);
+
+            let broadcast = Arc::clone(&self);
+            let results = Arc::clone(&results);
+            let progress = Arc::clone(&progress);
+            let mut state = state.clone();
+            tasks.spawn(async move {
+                let to = recipient.to.clone();
+                let outcome = broadcast.send(recipient, &mut state).await;
+
+                progress.send_modify(|progress| {
+                    progress.in_flight -= 1;
+                    match outcome {
+                        BroadcastOutcome::Sent { .. } => progress.sent += 1,
+                        BroadcastOutcome::Failed(_) => progress.failed += 1,
+                        BroadcastOutcome::Unconfirmed(_) => progress.unconfirmed += 1,
+                        BroadcastOutcome::Skipped(_) => progress.skipped += 1,
+                    }
+                });
+                results.lock().unwrap().push(BroadcastResult { index: position, to, outcome });
+                drop(permit);
+            });
+
+            while tasks.try_join_next().is_some() {}
+        }
+
+        while tasks.join_next().await.is_some() {}
+
+        progress.send_modify(|progress| {
+            if progress.state != BroadcastState::Cancelled {
+                progress.state = BroadcastState::Finished;
+            }
+        });
+
+        let mut results = std::mem::take(&mut *results.lock().unwrap());
+        results.sort_by_key(|result| result.index);
+
+        BroadcastReport {
+            progress: progress.borrow().clone(),
+            results,
+        }
+    }
+
+    async fn send(&self, recipient: BroadcastRecipient, state: &mut watch::Receiver<BroadcastProgress>) -> BroadcastOutcome {
+        let message = SendTemplateMessage {
+            to: recipient.to,
+            template_name: self.template_name.clone(),
+            language_code: self.language_code.clone(),
+            components: recipient.components,
+            context: None,
+        };
+
+        let mut attempt = 0;
+        loop {
+            self.rate_limiter.acquire().await;
+            if !wait_while_paused(state).await {
+                return BroadcastOutcome::Skipped(SkipReason::Cancelled);
+            }
+
+            match self.client.send_template_message(message.clone()).await {
+                Ok(response) => {
+                    return BroadcastOutcome::Sent {
+                        message_id: response.messages.into_iter().next().map(|m| m.id).unwrap_or_default(),
+                    }
+                }
+                Err(error) if error.is_unconfirmed() => return BroadcastOutcome::Unconfirmed(error),
+                Err(error) => match self.rate_limiter.retry_delay(attempt, &error) {
+                    Some(delay) => {
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                    }
+                    None => return BroadcastOutcome::Failed(error),
+                },
+            }
+        }
+    }
+}
+
+
+/// Controls a running broadcast. Dropping the handle does not stop it.
+pub struct BroadcastHandle {
+    progress: Arc<watch::Sender<BroadcastProgress>>,
+    task: JoinHandle<BroadcastReport>,
+}
+
+impl BroadcastHandle {
+    pub fn pause(&self) {
+        self.transition(BroadcastState::Running, BroadcastState::Paused);
+    }
+
+    pub fn resume(&self) {
+        self.transition(BroadcastState::Paused, BroadcastState::Running);
+    }
+
+    /// Stops taking recipients; sends already in flight complete.
+    pub fn cancel(&self) {
+        self.progress.send_if_modified(|progress| match progress.state {
+            BroadcastState::Running | BroadcastState::Paused => {
+                progress.state = BroadcastState::Cancelled;
+                true
+            }
+            _ => false,
+        });
+    }
+
+    fn transition(&self, from: BroadcastState, to: BroadcastState) {
+        self.progress.send_if_modified(|progress| {
+            let changed = progress.state == from;
+            if changed {
+                progress.state = to;
+            }
+            changed
+        });
+    }
+
+    pub fn progress(&self) -> BroadcastProgress {
+        self.progress.borrow().clone()
+    }
+
+    /// Receives every progress change, e.g. to report it as it happens.
+    pub fn subscribe(&self) -> watch::Receiver<BroadcastProgress> {
+        self.progress.subscribe()
+    }
+
+    /// Waits for the broadcast to finish or, once cancelled, for its in-flight sends.
+    pub async fn join(self) -> WhatsAppResult<BroadcastReport> {
+        self.task
+            .await
+            .map_err(|error| WhatsAppError::Other(format!("broadcast task failed: {}", error)))
+    }
+}
+
+
+enum Recipients {
+    Iter(Box<dyn Iterator<Item = BroadcastRecipient> + Send>),
+    Channel(mpsc::Receiver<BroadcastRecipient>),
+}
+
+impl Recipients {
+    async fn next(&mut self) -> Option<BroadcastRecipient> {
+        match self {
+            Self::Iter(recipients) => recipients.next(),
+            Self::Channel(recipients) => recipients.recv().await,
+        }
+    }
+}
+
+/// Waits until the broadcast is not paused; `false` once it is cancelled.
+async fn wait_while_paused(state: &mut watch::Receiver<BroadcastProgress>) -> bool {
+    match state.wait_for(|progress| progress.state != BroadcastState::Paused).await {
+        Ok(progress) => progress.state != BroadcastState::Cancelled,
+        Err(_) => false,
+    }
+}
+
+/// Distinct users a messaging limit tier allows per 24 hours; `None` if unlimited.
+fn messaging_tier_limit(tier: &str) -> Option<usize> {
+    match tier {
+        "TIER_50" => Some(50),
+        "TIER_250" => Some(250),
+        "TIER_1K" => Some(1_000),
+        "TIER_2K" => Some(2_000),
+        "TIER_10K" => Some(10_000),
+        "TIER_100K" => Some(100_000),
+        _ => None,
+    }
+}
@@ -0,0 +1,521 @@
+//! Business Manager client for WhatsApp Business Account administration
+//!
+//! Unlike `WhatsAppClient`, which acts on a single phone number, this client acts
+//! on a Business Manager and the WhatsApp Business Accounts it owns or manages
+//! on behalf of its clients.
+
+use hmac::{Hmac, Mac};
+use reqwest::{Client as HttpClient, header};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sha2::Sha256;
+
+use crate::client::{execute_request, validate_pin, PHONE_NUMBER_FIELDS};
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::types::*;
+
+const WABA_FIELDS: &str =
+    "id,name,currency,timezone_id,message_template_namespace,account_review_status,business_verification_status";
+
+
+#[derive(Clone, Debug)]
+pub struct BusinessClientConfig {
+
+    pub access_token: String,
+
+    pub business_id: String,
+
+    pub version: String,
+
+    /// Needed for Embedded Signup code exchange.
+    pub app_id: Option<String>,
+
+    /// Needed to generate system user tokens, which must carry an `appsecret_proof`,
+    /// and for Embedded Signup code exchange.
+    pub app_secret: Option<String>,
+
+    /// Two-step verification PIN set when registering numbers onboarded through Embedded Signup.
+    pub registration_pin: Option<String>,
+}
+
+impl Default for BusinessClientConfig {
+    fn default() -> Self {
+        Self {
+            access_token: String::new(),
+            business_id: String::new(),
+            version: "v22.0".to_string(),
+            app_id: None,
+            app_secret: None,
+            registration_pin: None,
+        }
+    }
+}
+
+
+#[derive(Clone)]
+pub struct BusinessClient {
+    config: BusinessClientConfig,
+    http_client: HttpClient,
+    base_url: String,
+}
+
+impl BusinessClient {
+
+    pub fn new(config: BusinessClientConfig) -> Self {
+        let mut headers = header::HeaderMap::new();
+        let auth_value = format!("Bearer {}", config.access_token);
+        headers.insert(
+            header::AUTHORIZATION,
+            header::HeaderValue::from_str(&auth_value).unwrap(),
+        );
+
+        let http_client = HttpClient::builder()
+            .default_headers(headers)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        let base_url = format!("https://graph.facebook.com/{}", config.version);
+
+        Self {
+            config,
+            http_client,
+            base_url,
+        }
+    }
+
+    /// Sends requests to `base_url` instead of the Graph API, e.g. a proxy or
+    /// a mock server in tests. It must include the API version.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn get<R: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> WhatsAppResult<R> {
+        let request = self.http_client.get(self.url(path)).query(query);
+        execute_request(request).await
+    }
+
+    async fn post<R: DeserializeOwned>(&self, path: &str, params: &[(&str, String)]) -> WhatsAppResult<R> {
+        let request = self.http_client.post(self.url(path)).form(params);
+        execute_request(request).await
+    }
+
+    fn required<'a>(value: &'a Option<String>, name: &str) -> WhatsAppResult<&'a str> {
+        value
+            .as_deref()
+            .ok_or_else(|| WhatsAppError::MissingField(name.to_string()))
+    }
+
+    /// HMAC-SHA256 of the access token keyed with the app secret.
+    fn appsecret_proof(&self) -> WhatsAppResult<String> {
+        let app_secret = Self::required(&self.config.app_secret, "app_secret")?;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(app_secret.as_bytes())
+            .map_err(|e| WhatsAppError::Other(e.to_string()))?;
+        mac.update(self.config.access_token.as_bytes());
+
+        Ok(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    pub async fn get_business_info(&self) -> WhatsAppResult<BusinessInfo> {
+        let path = format!("/{}", self.config.business_id);
+        self.get(&path, &[("fields", "id,name,verification_status,timezone_id".to_string())])
+            .await
+    }
+
+    /// Creates a client business owned by this Business Manager.
+    pub async fn create_business(&self, params: CreateBusiness) -> WhatsAppResult<CreateBusinessResponse> {
+        let path = format!("/{}/owned_businesses", self.config.business_id);
+        let mut form = vec![("name", params.name), ("vertical", params.vertical)];
+        if let Some(timezone_id) = params.timezone_id {
+            form.push(("timezone_id", timezone_id.to_string()));
+        }
+        self.post(&path, &form).await
+    }
+
+    pub async fn get_whatsapp_business_accounts(
+        &self,
+        assets: GetBusinessAssets,
+        params: WhatsAppBusinessAccountParams,
+    ) -> WhatsAppResult<BusinessAssetsResponse> {
+        let path = format!("/{}/{}", self.config.business_id, assets.edge());
+        let mut query = vec![("fields", WABA_FIELDS.to_string())];
+        if let Some(limit) = params.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        if let Some(after) = params.after {
+            query.push(("after", after));
+        }
+        self.get(&path, &query).await
+    }
+
+    pub async fn get_owned_whatsapp_business_accounts(&self) -> WhatsAppResult<BusinessAssetsResponse> {
+        self.get_whatsapp_business_accounts(GetBusinessAssets::Owned, WhatsAppBusinessAccountParams::default())
+            .await
+    }
+
+    pub async fn get_client_whatsapp_business_accounts(&self) -> WhatsAppResult<BusinessAssetsResponse> {
+        self.get_whatsapp_business_accounts(GetBusinessAssets::Client, WhatsAppBusinessAccountParams::default())
+            .await
+    }
+
+    pub async fn get_whatsapp_business_account(&self, waba_id: &str) -> WhatsAppResult<WhatsAppBusinessAccountResponse> {
+        let path = format!("/{}", waba_id);
+        self.get(&path, &[("fields", WABA_FIELDS.to_string())]).await
+    }
+
+    pub async fn get_phone_numbers(&self, waba_id: &str) -> WhatsAppResult<BusinessPhoneNumbersResponse> {
+        let path = format!("/{}/phone_numbers", waba_id);
+        self.get(&path, &[("fields", PHONE_NUMBER_FIELDS.to_string())]).await
+    }
+
+    pub async fn get_system_users(&self) -> WhatsAppResult<SystemUsersResponse> {
+        let path = format!("/{}/system_users", self.config.business_id);
+        self.get(&path, &[]).await
+    }
+
+    pub async fn create_system_user(&self, params: CreateSystemUser) -> WhatsAppResult<CreateSystemUserResponse> {
+        let path = format!("/{}/system_users", self.config.business_id);
+        let role = serde_json::to_value(params.role)?
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        self.post(&path, &[("name", params.name), ("role", role)]).await
+    }
+
+    /// Generates an access token for a system user.
+    ///
+    /// Requires `app_secret` in the config, as the request must carry an `appsecret_proof`.
+    pub async fn generate_system_user_token(&self, params: GenerateSystemUserToken) -> WhatsAppResult<AccessTokenResponse> {
+        if params.scopes.is_empty() {
+            return Err(WhatsAppError::ValidationError(
+                "At least one scope is required to generate a system user token".to_string(),
+            ));
+        }
+
+        let path = format!("/{}/access_tokens", params.system_user_id);
+        let mut form = vec![
+            ("business_app", params.app_id),
+            ("scope", params.scopes.join(",")),
+            ("appsecret_proof", self.appsecret_proof()?),
+        ];
+        if params.expires_in_60_days {
+            form.push(("set_token_expires_in_60_days", "true".to_string()));
+        }
+        self.post(&path, &form).await
+    }
+
+    /// Assigns a business user or system user to a WhatsApp Business Account with the given tasks.
+    pub async fn assign_user_to_whatsapp_business_account(
+        &self,
+        waba_id: &str,
+        params: AssignUserToWhatsAppBusiness,
+    ) -> WhatsAppResult<SuccessResponse> {
+        if params.tasks.is_empty() {
+            return Err(WhatsAppError::ValidationError(
+                "At least one task is required to assign a user".to_string(),
+            ));
+        }
+
+        let path = format!("/{}/assigned_users", waba_id);
+        let tasks = serde_json::to_string(&params.tasks)?;
+        self.post(&path, &[("user", params.user), ("tasks", tasks)]).await
+    }
+
+    pub async fn remove_user_from_whatsapp_business_account(&self, waba_id: &str, user: &str) -> WhatsAppResult<SuccessResponse> {
+        let path = format!("/{}/assigned_users", waba_id);
+        let request = self
+            .http_client
+            .delete(self.url(&path))
+            .query(&[("user", user)]);
+        execute_request(request).await
+    }
+
+    /// Exchanges the code returned by Embedded Signup for a business integration token.
+    pub async fn exchange_embedded_signup_code(&self, code: &str) -> WhatsAppResult<AccessTokenResponse> {
+        let app_id = Self::required(&self.config.app_id, "app_id")?;
+        let app_secret = Self::required(&self.config.app_secret, "app_secret")?;
+
+        self.get(
+            "/oauth/access_token",
+            &[
+                ("client_id", app_id.to_string()),
+                ("client_secret", app_secret.to_string()),
+                ("code", code.to_string()),
+            ],
+        )
+        .await
+    }
+
+    pub async fn get_subscribed_apps(&self, waba_id: &str) -> WhatsAppResult<SubscribedAppsResponse> {
+        let path = format!("/{}/subscribed_apps", waba_id);
+        self.get(&path, &[]).await
+    }
+
+    /// Subscribes this app to the webhooks of a WhatsApp Business Account.
+    ///
+    /// `access_token` overrides the client's token, e.g. with a business integration token.
+    pub async fn subscribe_app_to_waba(
+        &self,
+        waba_id: &str,
+        params: SubscribeApp,
+        access_token: Option<&str>,
+    ) -> WhatsAppResult<SuccessResponse> {
+        match (&params.override_callback_uri, &params.verify_token) {
+            (Some(uri), Some(_)) => Self::validate_callback_uri(uri)?,
+            (None, None) => {}
+            _ => {
+                return Err(WhatsAppError::ValidationError(
+                    "override_callback_uri and verify_token must be set together".to_string(),
+                ))
+            }
+        }
+
+        let path = format!("/{}/subscribed_apps", waba_id);
+        let mut request = self.http_client.post(self.url(&path)).json(&params);
+        if let Some(access_token) = access_token {
+            request = request.bearer_auth(access_token);
+        }
+        execute_request(request).await
+    }
+
+    /// Unsubscribes this app from the webhooks of a WhatsApp Business Account.
+    ///
+    /// `access_token` overrides the client's token, e.g. with a business integration token.
+    pub async fn unsubscribe_app_from_waba(
+        &self,
+        waba_id: &str,
+        access_token: Option<&str>,
+    ) -> WhatsAppResult<SuccessResponse> {
+        let path = format!("/{}/subscribed_apps", waba_id);
+        let mut request = self.http_client.delete(self.url(&path));
+        if let Some(access_token) = access_token {
+            request = request.bearer_auth(access_token);
+        }
+        execute_request(request).await
+    }
+
+    pub async fn get_phone_number_webhook_configuration(
+        &self,
+        phone_number_id: &str,
+    ) -> WhatsAppResult<PhoneNumberWebhookConfigurationResponse> {
+        let path = format!("/{}", phone_number_id);
+        self.get(&path, &[("fields", "id,webhook_configuration".to_string())]).await
+    }
+
+    /// Sends webhooks for one phone number to a different callback URL than its WABA.
+    pub async fn set_phone_number_webhook_override(
+        &self,
+        phone_number_id: &str,
+        params: WebhookOverride,
+    ) -> WhatsAppResult<SuccessResponse> {
+        Self::validate_callback_uri(&params.override_callback_uri)?;
+
+        let path = format!("/{}", phone_number_id);
+        let request = self
+            .http_client
+            .post(self.url(&path))
+            .json(&json!({ "webhook_configuration": params }));
+        execute_request(request).await
+    }
+
+    pub async fn remove_phone_number_webhook_override(&self, phone_number_id: &str) -> WhatsAppResult<SuccessResponse> {
+        let path = format!("/{}", phone_number_id);
+        let request = self
+            .http_client
+            .post(self.url(&path))
+            .json(&json!({ "webhook_configuration": { "override_callback_uri": "" } }));
+        execute_request(request).await
+    }
+
+    fn validate_callback_uri(uri: &str) -> WhatsAppResult<()> {
+        if !uri.starts_with("https://") {
+            return Err(WhatsAppError::ValidationError(format!(
+                "Callback URL must use https: {}",
+                uri
+            )));
+        }
+        Ok(())
+    }
+
+    /// Registers a phone number for Cloud API use with a two-step verification PIN.
+    ///
+    /// `access_token` overrides the client's token, e.g. with a business integration token.
+    pub async fn register_phone_number(
+        &self,
+        phone_number_id: &str,
+        pin: &str,
+        access_token: Option<&str>,
+    ) -> WhatsAppResult<SuccessResponse> {
+        validate_pin(pin)?;
+
+        let path = format!("/{}/register", phone_number_id);
+        let mut request = self
+            .http_client
+            .post(self.url(&path))
+            .json(&json!({ "messaging_product": "whatsapp", "pin": pin }));
+        if let Some(access_token) = access_token {
+            request = request.bearer_auth(access_token);
+        }
+        execute_request(request).await
+    }
+
+    /// Whether the phone number is already registered, i.e. on the Cloud API platform.
+    async fn is_registered_on_cloud_api(&self, phone_number_id: &str, access_token: Option<&str>) -> bool {
+        let path = format!("/{}", phone_number_id);
+        let mut request = self
+            .http_client
+            .get(self.url(&path))
+            .query(&[("fields", "id,platform_type")]);
+        if let Some(access_token) = access_token {
+            request = request.bearer_auth(access_token);
+        }
+        let phone_number: WhatsAppResult<PhoneNumberResponse> = execute_request(request).await;
+        phone_number.is_ok_and(|phone_number| phone_number.platform_type.as_deref() == Some("CLOUD_API"))
+    }
+
+    pub async fn get_extended_credit_lines(&self) -> WhatsAppResult<ExtendedCreditsResponse> {
+        let path = format!("/{}/extendedcredits", self.config.business_id);
+        self.get(&path, &[("fields", "id,legal_entity_name".to_string())]).await
+    }
+
+    /// Shares the business' first credit line with a customer's WhatsApp Business Account.
+    ///
+    /// The WABA's own currency is used when `currency` is `None`. A WABA already
+    /// funded by the credit line gets its existing allocation back, so a retry
+    /// after a lost response does not allocate a second one.
+    pub async fn share_credit_line(&self, waba_id: &str, currency: Option<&str>) -> WhatsAppResult<CreditLineAllocationResponse> {
+        let credit_line = self
+            .get_extended_credit_lines()
+            .await?
+            .data
+            .into_iter()
+            .next()
+            .ok_or_else(|| WhatsAppError::Other("Business has no extended credit line to share".to_string()))?;
+
+        if let Some(allocation) = self.credit_line_allocation(&credit_line.id, waba_id).await? {
+            return Ok(allocation);
+        }
+
+        let currency = match currency {
+            Some(currency) => currency.to_string(),
+            None => self
+                .get_whatsapp_business_account(waba_id)
+                .await?
+                .currency
+                .ok_or_else(|| WhatsAppError::MissingField("currency".to_string()))?,
+        };
+
+        let path = format!("/{}/whatsapp_credit_sharing_and_attach", credit_line.id);
+        let request = self
+            .http_client
+            .post(self.url(&path))
+            .query(&[("waba_id", waba_id), ("waba_currency", currency.as_str())]);
+        execute_request(request).await
+    }
+
+    /// The allocation of `credit_line_id` that funds `waba_id`, if it was already shared.
+    async fn credit_line_allocation(
+        &self,
+        credit_line_id: &str,
+        waba_id: &str,
+    ) -> WhatsAppResult<Option<CreditLineAllocationResponse>> {
+        let waba: WhatsAppBusinessAccountResponse =
+            self.get(&format!("/{}", waba_id), &[("fields", "id,primary_funding_id".to_string())]).await?;
+        let funding_id = match waba.primary_funding_id {
+            Some(funding_id) => funding_id,
+            None => return Ok(None),
+        };
+
+        let path = format!("/{}/owning_credit_allocation_configs", credit_line_id);
+        let configs: CreditAllocationConfigsResponse =
+            self.get(&path, &[("fields", "id,receiving_credential".to_string())]).await?;
+        Ok(configs
+            .data
+            .into_iter()
+            .find(|config| config.receiving_credential.as_ref().is_some_and(|credential| credential.id == funding_id))
+            .map(|config| CreditLineAllocationResponse {
+                allocation_config_id: config.id,
+                waba_id: waba_id.to_string(),
+            }))
+    }
+
+    /// Runs every Embedded Signup step for a newly onboarded customer.
+    ///
+    /// On failure, use `continue_embedded_signup` with a progress value to retry
+    /// only the remaining steps.
+    pub async fn complete_embedded_signup(
+        &self,
+        code: &str,
+        waba_id: &str,
+        phone_number_id: &str,
+    ) -> WhatsAppResult<EmbeddedSignupProgress> {
+        let mut progress = EmbeddedSignupProgress::new(code, waba_id, phone_number_id);
+        self.continue_embedded_signup(&mut progress).await?;
+        Ok(progress)
+    }
+
+    /// Runs the Embedded Signup steps that `progress` does not record as done.
+    ///
+    /// `progress` is updated after each step, so on error it reflects exactly what
+    /// succeeded and can be stored and passed back in later.
+    pub async fn continue_embedded_signup(&self, progress: &mut EmbeddedSignupProgress) -> WhatsAppResult<()> {
+        if progress.business_token.is_none() {
+            let token = self.exchange_embedded_signup_code(&progress.code).await?;
+            progress.business_token = Some(token.access_token);
+        }
+        let business_token = progress.business_token.clone();
+
+        if !progress.app_subscribed {
+            self.subscribe_app_to_waba(&progress.waba_id, SubscribeApp::default(), business_token.as_deref())
+                .await?;
+            progress.app_subscribed = true;
+        }
+
+        if !progress.phone_number_registered {
+            let pin = Self::required(&self.config.registration_pin, "registration_pin")?;
+            let registered = self
+                .register_phone_number(&progress.phone_number_id, pin, business_token.as_deref())
+                .await;
+            if let Err(error) = registered {
+                // A retry after a lost response finds the number already registered.
+                if !self
+                    .is_registered_on_cloud_api(&progress.phone_number_id, business_token.as_deref())
+                    .await
+                {
+                    return Err(error);
+                }
+            }
+            progress.phone_number_registered = true;
+        }
+
+        if progress.credit_line_allocation_id.is_none() {
+            let allocation = self.share_credit_line(&progress.waba_id, None).await?;
+            progress.credit_line_allocation_id = Some(allocation.allocation_config_id);
+        }
+
+        Ok(())
+    }
+}
+
+
+pub fn create_business_client(
+    access_token: &str,
+    business_id: &str,
+    version: Option<&str>,
+) -> BusinessClient {
+    let config = BusinessClientConfig {
+        access_token: access_token.to_string(),
+        business_id: business_id.to_string(),
+        version: version.unwrap_or("v22.0").to_string(),
+        app_id: None,
+        app_secret: None,
+        registration_pin: None,
+    };
+
+    BusinessClient::new(config)
+}
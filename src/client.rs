@@ -2,16 +2,38 @@
 //!
 //! This module provides the main client for interacting with the WhatsApp Cloud API.
 
-use reqwest::{Client as HttpClient, header};
+use reqwest::{Client as HttpClient, RequestBuilder, header, multipart};
 use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
 use std::sync::Arc;
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 use std::path::Path;
 
+use crate::broadcast::Broadcast;
+use crate::conversation_window::{ConversationWindow, WindowPolicy};
 use crate::error::{WhatsAppError, WhatsAppResult, ErrorHandler};
+use crate::flows::FlowJson;
 use crate::rate_limiter::RateLimiter;
+use crate::template_cache::TemplateCache;
+use crate::typing::{TypingGuard, TypingRegistry, TYPING_REFRESH_INTERVAL};
 use crate::types::*;
+use crate::util::{split_text, PhoneNumber};
+use crate::validation::{Validate, MAX_TEXT_BODY};
+
+const BUSINESS_PROFILE_FIELDS: &str =
+    "about,address,description,email,profile_picture_url,websites,vertical";
+
+/// Phone number fields read by both clients.
+pub(crate) const PHONE_NUMBER_FIELDS: &str =
+    "id,display_phone_number,verified_name,quality_rating,throughput,messaging_limit_tier,code_verification_status,name_status,new_name_status,platform_type";
+
+const PHONE_NUMBER_STATUS_FIELDS: &str =
+    "id,verified_name,name_status,new_name_status,code_verification_status";
+
+const FLOW_FIELDS: &str =
+    "id,name,status,categories,validation_errors,json_version,data_api_version,endpoint_uri";
 
 
 #[derive(Clone, Debug)]
@@ -55,9 +77,30 @@ pub struct WhatsAppClient {
     config: ClientConfig,
     http_client: HttpClient,
     rate_limiter: Arc<RateLimiter>,
+    template_cache: Arc<TemplateCache>,
+    conversation_window: Option<Arc<ConversationWindow>>,
+    typing: Arc<TypingRegistry>,
+    callback_data: Option<String>,
     base_url: String,
 }
 
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorResponse {
+    error: ApiErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+    message: String,
+    #[serde(rename = "type", default)]
+    error_type: String,
+    code: i32,
+    error_subcode: Option<i32>,
+    #[serde(default)]
+    fbtrace_id: String,
+}
+
 impl WhatsAppClient {
 
     pub fn new(config: ClientConfig) -> Self {
@@ -86,6 +129,10 @@ impl WhatsAppClient {
             config,
             http_client,
             rate_limiter,
+            template_cache: Arc::new(TemplateCache::new()),
+            conversation_window: None,
+            typing: Arc::new(TypingRegistry::default()),
+            callback_data: None,
             base_url,
         }
     }
@@ -103,6 +150,669 @@ impl WhatsAppClient {
     fn get_media_url(&self) -> String {
         format!("/{}/media", self.config.phone_number_id)
     }
+
+    fn get_business_account_id(&self) -> WhatsAppResult<&str> {
+        self.config
+            .business_account_id
+            .as_deref()
+            .ok_or_else(|| WhatsAppError::MissingField("business_account_id".to_string()))
+    }
+
+    fn get_templates_url(&self) -> WhatsAppResult<String> {
+        Ok(format!("/{}/message_templates", self.get_business_account_id()?))
+    }
+
+    fn get_flows_url(&self) -> WhatsAppResult<String> {
+        Ok(format!("/{}/flows", self.get_business_account_id()?))
+    }
+
+    fn get_business_profile_url(&self) -> String {
+        format!("/{}/whatsapp_business_profile", self.config.phone_number_id)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn execute<R: DeserializeOwned>(&self, request: RequestBuilder) -> WhatsAppResult<R> {
+        execute_request(request).await
+    }
+
+    fn with_messaging_product<T: Serialize>(body: &T) -> WhatsAppResult<Value> {
+        let mut value = serde_json::to_value(body)?;
+        if let Value::Object(map) = &mut value {
+            map.insert("messaging_product".to_string(), json!("whatsapp"));
+        }
+        Ok(value)
+    }
+
+    async fn send_message(
+        &self,
+        to: &str,
+        context: Option<&MessageContext>,
+        message_type: &str,
+        content: Value,
+    ) -> WhatsAppResult<SendMessageResponse> {
+        let to = PhoneNumber::parse(to)?;
+        self.typing.stop(to.as_str());
+
+        let mut payload = json!({
+            "messaging_product": "whatsapp",
+            "recipient_type": "individual",
+            "to": to.as_str(),
+            "type": message_type,
+        });
+        if let Some(context) = context {
+            payload["context"] = serde_json::to_value(context)?;
+        }
+        if let Some(callback_data) = &self.callback_data {
+            payload["biz_opaque_callback_data"] = json!(callback_data);
+        }
+        payload[message_type] = content;
+
+        let request = self.http_client.post(self.url(&self.get_messages_url())).json(&payload);
+        self.execute(request).await
+    }
+
+    /// Sends requests to `base_url` instead of the Graph API, e.g. a proxy or
+    /// a mock server in tests. It must include the API version.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub(crate) fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    pub(crate) fn rate_limiter(&self) -> &Arc<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    /// Returns a client that attaches `data` to every message it sends.
+    ///
+    /// The API echoes it in the message's status webhooks as
+    /// `biz_opaque_callback_data`, which ties statuses to the sender's own records.
+    pub fn with_callback_data(&self, data: &str) -> Self {
+        let mut client = self.clone();
+        client.callback_data = Some(data.to_string());
+        client
+    }
+
+    /// Prepares sending a template to many recipients; see `Broadcast`.
+    pub fn broadcast(&self, template_name: &str, language_code: &str) -> Broadcast {
+        Broadcast::new(self.clone(), template_name, language_code)
+    }
+
+    pub fn template_cache(&self) -> &Arc<TemplateCache> {
+        &self.template_cache
+    }
+
+    /// Checks free-form messages against the customer service window before sending.
+    ///
+    /// The window must be fed inbound messages, e.g. by passing every webhook
+    /// event to `ConversationWindow::handle_webhook_event`.
+    pub fn with_conversation_window(mut self, window: Arc<ConversationWindow>) -> Self {
+        self.conversation_window = Some(window);
+        self
+    }
+
+    pub fn conversation_window(&self) -> Option<&Arc<ConversationWindow>> {
+        self.conversation_window.as_ref()
+    }
+
+    /// Applies the window policy to a free-form message to `to`.
+    ///
+    /// Returns the fallback template's response when it was sent instead, in
+    /// which case the free-form message must not be sent.
+    async fn enforce_window(&self, to: &str) -> WhatsAppResult<Option<SendMessageResponse>> {
+        let window = match &self.conversation_window {
+            Some(window) if !window.is_open(to)? => window,
+            _ => return Ok(None),
+        };
+
+        match window.policy() {
+            WindowPolicy::Reject => window.check(to).map(|_| None),
+            WindowPolicy::FallbackTemplate(template) => {
+                let mut response = self
+                    .send_template_message(SendTemplateMessage {
+                        to: to.to_string(),
+                        template_name: template.template_name.clone(),
+                        language_code: template.language_code.clone(),
+                        components: template.components.clone(),
+                        context: None,
+                    })
+                    .await?;
+                response.fallback_template = Some(template.template_name.clone());
+                Ok(Some(response))
+            }
+        }
+    }
+
+    pub async fn send_text_message(&self, message: SendTextMessage) -> WhatsAppResult<SendMessageResponse> {
+        message.validate()?;
+        if let Some(response) = self.enforce_window(&message.to).await? {
+            return Ok(response);
+        }
+
+        self.post_text(message).await
+    }
+
+    /// Sends a validated text message without checking the customer service window.
+    async fn post_text(&self, message: SendTextMessage) -> WhatsAppResult<SendMessageResponse> {
+        let mut text = json!({ "body": message.text });
+        if let Some(preview_url) = message.preview_url {
+            text["preview_url"] = json!(preview_url);
+        }
+
+        self.send_message(&message.to, message.context.as_ref(), "text", text).await
+    }
+
+    /// Sends text of any length, split into as many messages as needed.
+    ///
+    /// Parts are sent one after another, each once the previous one was accepted,
+    /// so they arrive in order. Returns the response to every sent part, or the
+    /// single response of the fallback template when one was sent instead. If
+    /// a part fails, the remaining parts are not sent and, once a part was
+    /// sent, the error is `WhatsAppError::PartiallySent` with the ids of the
+    /// parts sent before it. A reply context is attached to the first part only.
+    /// The customer service window is checked once, before the first part, so
+    /// a window closing midway never mixes parts with a fallback template.
+    pub async fn send_long_text(&self, message: SendTextMessage) -> WhatsAppResult<Vec<SendMessageResponse>> {
+        if let Some(response) = self.enforce_window(&message.to).await? {
+            return Ok(vec![response]);
+        }
+
+        let mut responses: Vec<SendMessageResponse> = Vec::new();
+
+        for part in split_text(&message.text, MAX_TEXT_BODY) {
+            let part = SendTextMessage {
+                to: message.to.clone(),
+                text: part,
+                preview_url: message.preview_url,
+                context: if responses.is_empty() { message.context.clone() } else { None },
+            };
+            let sent = match part.validate() {
+                Ok(()) => self.post_text(part).await,
+                Err(error) => Err(error),
+            };
+            match sent {
+                Ok(response) => responses.push(response),
+                Err(error) if responses.is_empty() => return Err(error),
+                Err(error) => {
+                    return Err(WhatsAppError::PartiallySent {
+                        message_ids: responses
+                            .into_iter()
+                            .flat_map(|response| response.messages)
+                            .map(|message| message.id)
+                            .collect(),
+                        error: Box::new(error),
+                    })
+                }
+            }
+        }
+
+        Ok(responses)
+    }
+
+    pub async fn send_media_message(&self, message: SendMediaMessage) -> WhatsAppResult<SendMessageResponse> {
+        message.validate()?;
+        if let Some(response) = self.enforce_window(&message.to).await? {
+            return Ok(response);
+        }
+
+        let mut media = json!({});
+        if let Some(media_id) = message.media_id {
+            media["id"] = json!(media_id);
+        }
+        if let Some(media_url) = message.media_url {
+            media["link"] = json!(media_url);
+        }
+        if let Some(caption) = message.caption {
+            media["caption"] = json!(caption);
+        }
+        if let Some(filename) = message.filename {
+            media["filename"] = json!(filename);
+        }
+
+        self.send_message(&message.to, message.context.as_ref(), message.media_type.as_str(), media).await
+    }
+
+    pub async fn send_location_message(&self, message: SendLocationMessage) -> WhatsAppResult<SendMessageResponse> {
+        message.validate()?;
+        if let Some(response) = self.enforce_window(&message.to).await? {
+            return Ok(response);
+        }
+
+        let mut location = json!({
+            "latitude": message.latitude,
+            "longitude": message.longitude,
+        });
+        if let Some(name) = message.name {
+            location["name"] = json!(name);
+        }
+        if let Some(address) = message.address {
+            location["address"] = json!(address);
+        }
+
+        self.send_message(&message.to, message.context.as_ref(), "location", location).await
+    }
+
+    pub async fn send_interactive_message(&self, message: SendInteractiveMessage) -> WhatsAppResult<SendMessageResponse> {
+        message.validate()?;
+        if let Some(response) = self.enforce_window(&message.to).await? {
+            return Ok(response);
+        }
+
+        let interactive = serde_json::to_value(&message.interactive)?;
+        self.send_message(&message.to, message.context.as_ref(), "interactive", interactive).await
+    }
+
+    /// Sends a Flow message; build one with `Interactive::flow` for finer control.
+    pub async fn send_flow_message(&self, params: InteractiveFlowParams) -> WhatsAppResult<SendMessageResponse> {
+        self.send_interactive_message(params.into()).await
+    }
+
+    /// Asks the user to share their location.
+    pub async fn send_location_request(&self, params: messages::SendInteractiveLocationRequestParams) -> WhatsAppResult<SendMessageResponse> {
+        self.send_interactive_message(params.into()).await
+    }
+
+    /// Marks an inbound message as read, optionally showing a typing indicator.
+    pub async fn mark_message_as_read(&self, params: MarkMessageAsRead) -> WhatsAppResult<SuccessResponse> {
+        let mut body = Self::with_messaging_product(&params)?;
+        body["status"] = json!("read");
+
+        let request = self.http_client.post(self.url(&self.get_messages_url())).json(&body);
+        self.execute(request).await
+    }
+
+    /// Marks `message` as read and shows a typing indicator to its sender.
+    ///
+    /// The indicator is refreshed until the returned guard is dropped or a
+    /// message is sent to the sender through this client or one of its clones.
+    pub async fn start_typing(&self, message: &WebhookMessage) -> WhatsAppResult<TypingGuard> {
+        let params = MarkMessageAsRead {
+            message_id: message.id.clone(),
+            typing_indicator: Some(TypingIndicator::text()),
+        };
+        self.mark_message_as_read(params.clone()).await?;
+
+        let client = self.clone();
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TYPING_REFRESH_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if client.mark_message_as_read(params.clone()).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(self.typing.register(&message.from, task.abort_handle()))
+    }
+
+    pub async fn send_contact_message(&self, message: SendContactMessage) -> WhatsAppResult<SendMessageResponse> {
+        message.validate()?;
+        if let Some(response) = self.enforce_window(&message.to).await? {
+            return Ok(response);
+        }
+
+        let contacts = serde_json::to_value(&message.contacts)?;
+        self.send_message(&message.to, message.context.as_ref(), "contacts", contacts).await
+    }
+
+    /// Sends, changes or, with an empty emoji, removes a reaction.
+    ///
+    /// A closed customer service window always fails with
+    /// `ConversationWindowClosed`; a fallback template is never sent in place
+    /// of a reaction.
+    pub async fn send_reaction_message(&self, message: SendReactionMessage) -> WhatsAppResult<SendMessageResponse> {
+        message.validate()?;
+        if let Some(window) = &self.conversation_window {
+            window.check(&message.to)?;
+        }
+
+        let reaction = json!({
+            "message_id": message.message_id,
+            "emoji": message.emoji,
+        });
+        self.send_message(&message.to, None, "reaction", reaction).await
+    }
+
+    /// Reacts to a message with `emoji`, replacing any earlier reaction to it.
+    pub async fn react(&self, to: &str, message_id: &str, emoji: &str) -> WhatsAppResult<SendMessageResponse> {
+        let to = PhoneNumber::parse(to)?;
+        self.send_reaction_message(SendReactionMessage::new(to, message_id, emoji)).await
+    }
+
+    /// Removes the reaction to a message.
+    pub async fn unreact(&self, to: &str, message_id: &str) -> WhatsAppResult<SendMessageResponse> {
+        let to = PhoneNumber::parse(to)?;
+        self.send_reaction_message(SendReactionMessage::remove(to, message_id)).await
+    }
+
+    /// Sends any kind of message through its `send_*` method.
+    pub async fn send(&self, message: OutboundMessage) -> WhatsAppResult<SendMessageResponse> {
+        match message {
+            OutboundMessage::Text(message) => self.send_text_message(message).await,
+            OutboundMessage::Media(message) => self.send_media_message(message).await,
+            OutboundMessage::Location(message) => self.send_location_message(message).await,
+            OutboundMessage::Template(message) => self.send_template_message(message).await,
+            OutboundMessage::Interactive(message) => self.send_interactive_message(*message).await,
+            OutboundMessage::Contact(message) => self.send_contact_message(message).await,
+            OutboundMessage::Reaction(message) => self.send_reaction_message(message).await,
+        }
+    }
+
+    /// Sends a template message.
+    ///
+    /// Fails with a `ValidationError` without calling the API when the template
+    /// cache knows the template to be paused, rejected or otherwise unusable.
+    pub async fn send_template_message(&self, message: SendTemplateMessage) -> WhatsAppResult<SendMessageResponse> {
+        message.validate()?;
+        self.template_cache.ensure_usable(&message.template_name, &message.language_code)?;
+
+        let mut template = json!({
+            "name": message.template_name,
+            "language": { "code": message.language_code },
+        });
+        if let Some(components) = message.components {
+            template["components"] = serde_json::to_value(components)?;
+        }
+
+        self.send_message(&message.to, message.context.as_ref(), "template", template).await
+    }
+
+    /// Uploads a local file for use in media messages and returns its media id.
+    pub async fn upload_media(&self, params: UploadMedia) -> WhatsAppResult<UploadMediaResponse> {
+        let path = Path::new(&params.file_path);
+        let mut file = File::open(path)
+            .await
+            .map_err(|e| WhatsAppError::Other(format!("Cannot open {}: {}", params.file_path, e)))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .await
+            .map_err(|e| WhatsAppError::Other(format!("Cannot read {}: {}", params.file_path, e)))?;
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "file".to_string());
+        let part = multipart::Part::bytes(bytes)
+            .file_name(file_name)
+            .mime_str(&params.mime_type)?;
+        let form = multipart::Form::new()
+            .text("messaging_product", "whatsapp")
+            .text("type", params.mime_type.clone())
+            .part("file", part);
+
+        let request = self.http_client.post(self.url(&self.get_media_url())).multipart(form);
+        self.execute(request).await
+    }
+
+    /// Looks up the download URL of a media object, valid for five minutes.
+    pub async fn retrieve_media_url(&self, params: RetrieveMediaUrl) -> WhatsAppResult<RetrieveMediaUrlResponse> {
+        let request = self
+            .http_client
+            .get(self.url(&format!("/{}", params.media_id)))
+            .query(&[("phone_number_id", self.config.phone_number_id.as_str())]);
+        self.execute(request).await
+    }
+
+    /// Starts downloading media from a URL returned by `retrieve_media_url`.
+    ///
+    /// The body is left unread, so it can be streamed with `Response::chunk`.
+    pub async fn download_media(&self, url: &str) -> WhatsAppResult<reqwest::Response> {
+        let response = self.http_client.get(url).send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await?;
+        Err(api_error(status, &body))
+    }
+
+    pub async fn delete_media(&self, params: DeleteMedia) -> WhatsAppResult<SuccessResponse> {
+        let request = self
+            .http_client
+            .delete(self.url(&format!("/{}", params.media_id)))
+            .query(&[("phone_number_id", self.config.phone_number_id.as_str())]);
+        self.execute(request).await
+    }
+
+    /// Lists the templates of the business account and refreshes the template cache.
+    pub async fn get_templates(&self, params: GetTemplates) -> WhatsAppResult<GetTemplatesResponse> {
+        let request = self.http_client.get(self.url(&self.get_templates_url()?)).query(&params);
+        let response: GetTemplatesResponse = self.execute(request).await?;
+
+        self.template_cache.insert_all(&response.data);
+
+        Ok(response)
+    }
+
+    pub async fn create_template(&self, params: CreateTemplate) -> WhatsAppResult<CreateTemplateResponse> {
+        let request = self.http_client.post(self.url(&self.get_templates_url()?)).json(&params);
+        let response: CreateTemplateResponse = self.execute(request).await?;
+
+        self.template_cache.insert(&Template {
+            id: response.id.clone(),
+            name: params.name,
+            language: params.language,
+            status: response.status,
+            category: response.category,
+            components: params.components,
+            quality_score: None,
+        });
+
+        Ok(response)
+    }
+
+    pub async fn delete_template(&self, params: DeleteTemplate) -> WhatsAppResult<SuccessResponse> {
+        let request = self.http_client.delete(self.url(&self.get_templates_url()?)).query(&params);
+        let response: SuccessResponse = self.execute(request).await?;
+
+        if response.success {
+            match &params.hsm_id {
+                Some(hsm_id) => {
+                    self.template_cache.remove_by_id(hsm_id);
+                }
+                None => self.template_cache.remove_all(&params.name),
+            }
+        }
+
+        Ok(response)
+    }
+
+    pub async fn get_flows(&self) -> WhatsAppResult<GetFlowsResponse> {
+        let request = self
+            .http_client
+            .get(self.url(&self.get_flows_url()?))
+            .query(&[("fields", FLOW_FIELDS)]);
+        self.execute(request).await
+    }
+
+    pub async fn get_flow(&self, flow_id: &str) -> WhatsAppResult<Flow> {
+        let request = self
+            .http_client
+            .get(self.url(&format!("/{}", flow_id)))
+            .query(&[("fields", FLOW_FIELDS)]);
+        self.execute(request).await
+    }
+
+    pub async fn create_flow(&self, params: CreateFlow) -> WhatsAppResult<CreateFlowResponse> {
+        let request = self.http_client.post(self.url(&self.get_flows_url()?)).json(&params);
+        self.execute(request).await
+    }
+
+    pub async fn update_flow_metadata(&self, flow_id: &str, params: UpdateFlowMetadata) -> WhatsAppResult<SuccessResponse> {
+        let request = self.http_client.post(self.url(&format!("/{}", flow_id))).json(&params);
+        self.execute(request).await
+    }
+
+    /// Replaces the Flow JSON of a draft Flow.
+    ///
+    /// The upload succeeds even when the JSON has errors; they are listed in
+    /// `validation_errors` and block publishing until fixed.
+    pub async fn update_flow_json(&self, flow_id: &str, flow_json: &str) -> WhatsAppResult<UpdateFlowJsonResponse> {
+        let file = multipart::Part::text(flow_json.to_string())
+            .file_name("flow.json")
+            .mime_str("application/json")?;
+        let form = multipart::Form::new()
+            .text("name", "flow.json")
+            .text("asset_type", "FLOW_JSON")
+            .part("file", file);
+
+        let request = self
+            .http_client
+            .post(self.url(&format!("/{}/assets", flow_id)))
+            .multipart(form);
+        self.execute(request).await
+    }
+
+    /// Checks `flow_json` offline and uploads it when it has no violations.
+    pub async fn update_flow(&self, flow_id: &str, flow_json: &FlowJson) -> WhatsAppResult<UpdateFlowJsonResponse> {
+        flow_json.validate()?;
+
+        let flow_json = serde_json::to_string(flow_json)?;
+        self.update_flow_json(flow_id, &flow_json).await
+    }
+
+    pub async fn publish_flow(&self, flow_id: &str) -> WhatsAppResult<SuccessResponse> {
+        let request = self.http_client.post(self.url(&format!("/{}/publish", flow_id)));
+        self.execute(request).await
+    }
+
+    /// Stops a published Flow from being sent. This cannot be undone.
+    pub async fn deprecate_flow(&self, flow_id: &str) -> WhatsAppResult<SuccessResponse> {
+        let request = self.http_client.post(self.url(&format!("/{}/deprecate", flow_id)));
+        self.execute(request).await
+    }
+
+    /// Deletes a Flow; only drafts can be deleted.
+    pub async fn delete_flow(&self, flow_id: &str) -> WhatsAppResult<SuccessResponse> {
+        let request = self.http_client.delete(self.url(&format!("/{}", flow_id)));
+        self.execute(request).await
+    }
+
+    /// Gets a web preview URL of the Flow, optionally invalidating previously issued ones.
+    pub async fn get_flow_preview_url(&self, flow_id: &str, invalidate: bool) -> WhatsAppResult<FlowPreview> {
+        let fields = format!("preview.invalidate({})", invalidate);
+        let request = self
+            .http_client
+            .get(self.url(&format!("/{}", flow_id)))
+            .query(&[("fields", fields)]);
+        let response: FlowPreviewResponse = self.execute(request).await?;
+        Ok(response.preview)
+    }
+
+    pub async fn get_business_profile(&self) -> WhatsAppResult<BusinessProfile> {
+        let request = self
+            .http_client
+            .get(self.url(&self.get_business_profile_url()))
+            .query(&[("fields", BUSINESS_PROFILE_FIELDS)]);
+        let response: BusinessProfileResponse = self.execute(request).await?;
+
+        response
+            .data
+            .into_iter()
+            .next()
+            .ok_or_else(|| WhatsAppError::Other("Business profile response contained no data".to_string()))
+    }
+
+    pub async fn update_business_profile(&self, profile: BusinessProfile) -> WhatsAppResult<SuccessResponse> {
+        let body = Self::with_messaging_product(&profile)?;
+        let request = self.http_client.post(self.url(&self.get_business_profile_url())).json(&body);
+        self.execute(request).await
+    }
+
+    /// Lists the phone numbers of the business account with their quality rating and throughput tier.
+    pub async fn get_phone_numbers(&self) -> WhatsAppResult<RetrievePhoneNumbersResponse> {
+        let path = format!("/{}/phone_numbers", self.get_business_account_id()?);
+        let request = self
+            .http_client
+            .get(self.url(&path))
+            .query(&[("fields", PHONE_NUMBER_FIELDS)]);
+        self.execute(request).await
+    }
+
+    pub async fn get_phone_number(&self) -> WhatsAppResult<PhoneNumberResponse> {
+        let request = self
+            .http_client
+            .get(self.url(&self.get_phone_number_url()))
+            .query(&[("fields", PHONE_NUMBER_FIELDS)]);
+        self.execute(request).await
+    }
+
+    pub async fn update_phone_number_settings(&self, settings: UpdatePhoneNumberSettings) -> WhatsAppResult<SuccessResponse> {
+        let request = self.http_client.post(self.url(&self.get_phone_number_url())).json(&settings);
+        self.execute(request).await
+    }
+
+    /// Reads the display name review and code verification status of the phone number.
+    pub async fn get_phone_number_status(&self) -> WhatsAppResult<PhoneNumberStatusResponse> {
+        let request = self
+            .http_client
+            .get(self.url(&self.get_phone_number_url()))
+            .query(&[("fields", PHONE_NUMBER_STATUS_FIELDS)]);
+        self.execute(request).await
+    }
+
+    /// Sets or rotates the six digit two-step verification PIN.
+    pub async fn set_two_step_verification_pin(&self, pin: &str) -> WhatsAppResult<SuccessResponse> {
+        validate_pin(pin)?;
+
+        self.update_phone_number_settings(UpdatePhoneNumberSettings {
+            pin: Some(pin.to_string()),
+        })
+        .await
+    }
+
+    /// Submits a display name change for review.
+    ///
+    /// Progress is reported through `new_name_status` in `get_phone_number_status`.
+    pub async fn request_display_name_change(&self, new_display_name: &str) -> WhatsAppResult<SuccessResponse> {
+        if new_display_name.trim().is_empty() {
+            return Err(WhatsAppError::ValidationError(
+                "Display name must not be empty".to_string(),
+            ));
+        }
+
+        let request = self
+            .http_client
+            .post(self.url(&self.get_phone_number_url()))
+            .query(&[("new_display_name", new_display_name)]);
+        self.execute(request).await
+    }
+
+    pub async fn register_phone_number(&self, params: RegisterPhoneNumber) -> WhatsAppResult<SuccessResponse> {
+        validate_pin(&params.pin)?;
+
+        let path = format!("{}/register", self.get_phone_number_url());
+        let body = Self::with_messaging_product(&params)?;
+        let request = self.http_client.post(self.url(&path)).json(&body);
+        self.execute(request).await
+    }
+
+    pub async fn deregister_phone_number(&self, params: DeregisterPhoneNumber) -> WhatsAppResult<SuccessResponse> {
+        let path = format!("{}/deregister", self.get_phone_number_url());
+        let request = self.http_client.post(self.url(&path)).json(&params);
+        self.execute(request).await
+    }
+
+    pub async fn request_verification_code(&self, params: RequestVerificationCode) -> WhatsAppResult<SuccessResponse> {
+        let path = format!("{}/request_code", self.get_phone_number_url());
+        let request = self.http_client.post(self.url(&path)).json(&params);
+        self.execute(request).await
+    }
+
+    pub async fn verify_code(&self, params: VerifyCode) -> WhatsAppResult<SuccessResponse> {
+        let path = format!("{}/verify_code", self.get_phone_number_url());
+        let request = self.http_client.post(self.url(&path)).json(&params);
+        self.execute(request).await
+    }
     
     pub fn update_access_token(&mut self, access_token: String) {
         self.config.access_token = access_token.clone();
@@ -124,6 +834,46 @@ impl WhatsAppClient {
 }
 
 
+/// Fails unless `pin` is a six digit two-step verification PIN.
+pub(crate) fn validate_pin(pin: &str) -> WhatsAppResult<()> {
+    if pin.len() != 6 || !pin.chars().all(|c| c.is_ascii_digit()) {
+        return Err(WhatsAppError::ValidationError(
+            "Two-step verification PIN must be exactly 6 digits".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+
+/// Sends a Graph API request and decodes either the response or the API error.
+pub(crate) async fn execute_request<R: DeserializeOwned>(request: RequestBuilder) -> WhatsAppResult<R> {
+    let response = request.send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+
+    if status.is_success() {
+        return Ok(serde_json::from_str(&body)?);
+    }
+
+    Err(api_error(status, &body))
+}
+
+
+/// Decodes the API error in the body of a failed response.
+fn api_error(status: reqwest::StatusCode, body: &str) -> WhatsAppError {
+    match serde_json::from_str::<ApiErrorResponse>(body) {
+        Ok(ApiErrorResponse { error }) => ErrorHandler::enhance_error(
+            error.message,
+            error.error_type,
+            error.code,
+            error.error_subcode,
+            error.fbtrace_id,
+        ),
+        Err(_) => WhatsAppError::Other(format!("HTTP {}: {}", status, body)),
+    }
+}
+
+
 pub fn create_client(
     access_token: &str,
     phone_number_id: &str,
@@ -0,0 +1,206 @@
+//! Customer service window tracking
+//!
+//! Free-form messages can only be sent within 24 hours of the user's last
+//! inbound message; outside that window the API rejects them with error
+//! 131047 and only templates get through. [`ConversationWindow`] records
+//! inbound messages from webhooks so the client can tell before sending.
+//!
+//! Users with no recorded inbound message, e.g. after a restart with an
+//! in-memory store, are let through by default and left to the API to judge;
+//! `treat_unknown_as_closed` applies the policy to them as well.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::types::messages::Component;
+use crate::types::webhook::{WebhookChangeValue, WebhookEvent, WebhookMessage};
+use crate::util::phone::wa_id_key;
+
+/// Length of the customer service window.
+pub const CUSTOMER_SERVICE_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+
+/// Storage of the last inbound message time per user.
+///
+/// Times are Unix timestamps in seconds; users are keyed by `wa_id`, so
+/// `+1 555…` and `1555…` are the same user.
+pub trait WindowStore: Send + Sync {
+
+    fn last_inbound(&self, wa_id: &str) -> WhatsAppResult<Option<u64>>;
+
+    /// Records an inbound message. Older timestamps than the stored one are ignored.
+    fn record_inbound(&self, wa_id: &str, timestamp: u64) -> WhatsAppResult<()>;
+}
+
+
+#[derive(Debug, Default)]
+pub struct InMemoryWindowStore {
+    last_inbound: RwLock<HashMap<String, u64>>,
+}
+
+impl InMemoryWindowStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl WindowStore for InMemoryWindowStore {
+    fn last_inbound(&self, wa_id: &str) -> WhatsAppResult<Option<u64>> {
+        Ok(self.last_inbound.read().unwrap().get(wa_id).copied())
+    }
+
+    fn record_inbound(&self, wa_id: &str, timestamp: u64) -> WhatsAppResult<()> {
+        let mut last_inbound = self.last_inbound.write().unwrap();
+        let entry = last_inbound.entry(wa_id.to_string()).or_insert(timestamp);
+        *entry = (*entry).max(timestamp);
+        Ok(())
+    }
+}
+
+
+/// Template sent in place of a free-form message when the window is closed.
+#[derive(Debug, Clone)]
+pub struct FallbackTemplate {
+
+    pub template_name: String,
+
+    pub language_code: String,
+
+    pub components: Option<Vec<Component>>,
+}
+
+
+/// What the client does with a free-form message to a user whose window is closed.
+#[derive(Debug, Clone)]
+pub enum WindowPolicy {
+
+    /// Fail with `WhatsAppError::ConversationWindowClosed`.
+    Reject,
+
+    /// Send the template instead and return its response, with
+    /// `SendMessageResponse::fallback_template` set to the template's name.
+    FallbackTemplate(FallbackTemplate),
+}
+
+
+pub struct ConversationWindow {
+    store: Box<dyn WindowStore>,
+    policy: WindowPolicy,
+    duration: Duration,
+    unknown_closed: bool,
+}
+
+impl fmt::Debug for ConversationWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConversationWindow")
+            .field("policy", &self.policy)
+            .field("duration", &self.duration)
+            .field("unknown_closed", &self.unknown_closed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ConversationWindow {
+    fn default() -> Self {
+        Self::new(InMemoryWindowStore::new())
+    }
+}
+
+impl ConversationWindow {
+    pub fn new(store: impl WindowStore + 'static) -> Self {
+        Self {
+            store: Box::new(store),
+            policy: WindowPolicy::Reject,
+            duration: CUSTOMER_SERVICE_WINDOW,
+            unknown_closed: false,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: WindowPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_fallback_template(self, template: FallbackTemplate) -> Self {
+        self.with_policy(WindowPolicy::FallbackTemplate(template))
+    }
+
+    /// Overrides the window length, e.g. to leave a safety margin before it closes.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Applies the policy to users with no recorded inbound message too.
+    ///
+    /// Only use this with a store that outlives the process and has seen every
+    /// inbound message, or every reply to an unknown user is refused.
+    pub fn treat_unknown_as_closed(mut self, closed: bool) -> Self {
+        self.unknown_closed = closed;
+        self
+    }
+
+    pub fn policy(&self) -> &WindowPolicy {
+        &self.policy
+    }
+
+    /// Records every inbound message of a webhook event; other fields are ignored.
+    pub fn handle_webhook_event(&self, event: &WebhookEvent) -> WhatsAppResult<()> {
+        for change in event.changes() {
+            if let WebhookChangeValue::Messages(value) = &change.value {
+                for message in &value.messages {
+                    self.record_message(message)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn record_message(&self, message: &WebhookMessage) -> WhatsAppResult<()> {
+        match message.timestamp.parse() {
+            Ok(timestamp) => self.store.record_inbound(&wa_id_key(&message.from), timestamp),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Time of the user's last recorded inbound message.
+    pub fn last_inbound(&self, to: &str) -> WhatsAppResult<Option<u64>> {
+        self.store.last_inbound(&wa_id_key(to))
+    }
+
+    /// When the window with `to` closes, or `None` if no inbound message is known.
+    pub fn expires_at(&self, to: &str) -> WhatsAppResult<Option<u64>> {
+        Ok(self.last_inbound(to)?.map(|last| last + self.duration.as_secs()))
+    }
+
+    /// Whether free-form messages to `to` may be sent: the window is open, or
+    /// no inbound message is known and unknown users are not treated as closed.
+    pub fn is_open(&self, to: &str) -> WhatsAppResult<bool> {
+        Ok(match self.expires_at(to)? {
+            Some(expires_at) => now() < expires_at,
+            None => !self.unknown_closed,
+        })
+    }
+
+    /// Fails with `ConversationWindowClosed` when free-form messages to `to` would be rejected.
+    pub fn check(&self, to: &str) -> WhatsAppResult<()> {
+        if self.is_open(to)? {
+            Ok(())
+        } else {
+            Err(WhatsAppError::ConversationWindowClosed {
+                to: to.to_string(),
+                last_inbound: self.last_inbound(to)?,
+            })
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,682 @@
+//! State machines for conversational bots
+//!
+//! A [`Dialog`] keeps one [`Session`] per user, holding the state they are in
+//! and the answers collected so far. Each state lists transitions, tried in
+//! order against every inbound message: a [`Trigger`] matches text, a button,
+//! list or quick reply id, or a Flow completion, and the first match moves the
+//! user to the next state, running its [`DialogAction`]s and then the new
+//! state's entry actions. Actions send text and interactive messages or store
+//! the input in the session; the session is saved before anything is sent.
+//!
+//! A state can time out: the next message after the timeout, or a call to
+//! `Dialog::expire_sessions`, first takes the state's timeout transition.
+//!
+//! Feed the dialog every webhook event with `Dialog::handle_webhook_event`; it
+//! returns what happened, for work the actions don't cover, and which users
+//! it failed for without letting one failure hold up the others.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+use crate::client::WhatsAppClient;
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::types::messages::{Interactive, SendInteractiveMessage, SendTextMessage};
+use crate::types::webhook::{WebhookChangeValue, WebhookEvent, WebhookInteractive, WebhookMessage};
+use crate::util::phone::wa_id_key;
+
+
+/// A user's progress through a dialog.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session<S> {
+
+    pub state: S,
+
+    /// Inputs saved by `DialogAction::Store`, by key.
+    pub data: HashMap<String, String>,
+
+    /// Unix time of the last message or transition, in seconds.
+    pub last_activity: u64,
+}
+
+
+/// Storage of sessions, keyed by `wa_id`.
+pub trait SessionStore<S>: Send + Sync {
+
+    fn load(&self, wa_id: &str) -> WhatsAppResult<Option<Session<S>>>;
+
+    fn save(&self, wa_id: &str, session: Session<S>) -> WhatsAppResult<()>;
+
+    fn remove(&self, wa_id: &str) -> WhatsAppResult<()>;
+
+    /// Every stored session, for expiring timed out ones.
+    fn sessions(&self) -> WhatsAppResult<Vec<(String, Session<S>)>>;
+}
+
+
+#[derive(Debug)]
+pub struct InMemorySessionStore<S> {
+    sessions: RwLock<HashMap<String, Session<S>>>,
+}
+
+impl<S> Default for InMemorySessionStore<S> {
+    fn default() -> Self {
+        Self {
+            sessions: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl<S> InMemorySessionStore<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S: Clone + Send + Sync> SessionStore<S> for InMemorySessionStore<S> {
+    fn load(&self, wa_id: &str) -> WhatsAppResult<Option<Session<S>>> {
+        Ok(self.sessions.read().unwrap().get(wa_id).cloned())
+    }
+
+    fn save(&self, wa_id: &str, session: Session<S>) -> WhatsAppResult<()> {
+        self.sessions.write().unwrap().insert(wa_id.to_string(), session);
+        Ok(())
+    }
+
+    fn remove(&self, wa_id: &str) -> WhatsAppResult<()> {
+        self.sessions.write().unwrap().remove(wa_id);
+        Ok(())
+    }
+
+    fn sessions(&self) -> WhatsAppResult<Vec<(String, Session<S>)>> {
+        Ok(self
+            .sessions
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(wa_id, session)| (wa_id.clone(), session.clone()))
+            .collect())
+    }
+}
+
+
+/// What an inbound message said, as far as a dialog is concerned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogInput {
+
+    Text(String),
+
+    /// A reply button, list row or template quick reply.
+    Reply {
+        id: String,
+        title: String,
+    },
+
+    /// A completed Flow, with its `response_json`.
+    Flow {
+        flow_token: Option<String>,
+        response_json: String,
+    },
+
+    /// Any other kind of message, such as media or a location.
+    Other,
+}
+
+impl DialogInput {
+    pub fn from_message(message: &WebhookMessage) -> Self {
+        if let Some(text) = &message.text {
+            return Self::Text(text.body.clone());
+        }
+        if let Some(button) = &message.button {
+            return Self::Reply {
+                id: button.payload.clone(),
+                title: button.text.clone(),
+            };
+        }
+        match &message.interactive {
+            Some(WebhookInteractive::ButtonReply { button_reply: reply })
+            | Some(WebhookInteractive::ListReply { list_reply: reply }) => Self::Reply {
+                id: reply.id.clone(),
+                title: reply.title.clone(),
+            },
+            Some(WebhookInteractive::NfmReply { nfm_reply }) => Self::Flow {
+                flow_token: nfm_reply.flow_response().ok().and_then(|response| response.flow_token),
+                response_json: nfm_reply.response_json.clone(),
+            },
+            Some(WebhookInteractive::Unknown) | None => Self::Other,
+        }
+    }
+
+    /// The value `DialogAction::Store` saves: the text, reply id or Flow response.
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            Self::Reply { id, .. } => Some(id),
+            Self::Flow { response_json, .. } => Some(response_json),
+            Self::Other => None,
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+
+    /// Text equal to this, ignoring case and surrounding whitespace.
+    Text(String),
+
+    /// Text containing this, ignoring case.
+    Contains(String),
+
+    AnyText,
+
+    /// A reply button, list row or template quick reply with this id.
+    Reply(String),
+
+    AnyReply,
+
+    /// A completed Flow, optionally only the one sent with this flow token.
+    Flow(Option<String>),
+
+    Any,
+}
+
+impl Trigger {
+    pub fn text(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+
+    pub fn contains(text: &str) -> Self {
+        Self::Contains(text.to_string())
+    }
+
+    pub fn reply(id: &str) -> Self {
+        Self::Reply(id.to_string())
+    }
+
+    pub fn flow() -> Self {
+        Self::Flow(None)
+    }
+
+    pub fn flow_token(flow_token: &str) -> Self {
+        Self::Flow(Some(flow_token.to_string()))
+    }
+
+    pub fn matches(&self, input: &DialogInput) -> bool {
+        match (self, input) {
+            (Self::Any, _) => true,
+            (Self::Text(expected), DialogInput::Text(text)) => text.trim().eq_ignore_ascii_case(expected.trim()),
+            (Self::Contains(needle), DialogInput::Text(text)) => text.to_lowercase().contains(&needle.to_lowercase()),
+            (Self::AnyText, DialogInput::Text(_)) => true,
+            (Self::Reply(expected), DialogInput::Reply { id, .. }) => id == expected,
+            (Self::AnyReply, DialogInput::Reply { .. }) => true,
+            (Self::Flow(expected), DialogInput::Flow { flow_token, .. }) => {
+                expected.is_none() || expected == flow_token
+            }
+            _ => false,
+        }
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub enum DialogAction {
+
+    Text(String),
+
+    Interactive(Box<Interactive>),
+
+    /// Saves the value of the triggering input in the session under this key.
+    Store(String),
+
+    /// Removes all saved inputs from the session.
+    ClearData,
+}
+
+impl DialogAction {
+    pub fn text(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+
+    pub fn interactive(interactive: Interactive) -> Self {
+        Self::Interactive(Box::new(interactive))
+    }
+
+    pub fn store(key: &str) -> Self {
+        Self::Store(key.to_string())
+    }
+}
+
+
+#[derive(Debug, Clone)]
+struct Transition<S> {
+    trigger: Trigger,
+    /// `None` ends the dialog.
+    target: Option<S>,
+    actions: Vec<DialogAction>,
+}
+
+
+#[derive(Debug, Clone)]
+struct Timeout<S> {
+    after: Duration,
+    target: Option<S>,
+    actions: Vec<DialogAction>,
+}
+
+
+/// Transitions and actions of one state.
+#[derive(Debug, Clone)]
+pub struct StateConfig<S> {
+    transitions: Vec<Transition<S>>,
+    on_enter: Vec<DialogAction>,
+    otherwise: Vec<DialogAction>,
+    timeout: Option<Timeout<S>>,
+}
+
+impl<S> Default for StateConfig<S> {
+    fn default() -> Self {
+        Self {
+            transitions: Vec::new(),
+            on_enter: Vec::new(),
+            otherwise: Vec::new(),
+            timeout: None,
+        }
+    }
+}
+
+impl<S> StateConfig<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves to `target` on input matching `trigger`, running `actions` first.
+    pub fn on(mut self, trigger: Trigger, target: S, actions: Vec<DialogAction>) -> Self {
+        self.transitions.push(Transition {
+            trigger,
+            target: Some(target),
+            actions,
+        });
+        self
+    }
+
+    /// Ends the dialog on input matching `trigger`; the user's next message
+    /// starts over in the initial state.
+    pub fn end_on(mut self, trigger: Trigger, actions: Vec<DialogAction>) -> Self {
+        self.transitions.push(Transition {
+            trigger,
+            target: None,
+            actions,
+        });
+        self
+    }
+
+    /// Runs when a transition enters this state, after the transition's actions.
+    pub fn on_enter(mut self, actions: Vec<DialogAction>) -> Self {
+        self.on_enter = actions;
+        self
+    }
+
+    /// Runs when no transition matches; the state stays the same.
+    pub fn otherwise(mut self, actions: Vec<DialogAction>) -> Self {
+        self.otherwise = actions;
+        self
+    }
+
+    /// Moves to `target` once the user has been inactive in this state for `after`.
+    pub fn timeout(mut self, after: Duration, target: S, actions: Vec<DialogAction>) -> Self {
+        self.timeout = Some(Timeout {
+            after,
+            target: Some(target),
+            actions,
+        });
+        self
+    }
+
+    /// Ends the dialog once the user has been inactive in this state for `after`.
+    pub fn end_after(mut self, after: Duration, actions: Vec<DialogAction>) -> Self {
+        self.timeout = Some(Timeout {
+            after,
+            target: None,
+            actions,
+        });
+        self
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogOutcome<S> {
+
+    /// A transition matched; `to` is `None` when it ended the dialog.
+    Transitioned {
+        from: S,
+        to: Option<S>,
+    },
+
+    /// The state timed out; `to` is `None` when the timeout ended the dialog.
+    TimedOut {
+        from: S,
+        to: Option<S>,
+    },
+
+    /// No transition matched the input.
+    Unmatched {
+        state: S,
+    },
+}
+
+
+#[derive(Debug, Clone)]
+pub struct DialogEvent<S> {
+
+    pub wa_id: String,
+
+    pub outcome: DialogOutcome<S>,
+
+    /// The message's input; `None` for timeouts found by `expire_sessions`.
+    pub input: Option<DialogInput>,
+
+    /// The saved inputs after the outcome's actions ran.
+    pub data: HashMap<String, String>,
+}
+
+
+/// A user whose message or timeout could not be handled.
+#[derive(Debug)]
+pub struct DialogFailure {
+
+    pub wa_id: String,
+
+    pub error: WhatsAppError,
+}
+
+
+/// What happened while handling an event or expiring sessions.
+#[derive(Debug)]
+pub struct DialogReport<S> {
+
+    pub events: Vec<DialogEvent<S>>,
+
+    /// Failures by user; a failing user does not stop the others.
+    pub failures: Vec<DialogFailure>,
+}
+
+impl<S> DialogReport<S> {
+    fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+
+pub struct Dialog<S> {
+    client: WhatsAppClient,
+    initial: S,
+    states: HashMap<S, StateConfig<S>>,
+    store: Box<dyn SessionStore<S>>,
+}
+
+impl<S> fmt::Debug for Dialog<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dialog")
+            .field("initial", &self.initial)
+            .field("states", &self.states)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Dialog<S>
+where
+    S: Clone + Eq + Hash + Send + Sync + 'static,
+{
+    /// Users without a session start in `initial`.
+    pub fn new(client: WhatsAppClient, initial: S) -> Self {
+        Self {
+            client,
+            initial,
+            states: HashMap::new(),
+            store: Box::new(InMemorySessionStore::new()),
+        }
+    }
+
+    pub fn with_store(mut self, store: impl SessionStore<S> + 'static) -> Self {
+        self.store = Box::new(store);
+        self
+    }
+
+    pub fn state(mut self, state: S, config: StateConfig<S>) -> Self {
+        self.states.insert(state, config);
+        self
+    }
+
+    pub fn session(&self, wa_id: &str) -> WhatsAppResult<Option<Session<S>>> {
+        self.store.load(&wa_id_key(wa_id))
+    }
+
+    /// Forgets a user's session; their next message starts in the initial state.
+    pub fn reset(&self, wa_id: &str) -> WhatsAppResult<()> {
+        self.store.remove(&wa_id_key(wa_id))
+    }
+
+    /// Runs every inbound message of a webhook event through the dialog.
+    ///
+    /// A message that fails is reported and the following ones still run.
+    pub async fn handle_webhook_event(&self, event: &WebhookEvent) -> DialogReport<S> {
+        let mut report = DialogReport::new();
+        for change in event.changes() {
+            if let WebhookChangeValue::Messages(value) = &change.value {
+                for message in &value.messages {
+                    match self.handle_message(message).await {
+                        Ok(events) => report.events.extend(events),
+                        Err(error) => report.failures.push(DialogFailure { wa_id: wa_id_key(&message.from), error }),
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// Runs one inbound message through the dialog. A timeout that expired
+    /// before the message is reported first.
+    pub async fn handle_message(&self, message: &WebhookMessage) -> WhatsAppResult<Vec<DialogEvent<S>>> {
+        let wa_id = wa_id_key(&message.from);
+        let input = DialogInput::from_message(message);
+        let mut events = Vec::new();
+
+        let mut session = match self.store.load(&wa_id)? {
+            Some(session) => match self.timed_out(&session) {
+                Some(timeout) => {
+                    let event = self.time_out(&wa_id, session, timeout).await?;
+                    let session = self.store.load(&wa_id)?;
+                    events.push(event);
+                    session.unwrap_or_else(|| self.new_session())
+                }
+                None => session,
+            },
+            None => self.new_session(),
+        };
+        session.last_activity = now();
+
+        let config = self.states.get(&session.state);
+        let transition = config.and_then(|config| {
+            config.transitions.iter().find(|transition| transition.trigger.matches(&input))
+        });
+
+        let from = session.state.clone();
+        let outcome = match transition {
+            Some(transition) => {
+                self.take(&wa_id, &mut session, &transition.actions, transition.target.clone(), Some(&input))
+                    .await?;
+                DialogOutcome::Transitioned {
+                    from,
+                    to: transition.target.clone(),
+                }
+            }
+            None => {
+                let otherwise = config.map(|config| config.otherwise.as_slice()).unwrap_or_default();
+                store_inputs(&mut session, otherwise, Some(&input));
+                self.store.save(&wa_id, session.clone())?;
+                self.send(&wa_id, otherwise).await?;
+                DialogOutcome::Unmatched { state: from }
+            }
+        };
+
+        events.push(DialogEvent {
+            wa_id,
+            outcome,
+            input: Some(input),
+            data: session.data,
+        });
+        Ok(events)
+    }
+
+    /// Takes the timeout transition of every session whose state timed out.
+    ///
+    /// Call it periodically for timeouts to take effect without waiting for
+    /// the user's next message. A session that fails is reported and the
+    /// following ones still time out; only listing the sessions fails the call.
+    pub async fn expire_sessions(&self) -> WhatsAppResult<DialogReport<S>> {
+        let mut report = DialogReport::new();
+        for (wa_id, session) in self.store.sessions()? {
+            if let Some(timeout) = self.timed_out(&session) {
+                match self.time_out(&wa_id, session, timeout).await {
+                    Ok(event) => report.events.push(event),
+                    Err(error) => report.failures.push(DialogFailure { wa_id, error }),
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    fn new_session(&self) -> Session<S> {
+        Session {
+            state: self.initial.clone(),
+            data: HashMap::new(),
+            last_activity: now(),
+        }
+    }
+
+    fn timed_out(&self, session: &Session<S>) -> Option<&Timeout<S>> {
+        let timeout = self.states.get(&session.state)?.timeout.as_ref()?;
+        let inactive = now().saturating_sub(session.last_activity);
+        if inactive >= timeout.after.as_secs() { Some(timeout) } else { None }
+    }
+
+    async fn time_out(&self, wa_id: &str, mut session: Session<S>, timeout: &Timeout<S>) -> WhatsAppResult<DialogEvent<S>> {
+        let from = session.state.clone();
+        session.last_activity = now();
+
+        self.take(wa_id, &mut session, &timeout.actions, timeout.target.clone(), None).await?;
+
+        Ok(DialogEvent {
+            wa_id: wa_id.to_string(),
+            outcome: DialogOutcome::TimedOut {
+                from,
+                to: timeout.target.clone(),
+            },
+            input: None,
+            data: session.data,
+        })
+    }
+
+    /// Runs `actions` and moves the session to `target`, or ends it,
+    /// running the target's entry actions.
+    ///
+    /// The inputs are stored and the session saved before anything is sent,
+    /// so a failed send neither loses them nor leaves the user in the
+    /// previous state.
+    async fn take(
+        &self,
+        wa_id: &str,
+        session: &mut Session<S>,
+        actions: &[DialogAction],
+        target: Option<S>,
+        input: Option<&DialogInput>,
+    ) -> WhatsAppResult<()> {
+        store_inputs(session, actions, input);
+
+        let on_enter = match target {
+            Some(target) => {
+                let on_enter = self.states.get(&target).map(|config| config.on_enter.as_slice()).unwrap_or_default();
+                session.state = target;
+                store_inputs(session, on_enter, None);
+                self.store.save(wa_id, session.clone())?;
+                on_enter
+            }
+            None => {
+                self.store.remove(wa_id)?;
+                &[]
+            }
+        };
+
+        self.send(wa_id, actions).await?;
+        self.send(wa_id, on_enter).await
+    }
+
+    /// Sends the messages of `actions`, in order.
+    async fn send(&self, wa_id: &str, actions: &[DialogAction]) -> WhatsAppResult<()> {
+        for action in actions {
+            match action {
+                DialogAction::Text(text) => {
+                    self.client
+                        .send_text_message(SendTextMessage {
+                            to: wa_id.to_string(),
+                            text: text.clone(),
+                            preview_url: None,
+                            context: None,
+                        })
+                        .await?;
+                }
+                DialogAction::Interactive(interactive) => {
+                    self.client
+                        .send_interactive_message(SendInteractiveMessage {
+                            to: wa_id.to_string(),
+                            interactive: (**interactive).clone(),
+                            context: None,
+                        })
+                        .await?;
+                }
+                DialogAction::Store(_) | DialogAction::ClearData => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Applies the `Store` and `ClearData` actions of `actions`, in order.
+fn store_inputs<S>(session: &mut Session<S>, actions: &[DialogAction], input: Option<&DialogInput>) {
+    for action in actions {
+        match action {
+            DialogAction::Store(key) => {
+                if let Some(value) = input.and_then(DialogInput::value) {
+                    session.data.insert(key.clone(), value.to_string());
+                }
+            }
+            DialogAction::ClearData => session.data.clear(),
+            DialogAction::Text(_) | DialogAction::Interactive(_) => {}
+        }
+    }
+}
+
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
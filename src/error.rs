@@ -46,15 +46,167 @@ pub enum WhatsAppError {
     #[error("Validation error: {0}")]
     ValidationError(String),
 
+
+    #[error("Invalid message: {}", Violation::join(.0))]
+    InvalidMessage(Vec<Violation>),
+
    
+    #[error("Customer service window with {to} is closed; only templates can be sent")]
+    ConversationWindowClosed {
+
+        to: String,
+
+        /// Unix time of the user's last known inbound message.
+        last_inbound: Option<u64>,
+    },
+
+
     #[error("Missing required field: {0}")]
     MissingField(String),
 
 
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
+
+    /// A message sent in several parts failed after some parts were sent.
+    #[error("{error} (after sending {} parts)", .message_ids.len())]
+    PartiallySent {
+
+        /// Ids of the parts sent before the failure, in order.
+        message_ids: Vec<String>,
+
+        error: Box<WhatsAppError>,
+    },
+
+
     #[error("{0}")]
     Other(String),
 }
 
+/// Broad cause of an error, for deciding what to do about a failed send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+
+    /// The request itself is wrong; sending it again fails the same way.
+    InvalidRequest,
+
+    /// The recipient cannot receive the message, e.g. they opted out of
+    /// marketing messages or their customer service window is closed.
+    Recipient,
+
+    /// The template is missing, paused, disabled or its parameters don't match.
+    Template,
+
+    /// A rate, throughput or pair rate limit was hit.
+    RateLimited,
+
+    /// The access token, permissions or business account are not usable.
+    Account,
+
+    /// A temporary failure on the API side or in the network.
+    Transient,
+
+    Other,
+}
+
+impl WhatsAppError {
+    /// The API's error code, for `ApiError`s.
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            Self::ApiError { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::ApiError { code, .. } => match code {
+                4 | 80007 | 130429 | 131048 | 131056 => ErrorKind::RateLimited,
+                1 | 2 | 131016 | 133004 => ErrorKind::Transient,
+                0 | 3 | 10 | 190 | 200..=299 | 131031 | 131042 | 131045 | 133000..=133003 | 133005..=133010 => ErrorKind::Account,
+                131021 | 131026 | 131047 | 131049 | 131050 => ErrorKind::Recipient,
+                131014 | 132000..=132999 => ErrorKind::Template,
+                100 | 131008 | 131009 | 131051..=131053 => ErrorKind::InvalidRequest,
+                _ => ErrorKind::Other,
+            },
+            Self::HttpError(error) if error.is_timeout() || error.is_connect() => ErrorKind::Transient,
+            Self::HttpError(_) | Self::StorageError(_) | Self::Other(_) => ErrorKind::Other,
+            Self::RateLimitExceeded { .. } => ErrorKind::RateLimited,
+            Self::AuthenticationError(_) => ErrorKind::Account,
+            Self::ConversationWindowClosed { .. } => ErrorKind::Recipient,
+            Self::PartiallySent { error, .. } => error.kind(),
+            Self::JsonError(_) | Self::ValidationError(_) | Self::InvalidMessage(_) | Self::MissingField(_) => {
+                ErrorKind::InvalidRequest
+            }
+        }
+    }
+
+    /// Whether sending the same request again later may succeed.
+    ///
+    /// Spam rate limits (131048) are not, as they lift only once the number's
+    /// quality recovers, and neither are partial sends, which would repeat the
+    /// parts already sent.
+    pub fn is_retryable(&self) -> bool {
+        if let Self::PartiallySent { .. } = self {
+            return false;
+        }
+        match self.kind() {
+            ErrorKind::Transient => true,
+            ErrorKind::RateLimited => self.code() != Some(131048),
+            _ => false,
+        }
+    }
+
+    /// Whether the request may have reached the API before this error, so
+    /// that sending it again could deliver the message twice.
+    ///
+    /// That is any network error once the connection was made, such as a
+    /// timeout waiting for the response, and any JSON error, as request
+    /// bodies always serialize and only a response can fail to decode.
+    pub fn is_unconfirmed(&self) -> bool {
+        match self {
+            Self::HttpError(error) => !error.is_connect() && !error.is_builder(),
+            Self::JsonError(_) => true,
+            Self::PartiallySent { error, .. } => error.is_unconfirmed(),
+            _ => false,
+        }
+    }
+}
+
+/// A single failed check on an outgoing message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Path of the offending field, e.g. `interactive.action.buttons[2].reply.title`.
+    pub path: String,
+
+    pub message: String,
+}
+
+impl Violation {
+
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    fn join(violations: &[Violation]) -> String {
+        violations
+            .iter()
+            .map(|violation| violation.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
 pub struct ErrorHandler;
 
 impl ErrorHandler {
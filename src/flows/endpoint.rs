@@ -0,0 +1,435 @@
+//! Data-exchange endpoint for WhatsApp Flows
+//!
+//! Flows that use `data_exchange` call an endpoint of the business while the
+//! user moves between screens. Each request body carries the payload encrypted
+//! with AES-128-GCM under a fresh key, which is itself encrypted with the
+//! business's RSA public key (OAEP, SHA-256). The response must be encrypted
+//! with the same key and the bitwise inverse of the request's IV.
+//!
+//! [`FlowEndpoint`] does the cryptography and signature checking and hands
+//! typed requests to a [`FlowHandler`]. It is independent of any HTTP server:
+//! pass it the raw request body and `X-Hub-Signature-256` header, and write
+//! back the status and body of the returned [`FlowEndpointReply`].
+//!
+//! [`EncryptedFlowRequest::encrypt`] and [`FlowSession::decrypt_response`] do
+//! the WhatsApp side of the exchange, so an endpoint can be exercised locally
+//! with a generated key pair.
+
+use std::future::Future;
+
+use aes_gcm::aead::generic_array::typenum::U16;
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::aes::Aes128;
+use aes_gcm::AesGcm;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::{Oaep, RsaPrivateKey, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use crate::error::{WhatsAppError, WhatsAppResult};
+
+/// AES-128-GCM with the 16 byte IV WhatsApp uses.
+type FlowCipher = AesGcm<Aes128, U16>;
+
+const KEY_LENGTH: usize = 16;
+const IV_LENGTH: usize = 16;
+
+const INTERNAL_ERROR: &str = "Internal error";
+
+/// Status for requests whose signature does not match the app secret.
+pub const STATUS_INVALID_SIGNATURE: u16 = 432;
+/// Status for requests that cannot be decrypted; WhatsApp refetches the public key.
+pub const STATUS_DECRYPTION_FAILED: u16 = 421;
+/// Status for requests with an unknown or expired flow token.
+pub const STATUS_INVALID_FLOW_TOKEN: u16 = 427;
+
+
+/// Request body posted to the endpoint, with all fields base64 encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedFlowRequest {
+
+    pub encrypted_flow_data: String,
+
+    pub encrypted_aes_key: String,
+
+    pub initial_vector: String,
+}
+
+impl EncryptedFlowRequest {
+    /// Encrypts `payload` the way WhatsApp does, for exercising an endpoint locally.
+    pub fn encrypt(public_key: &RsaPublicKey, payload: &Value) -> WhatsAppResult<(Self, FlowSession)> {
+        let mut key = [0u8; KEY_LENGTH];
+        let mut iv = [0u8; IV_LENGTH];
+        OsRng.fill_bytes(&mut key);
+        OsRng.fill_bytes(&mut iv);
+
+        let encrypted_key = public_key
+            .encrypt(&mut OsRng, Oaep::new::<Sha256>(), &key)
+            .map_err(|e| WhatsAppError::Other(format!("Failed to encrypt flow key: {}", e)))?;
+
+        let session = FlowSession { key: key.to_vec(), iv: iv.to_vec() };
+        let encrypted_data = session.seal(&iv, &serde_json::to_vec(payload)?)?;
+
+        let request = EncryptedFlowRequest {
+            encrypted_flow_data: BASE64.encode(encrypted_data),
+            encrypted_aes_key: BASE64.encode(encrypted_key),
+            initial_vector: BASE64.encode(iv),
+        };
+
+        Ok((request, session))
+    }
+
+    /// Recovers the payload and the session its response must be encrypted with.
+    pub fn decrypt(&self, private_key: &RsaPrivateKey) -> WhatsAppResult<(Value, FlowSession)> {
+        let encrypted_key = decode(&self.encrypted_aes_key, "encrypted_aes_key")?;
+        let iv = decode(&self.initial_vector, "initial_vector")?;
+        let encrypted_data = decode(&self.encrypted_flow_data, "encrypted_flow_data")?;
+
+        let key = private_key
+            .decrypt(Oaep::new::<Sha256>(), &encrypted_key)
+            .map_err(|e| WhatsAppError::ValidationError(format!("Failed to decrypt flow key: {}", e)))?;
+
+        if key.len() != KEY_LENGTH || iv.len() != IV_LENGTH {
+            return Err(WhatsAppError::ValidationError(format!(
+                "Expected a {} byte key and {} byte IV, got {} and {}",
+                KEY_LENGTH,
+                IV_LENGTH,
+                key.len(),
+                iv.len()
+            )));
+        }
+
+        let session = FlowSession { key, iv };
+        let payload = session.open(&session.iv, &encrypted_data)?;
+
+        Ok((serde_json::from_slice(&payload)?, session))
+    }
+}
+
+fn decode(value: &str, field: &str) -> WhatsAppResult<Vec<u8>> {
+    BASE64
+        .decode(value)
+        .map_err(|e| WhatsAppError::ValidationError(format!("{} is not valid base64: {}", field, e)))
+}
+
+
+/// The AES key and IV of one request, used to encrypt its response.
+#[derive(Clone)]
+pub struct FlowSession {
+    key: Vec<u8>,
+    iv: Vec<u8>,
+}
+
+impl std::fmt::Debug for FlowSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlowSession").finish_non_exhaustive()
+    }
+}
+
+impl FlowSession {
+    fn cipher(&self) -> FlowCipher {
+        FlowCipher::new(GenericArray::from_slice(&self.key))
+    }
+
+    fn flipped_iv(&self) -> Vec<u8> {
+        self.iv.iter().map(|byte| !byte).collect()
+    }
+
+    fn seal(&self, iv: &[u8], plaintext: &[u8]) -> WhatsAppResult<Vec<u8>> {
+        self.cipher()
+            .encrypt(GenericArray::from_slice(iv), plaintext)
+            .map_err(|_| WhatsAppError::Other("Failed to encrypt flow payload".to_string()))
+    }
+
+    fn open(&self, iv: &[u8], ciphertext: &[u8]) -> WhatsAppResult<Vec<u8>> {
+        self.cipher()
+            .decrypt(GenericArray::from_slice(iv), ciphertext)
+            .map_err(|_| WhatsAppError::ValidationError("Failed to decrypt flow payload".to_string()))
+    }
+
+    /// Encrypts a response body, returned base64 encoded as the endpoint's plain text reply.
+    pub fn encrypt_response(&self, response: &Value) -> WhatsAppResult<String> {
+        let encrypted = self.seal(&self.flipped_iv(), &serde_json::to_vec(response)?)?;
+        Ok(BASE64.encode(encrypted))
+    }
+
+    /// Decrypts a response body the way WhatsApp does, for exercising an endpoint locally.
+    pub fn decrypt_response(&self, body: &str) -> WhatsAppResult<Value> {
+        let encrypted = decode(body.trim(), "response")?;
+        let decrypted = self.open(&self.flipped_iv(), &encrypted)?;
+        Ok(serde_json::from_slice(&decrypted)?)
+    }
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawFlowRequest {
+    version: String,
+    action: String,
+    screen: Option<String>,
+    #[serde(default)]
+    data: Option<Value>,
+    flow_token: Option<String>,
+}
+
+
+/// A decrypted `INIT`, `data_exchange` or `BACK` request.
+#[derive(Debug, Clone)]
+pub struct FlowDataRequest {
+
+    pub version: String,
+
+    pub flow_token: String,
+
+    /// Screen the request was made from; absent for `INIT`.
+    pub screen: Option<String>,
+
+    /// Payload the screen sent, or `Null` when there is none.
+    pub data: Value,
+}
+
+
+/// Sent by WhatsApp when the client rejected a previous response.
+#[derive(Debug, Clone)]
+pub struct FlowErrorNotification {
+
+    pub flow_token: String,
+
+    pub screen: Option<String>,
+
+    pub error: String,
+
+    pub error_message: Option<String>,
+}
+
+
+/// What a handler answers a data request with.
+#[derive(Debug, Clone)]
+pub enum FlowScreenResponse {
+
+    /// Shows `screen`, filled with `data`.
+    Screen { screen: String, data: Value },
+
+    /// Closes the Flow. `params` are delivered with the `nfm_reply` webhook
+    /// along with the flow token.
+    Close { flow_token: String, params: Value },
+}
+
+impl FlowScreenResponse {
+    pub fn screen(screen: impl Into<String>, data: Value) -> Self {
+        FlowScreenResponse::Screen { screen: screen.into(), data }
+    }
+
+    pub fn close(flow_token: impl Into<String>, params: Value) -> Self {
+        FlowScreenResponse::Close { flow_token: flow_token.into(), params }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            FlowScreenResponse::Screen { screen, data } => json!({ "screen": screen, "data": data }),
+            FlowScreenResponse::Close { flow_token, params } => {
+                let mut params = match params {
+                    Value::Object(map) => map.clone(),
+                    _ => serde_json::Map::new(),
+                };
+                params.insert("flow_token".to_string(), json!(flow_token));
+
+                json!({
+                    "screen": "SUCCESS",
+                    "data": { "extension_message_response": { "params": params } },
+                })
+            }
+        }
+    }
+}
+
+
+/// Business logic behind a Flow endpoint.
+///
+/// To reject an unknown or expired flow token, return
+/// `WhatsAppError::AuthenticationError`; the endpoint answers it with
+/// [`STATUS_INVALID_FLOW_TOKEN`] and the error's message. Other errors are
+/// answered with status 500 and a generic body, so internal details never
+/// reach the client.
+pub trait FlowHandler: Send + Sync {
+
+    /// The Flow was opened with `flow_action: data_exchange` and needs its first screen.
+    fn init(&self, request: FlowDataRequest) -> impl Future<Output = WhatsAppResult<FlowScreenResponse>> + Send;
+
+    /// A screen submitted data.
+    fn data_exchange(&self, request: FlowDataRequest) -> impl Future<Output = WhatsAppResult<FlowScreenResponse>> + Send;
+
+    /// The user went back to a screen marked `refresh_on_back`.
+    fn back(&self, request: FlowDataRequest) -> impl Future<Output = WhatsAppResult<FlowScreenResponse>> + Send {
+        async move {
+            Err(WhatsAppError::Other(format!(
+                "BACK is not handled (screen: {})",
+                request.screen.unwrap_or_default()
+            )))
+        }
+    }
+
+    /// WhatsApp reports that a previous response could not be used. It is
+    /// acknowledged whatever this does.
+    fn error_notification(&self, notification: FlowErrorNotification) -> impl Future<Output = ()> + Send {
+        let _ = notification;
+        async {}
+    }
+}
+
+
+/// Status and plain text body to answer the HTTP request with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowEndpointReply {
+
+    pub status: u16,
+
+    pub body: String,
+}
+
+impl FlowEndpointReply {
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        FlowEndpointReply { status, body: message.into() }
+    }
+}
+
+
+pub struct FlowEndpoint<H> {
+    private_key: RsaPrivateKey,
+    app_secret: Option<String>,
+    handler: H,
+}
+
+impl<H: FlowHandler> FlowEndpoint<H> {
+    /// Requests must be signed with `app_secret`, as WhatsApp does.
+    pub fn new(private_key: RsaPrivateKey, app_secret: impl Into<String>, handler: H) -> Self {
+        Self {
+            private_key,
+            app_secret: Some(app_secret.into()),
+            handler,
+        }
+    }
+
+    /// Loads an unencrypted PKCS#8 or PKCS#1 PEM private key.
+    ///
+    /// Keys generated with a passphrase must be decrypted first, e.g. with
+    /// `openssl pkcs8 -topk8 -nocrypt`.
+    pub fn from_pem(pem: &str, app_secret: impl Into<String>, handler: H) -> WhatsAppResult<Self> {
+        let private_key = RsaPrivateKey::from_pkcs8_pem(pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+            .map_err(|e| WhatsAppError::ValidationError(format!("Invalid private key: {}", e)))?;
+
+        Ok(Self::new(private_key, app_secret, handler))
+    }
+
+    /// Accepts requests without checking their signature, so anyone who can
+    /// reach the endpoint can call the handler. Only for local testing.
+    pub fn insecure_skip_signature(mut self) -> Self {
+        self.app_secret = None;
+        self
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    /// Checks an `X-Hub-Signature-256` header against the raw body.
+    ///
+    /// Always succeeds after `insecure_skip_signature`.
+    pub fn verify_signature(&self, body: &[u8], signature: Option<&str>) -> WhatsAppResult<()> {
+        let app_secret = match &self.app_secret {
+            Some(app_secret) => app_secret,
+            None => return Ok(()),
+        };
+
+        let invalid = || WhatsAppError::AuthenticationError("Invalid flow request signature".to_string());
+        let expected = signature
+            .and_then(|signature| signature.strip_prefix("sha256="))
+            .and_then(|signature| hex::decode(signature).ok())
+            .ok_or_else(invalid)?;
+
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(app_secret.as_bytes())
+            .map_err(|e| WhatsAppError::Other(e.to_string()))?;
+        mac.update(body);
+        mac.verify_slice(&expected).map_err(|_| invalid())
+    }
+
+    /// Handles one request, from raw body to encrypted reply.
+    pub async fn handle(&self, body: &[u8], signature: Option<&str>) -> FlowEndpointReply {
+        if self.verify_signature(body, signature).is_err() {
+            return FlowEndpointReply::error(STATUS_INVALID_SIGNATURE, "Invalid signature");
+        }
+
+        let decrypted = serde_json::from_slice::<EncryptedFlowRequest>(body)
+            .map_err(WhatsAppError::from)
+            .and_then(|request| request.decrypt(&self.private_key));
+        let (payload, session) = match decrypted {
+            Ok(decrypted) => decrypted,
+            Err(_) => return FlowEndpointReply::error(STATUS_DECRYPTION_FAILED, "Failed to decrypt request"),
+        };
+
+        let response = match self.dispatch(payload).await {
+            Ok(response) => response,
+            Err(WhatsAppError::AuthenticationError(message)) => {
+                return FlowEndpointReply::error(STATUS_INVALID_FLOW_TOKEN, message)
+            }
+            Err(_) => return FlowEndpointReply::error(500, INTERNAL_ERROR),
+        };
+
+        match session.encrypt_response(&response) {
+            Ok(body) => FlowEndpointReply { status: 200, body },
+            Err(_) => FlowEndpointReply::error(500, INTERNAL_ERROR),
+        }
+    }
+
+    /// Routes a decrypted request to the handler and returns the response to encrypt.
+    pub async fn dispatch(&self, payload: Value) -> WhatsAppResult<Value> {
+        let request: RawFlowRequest = serde_json::from_value(payload)?;
+
+        if request.action == "ping" {
+            return Ok(json!({ "data": { "status": "active" } }));
+        }
+
+        let flow_token = request
+            .flow_token
+            .ok_or_else(|| WhatsAppError::MissingField("flow_token".to_string()))?;
+        let data = request.data.unwrap_or(Value::Null);
+
+        if let Some(error) = data.get("error").and_then(Value::as_str) {
+            self.handler
+                .error_notification(FlowErrorNotification {
+                    flow_token,
+                    screen: request.screen,
+                    error: error.to_string(),
+                    error_message: data.get("error_message").and_then(Value::as_str).map(str::to_string),
+                })
+                .await;
+            return Ok(json!({ "data": { "acknowledged": true } }));
+        }
+
+        let data_request = FlowDataRequest {
+            version: request.version,
+            flow_token,
+            screen: request.screen,
+            data,
+        };
+
+        let response = match request.action.as_str() {
+            "INIT" => self.handler.init(data_request).await?,
+            "data_exchange" => self.handler.data_exchange(data_request).await?,
+            "BACK" => self.handler.back(data_request).await?,
+            other => {
+                return Err(WhatsAppError::ValidationError(format!("Unknown flow action `{}`", other)))
+            }
+        };
+
+        Ok(response.to_json())
+    }
+}
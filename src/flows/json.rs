@@ -0,0 +1,526 @@
+//! Typed model of Flow JSON
+//!
+//! Covers the screens, layout, components, actions, routing model and data
+//! schemas of Flow JSON as uploaded with `WhatsAppClient::update_flow`. Fields
+//! keep the hyphenated names Flow JSON uses when serialized. Properties that
+//! accept either a literal or a binding expression such as `${data.slots}`
+//! are [`Dynamic`].
+//!
+//! Unknown component types fail to deserialize rather than being dropped, so a
+//! typo in a hand-written Flow is reported instead of silently uploaded.
+
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowJson {
+
+    /// Flow JSON version, e.g. `6.0`.
+    pub version: String,
+
+    /// Set for Flows that use a data-exchange endpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_api_version: Option<String>,
+
+    /// Screens each screen may navigate to; required with `data_api_version`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_model: Option<BTreeMap<String, Vec<String>>>,
+
+    pub screens: Vec<FlowScreen>,
+}
+
+impl FlowJson {
+    pub fn screen(&self, id: &str) -> Option<&FlowScreen> {
+        self.screens.iter().find(|screen| screen.id == id)
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowScreen {
+
+    /// Upper case letters and underscores; `SUCCESS` is reserved.
+    pub id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub terminal: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_on_back: Option<bool>,
+
+    /// Schema of the data the screen receives, keyed by field name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<BTreeMap<String, DataField>>,
+
+    pub layout: FlowLayout,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FlowLayoutType {
+
+    SingleColumnLayout,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowLayout {
+
+    pub r#type: FlowLayoutType,
+
+    pub children: Vec<FlowComponent>,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DataType {
+
+    String,
+
+    Number,
+
+    Boolean,
+
+    Object,
+
+    Array,
+}
+
+
+/// One field of a screen's data schema.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataField {
+
+    pub r#type: DataType,
+
+    /// Item schema of an array.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<DataField>>,
+
+    /// Property schemas of an object.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub properties: Option<BTreeMap<String, DataField>>,
+
+    #[serde(rename = "__example__", skip_serializing_if = "Option::is_none")]
+    pub example: Option<Value>,
+}
+
+
+/// A property given either literally or as a binding expression.
+///
+/// Any string containing `${` is read as a binding, so a `Dynamic<String>` is
+/// only a `Value` when it is plain text.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum Dynamic<T> {
+
+    Value(T),
+
+    Binding(String),
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Dynamic<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        match Value::deserialize(deserializer)? {
+            Value::String(expression) if expression.contains("${") => Ok(Dynamic::Binding(expression)),
+            value => serde_json::from_value(value).map(Dynamic::Value).map_err(D::Error::custom),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct DataSourceItem {
+
+    pub id: String,
+
+    pub title: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NextScreen {
+
+    /// Always `screen`.
+    pub r#type: String,
+
+    pub name: String,
+}
+
+
+/// What a component does when it is tapped or changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "snake_case")]
+pub enum ComponentAction {
+
+    Navigate {
+        next: NextScreen,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payload: Option<Map<String, Value>>,
+    },
+
+    Complete {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payload: Option<Map<String, Value>>,
+    },
+
+    DataExchange {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payload: Option<Map<String, Value>>,
+    },
+
+    UpdateData {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payload: Option<Map<String, Value>>,
+    },
+
+    OpenUrl {
+        url: String,
+    },
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum FlowComponent {
+
+    TextHeading(TextComponent),
+
+    TextSubheading(TextComponent),
+
+    TextBody(TextComponent),
+
+    TextCaption(TextComponent),
+
+    TextInput(TextInput),
+
+    TextArea(TextArea),
+
+    Dropdown(SelectionComponent),
+
+    RadioButtonsGroup(SelectionComponent),
+
+    CheckboxGroup(SelectionComponent),
+
+    DatePicker(DatePicker),
+
+    OptIn(OptIn),
+
+    Image(Image),
+
+    EmbeddedLink(EmbeddedLink),
+
+    Footer(Footer),
+
+    /// Groups inputs in Flow JSON versions before 4.0.
+    Form(Form),
+
+    If(If),
+
+    Switch(Switch),
+}
+
+impl FlowComponent {
+    /// Name the component's value is submitted under, for input components.
+    pub fn input_name(&self) -> Option<&str> {
+        match self {
+            FlowComponent::TextInput(c) => Some(&c.name),
+            FlowComponent::TextArea(c) => Some(&c.name),
+            FlowComponent::Dropdown(c)
+            | FlowComponent::RadioButtonsGroup(c)
+            | FlowComponent::CheckboxGroup(c) => Some(&c.name),
+            FlowComponent::DatePicker(c) => Some(&c.name),
+            FlowComponent::OptIn(c) => Some(&c.name),
+            _ => None,
+        }
+    }
+
+    /// Components nested directly inside this one.
+    pub fn children(&self) -> Vec<&FlowComponent> {
+        match self {
+            FlowComponent::Form(form) => form.children.iter().collect(),
+            FlowComponent::If(branch) => branch.then.iter().chain(branch.r#else.iter().flatten()).collect(),
+            FlowComponent::Switch(switch) => switch.cases.values().flatten().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Actions the component can trigger.
+    pub fn actions(&self) -> Vec<&ComponentAction> {
+        let actions = match self {
+            FlowComponent::Footer(c) => vec![Some(&c.on_click_action)],
+            FlowComponent::EmbeddedLink(c) => vec![Some(&c.on_click_action)],
+            FlowComponent::OptIn(c) => vec![c.on_click_action.as_ref()],
+            FlowComponent::Dropdown(c)
+            | FlowComponent::RadioButtonsGroup(c)
+            | FlowComponent::CheckboxGroup(c) => vec![c.on_select_action.as_ref()],
+            FlowComponent::DatePicker(c) => vec![c.on_select_action.as_ref()],
+            _ => Vec::new(),
+        };
+        actions.into_iter().flatten().collect()
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TextComponent {
+
+    pub text: Dynamic<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<Dynamic<bool>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TextInput {
+
+    pub name: String,
+
+    pub label: Dynamic<String>,
+
+    /// `text`, `number`, `email`, `password`, `passcode` or `phone`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_type: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Dynamic<bool>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_chars: Option<Dynamic<u32>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_chars: Option<Dynamic<u32>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub helper_text: Option<Dynamic<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init_value: Option<Dynamic<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<Dynamic<bool>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TextArea {
+
+    pub name: String,
+
+    pub label: Dynamic<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Dynamic<bool>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<Dynamic<u32>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub helper_text: Option<Dynamic<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<Dynamic<bool>>,
+}
+
+
+/// Dropdown, RadioButtonsGroup or CheckboxGroup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SelectionComponent {
+
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<Dynamic<String>>,
+
+    pub data_source: Dynamic<Vec<DataSourceItem>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Dynamic<bool>>,
+
+    /// CheckboxGroup only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_selected_items: Option<Dynamic<u32>>,
+
+    /// CheckboxGroup only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_selected_items: Option<Dynamic<u32>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_select_action: Option<ComponentAction>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<Dynamic<bool>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DatePicker {
+
+    pub name: String,
+
+    pub label: Dynamic<String>,
+
+    /// `YYYY-MM-DD`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_date: Option<Dynamic<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_date: Option<Dynamic<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unavailable_dates: Option<Dynamic<Vec<String>>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub helper_text: Option<Dynamic<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Dynamic<bool>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_select_action: Option<ComponentAction>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<Dynamic<bool>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct OptIn {
+
+    pub name: String,
+
+    pub label: Dynamic<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Dynamic<bool>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_click_action: Option<ComponentAction>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<Dynamic<bool>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Image {
+
+    /// Base64 encoded image.
+    pub src: Dynamic<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<Dynamic<u32>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<Dynamic<u32>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale_type: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alt_text: Option<Dynamic<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<Dynamic<bool>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EmbeddedLink {
+
+    pub text: Dynamic<String>,
+
+    pub on_click_action: ComponentAction,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<Dynamic<bool>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Footer {
+
+    pub label: Dynamic<String>,
+
+    pub on_click_action: ComponentAction,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left_caption: Option<Dynamic<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub center_caption: Option<Dynamic<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub right_caption: Option<Dynamic<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<Dynamic<bool>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Form {
+
+    pub name: String,
+
+    pub children: Vec<FlowComponent>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct If {
+
+    /// Boolean expression, e.g. `${form.opt_in}`.
+    pub condition: String,
+
+    pub then: Vec<FlowComponent>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#else: Option<Vec<FlowComponent>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Switch {
+
+    /// Expression whose value selects the case, e.g. `${data.plan}`.
+    pub value: String,
+
+    pub cases: BTreeMap<String, Vec<FlowComponent>>,
+}
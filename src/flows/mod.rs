@@ -0,0 +1,11 @@
+//! WhatsApp Flows support beyond sending and managing Flows
+//!
+//! - [`endpoint`] serves the encrypted data-exchange requests Flows make while open.
+//! - [`json`] models Flow JSON, which [`validator`] checks before it is uploaded.
+
+pub mod endpoint;
+pub mod json;
+pub mod validator;
+
+pub use endpoint::{FlowEndpoint, FlowHandler, FlowDataRequest, FlowScreenResponse};
+pub use json::{FlowJson, FlowScreen, FlowComponent, ComponentAction};
@@ -0,0 +1,424 @@
+//! Offline checks for Flow JSON
+//!
+//! Catches the mistakes the Flows API would otherwise only report after an
+//! upload: unknown or unreachable screens, routes missing from the routing
+//! model, terminal screens without a footer, and binding expressions that
+//! refer to data or form fields that do not exist.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use serde_json::Value;
+
+use crate::error::Violation;
+use crate::flows::json::*;
+use crate::validation::Validate;
+
+/// Keys of container components that hold nested components rather than properties.
+const NESTED_KEYS: &[&str] = &["children", "then", "else", "cases"];
+
+
+impl Validate for FlowJson {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        let validator = FlowValidator::new(self, path);
+
+        if self.version.trim().is_empty() {
+            violations.push(Violation::new(validator.field("version"), "must not be empty"));
+        }
+        if self.screens.is_empty() {
+            violations.push(Violation::new(validator.field("screens"), "must contain at least one screen"));
+            return;
+        }
+
+        validator.check_screen_ids(violations);
+        validator.check_routing_model(violations);
+        validator.check_reachability(violations);
+
+        for (i, screen) in self.screens.iter().enumerate() {
+            validator.check_screen(i, screen, violations);
+        }
+    }
+}
+
+
+struct FlowValidator<'a> {
+    flow: &'a FlowJson,
+    path: &'a str,
+}
+
+impl<'a> FlowValidator<'a> {
+    fn new(flow: &'a FlowJson, path: &'a str) -> Self {
+        Self { flow, path }
+    }
+
+    fn field(&self, name: &str) -> String {
+        if self.path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", self.path, name)
+        }
+    }
+
+    fn screen_path(&self, i: usize) -> String {
+        format!("{}[{}]", self.field("screens"), i)
+    }
+
+    fn screen_exists(&self, id: &str) -> bool {
+        self.flow.screen(id).is_some()
+    }
+
+    fn check_screen_ids(&self, violations: &mut Vec<Violation>) {
+        let mut seen = BTreeSet::new();
+
+        for (i, screen) in self.flow.screens.iter().enumerate() {
+            let path = format!("{}.id", self.screen_path(i));
+
+            if screen.id.is_empty() || !screen.id.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_') {
+                violations.push(Violation::new(path.clone(), "must consist of upper case letters, digits and underscores"));
+            }
+            if screen.id == "SUCCESS" {
+                violations.push(Violation::new(path.clone(), "SUCCESS is reserved"));
+            }
+            if !seen.insert(screen.id.as_str()) {
+                violations.push(Violation::new(path, format!("duplicate screen id {}", screen.id)));
+            }
+        }
+    }
+
+    fn check_routing_model(&self, violations: &mut Vec<Violation>) {
+        let routing_model = match &self.flow.routing_model {
+            Some(routing_model) => routing_model,
+            None => {
+                if self.flow.data_api_version.is_some() {
+                    violations.push(Violation::new(
+                        self.field("routing_model"),
+                        "is required when data_api_version is set",
+                    ));
+                }
+                return;
+            }
+        };
+
+        for (from, targets) in routing_model {
+            let path = format!("{}.{}", self.field("routing_model"), from);
+            if !self.screen_exists(from) {
+                violations.push(Violation::new(path.clone(), format!("unknown screen {}", from)));
+            }
+            for target in targets {
+                if !self.screen_exists(target) {
+                    violations.push(Violation::new(path.clone(), format!("routes to unknown screen {}", target)));
+                }
+                if target == from {
+                    violations.push(Violation::new(path.clone(), "must not route a screen to itself"));
+                }
+            }
+        }
+    }
+
+    /// Routes between screens: the routing model when there is one, plus every navigate action.
+    fn edges(&self) -> BTreeMap<&'a str, BTreeSet<&'a str>> {
+        let mut edges: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+
+        if let Some(routing_model) = &self.flow.routing_model {
+            for (from, targets) in routing_model {
+                edges.entry(from).or_default().extend(targets.iter().map(String::as_str));
+            }
+        }
+        for screen in &self.flow.screens {
+            for component in all_components(&screen.layout.children) {
+                for action in component.actions() {
+                    if let ComponentAction::Navigate { next, .. } = action {
+                        edges.entry(&screen.id).or_default().insert(&next.name);
+                    }
+                }
+            }
+        }
+
+        edges
+    }
+
+    fn check_reachability(&self, violations: &mut Vec<Violation>) {
+        let edges = self.edges();
+        let targeted: BTreeSet<&str> = edges.values().flatten().copied().collect();
+
+        // With a routing model any screen nothing routes to is an entry point;
+        // otherwise the Flow opens on its first screen.
+        let entries: Vec<&str> = if self.flow.routing_model.is_some() {
+            self.flow
+                .screens
+                .iter()
+                .map(|screen| screen.id.as_str())
+                .filter(|id| !targeted.contains(id))
+                .collect()
+        } else {
+            vec![self.flow.screens[0].id.as_str()]
+        };
+
+        if entries.is_empty() {
+            violations.push(Violation::new(
+                self.field("routing_model"),
+                "has no entry screen; every screen is routed to from another",
+            ));
+            return;
+        }
+
+        let mut reached: BTreeSet<&str> = entries.iter().copied().collect();
+        let mut queue: VecDeque<&str> = entries.into_iter().collect();
+        while let Some(id) = queue.pop_front() {
+            for &next in edges.get(id).into_iter().flatten() {
+                if reached.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        for (i, screen) in self.flow.screens.iter().enumerate() {
+            if !reached.contains(screen.id.as_str()) {
+                violations.push(Violation::new(self.screen_path(i), format!("screen {} is unreachable", screen.id)));
+            }
+        }
+
+        if !self.flow.screens.iter().any(|screen| screen.terminal) {
+            violations.push(Violation::new(self.field("screens"), "must contain a terminal screen"));
+        }
+    }
+
+    fn check_screen(&self, i: usize, screen: &FlowScreen, violations: &mut Vec<Violation>) {
+        let screen_path = self.screen_path(i);
+
+        for (name, field) in screen.data.iter().flatten() {
+            if field.example.is_none() {
+                violations.push(Violation::new(
+                    format!("{}.data.{}", screen_path, name),
+                    "requires an __example__ value",
+                ));
+            }
+        }
+
+        let components = all_components(&screen.layout.children);
+
+        let footers = components.iter().filter(|c| matches!(c, FlowComponent::Footer(_))).count();
+        if footers > 1 {
+            violations.push(Violation::new(format!("{}.layout", screen_path), "must contain at most one Footer"));
+        }
+        if screen.terminal && footers == 0 {
+            violations.push(Violation::new(format!("{}.layout", screen_path), "terminal screens require a Footer"));
+        }
+
+        let mut names = BTreeSet::new();
+        for component in &components {
+            if let Some(name) = component.input_name() {
+                if name.trim().is_empty() {
+                    violations.push(Violation::new(format!("{}.layout", screen_path), "input names must not be empty"));
+                } else if !names.insert(name) {
+                    violations.push(Violation::new(format!("{}.layout", screen_path), format!("duplicate input name {}", name)));
+                }
+            }
+        }
+
+        let children_path = format!("{}.layout.children", screen_path);
+        self.check_components(screen, &screen.layout.children, &children_path, &names, violations);
+    }
+
+    fn check_components(
+        &self,
+        screen: &FlowScreen,
+        components: &[FlowComponent],
+        path: &str,
+        inputs: &BTreeSet<&str>,
+        violations: &mut Vec<Violation>,
+    ) {
+        for (i, component) in components.iter().enumerate() {
+            let component_path = format!("{}[{}]", path, i);
+
+            for action in component.actions() {
+                self.check_action(screen, action, &component_path, violations);
+            }
+
+            if let FlowComponent::Dropdown(selection)
+            | FlowComponent::RadioButtonsGroup(selection)
+            | FlowComponent::CheckboxGroup(selection) = component
+            {
+                check_data_source(selection, &component_path, violations);
+            }
+
+            for expression in expressions(&own_properties(component)) {
+                self.check_expression(screen, &expression, inputs, &component_path, violations);
+            }
+
+            match component {
+                FlowComponent::Form(form) => {
+                    let nested = format!("{}.children", component_path);
+                    self.check_components(screen, &form.children, &nested, inputs, violations);
+                }
+                FlowComponent::If(branch) => {
+                    let nested = format!("{}.then", component_path);
+                    self.check_components(screen, &branch.then, &nested, inputs, violations);
+                    if let Some(otherwise) = &branch.r#else {
+                        let nested = format!("{}.else", component_path);
+                        self.check_components(screen, otherwise, &nested, inputs, violations);
+                    }
+                }
+                FlowComponent::Switch(switch) => {
+                    for (case, children) in &switch.cases {
+                        let nested = format!("{}.cases.{}", component_path, case);
+                        self.check_components(screen, children, &nested, inputs, violations);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check_action(&self, screen: &FlowScreen, action: &ComponentAction, path: &str, violations: &mut Vec<Violation>) {
+        match action {
+            ComponentAction::Navigate { next, .. } => {
+                if !self.screen_exists(&next.name) {
+                    violations.push(Violation::new(path, format!("navigates to unknown screen {}", next.name)));
+                } else if let Some(routing_model) = &self.flow.routing_model {
+                    let routed = routing_model
+                        .get(&screen.id)
+                        .is_some_and(|targets| targets.contains(&next.name));
+                    if !routed {
+                        violations.push(Violation::new(
+                            path,
+                            format!("route {} -> {} is missing from routing_model", screen.id, next.name),
+                        ));
+                    }
+                }
+            }
+            ComponentAction::Complete { .. } if !screen.terminal => {
+                violations.push(Violation::new(path, "complete is only allowed on terminal screens"));
+            }
+            ComponentAction::DataExchange { .. } if self.flow.data_api_version.is_none() => {
+                violations.push(Violation::new(path, "data_exchange requires data_api_version"));
+            }
+            _ => {}
+        }
+    }
+
+    fn check_expression(
+        &self,
+        screen: &FlowScreen,
+        expression: &str,
+        inputs: &BTreeSet<&str>,
+        path: &str,
+        violations: &mut Vec<Violation>,
+    ) {
+        for reference in references(expression) {
+            let segments: Vec<&str> = reference.split('.').collect();
+
+            let problem = match segments.as_slice() {
+                ["data", field, ..] => (!has_data_field(screen, field))
+                    .then(|| format!("{} refers to data field {} which screen {} does not declare", reference, field, screen.id)),
+                ["form", field, ..] => (!inputs.contains(field))
+                    .then(|| format!("{} refers to input {} which is not on screen {}", reference, field, screen.id)),
+                ["screen", id, kind, field, ..] => match self.flow.screen(id) {
+                    None => Some(format!("{} refers to unknown screen {}", reference, id)),
+                    Some(other) if *kind == "data" && !has_data_field(other, field) => {
+                        Some(format!("{} refers to data field {} which screen {} does not declare", reference, field, id))
+                    }
+                    Some(other) if *kind == "form" && !screen_inputs(other).contains(field) => {
+                        Some(format!("{} refers to input {} which is not on screen {}", reference, field, id))
+                    }
+                    _ => None,
+                },
+                _ => Some(format!("{} is not a valid reference", reference)),
+            };
+
+            if let Some(message) = problem {
+                violations.push(Violation::new(path, message));
+            }
+        }
+    }
+}
+
+
+/// Every component of a layout, including those nested in containers.
+fn all_components(components: &[FlowComponent]) -> Vec<&FlowComponent> {
+    let mut all = Vec::new();
+    for component in components {
+        all.push(component);
+        all.extend(all_components_of(component));
+    }
+    all
+}
+
+fn all_components_of(component: &FlowComponent) -> Vec<&FlowComponent> {
+    let mut all = Vec::new();
+    for child in component.children() {
+        all.push(child);
+        all.extend(all_components_of(child));
+    }
+    all
+}
+
+fn screen_inputs(screen: &FlowScreen) -> BTreeSet<&str> {
+    all_components(&screen.layout.children)
+        .into_iter()
+        .filter_map(FlowComponent::input_name)
+        .collect()
+}
+
+fn has_data_field(screen: &FlowScreen, field: &str) -> bool {
+    screen.data.as_ref().is_some_and(|data| data.contains_key(field))
+}
+
+fn check_data_source(selection: &SelectionComponent, path: &str, violations: &mut Vec<Violation>) {
+    if let Dynamic::Value(items) = &selection.data_source {
+        if items.is_empty() {
+            violations.push(Violation::new(format!("{}.data-source", path), "must not be empty"));
+        }
+
+        let mut ids = BTreeSet::new();
+        for item in items {
+            if !ids.insert(item.id.as_str()) {
+                violations.push(Violation::new(format!("{}.data-source", path), format!("duplicate id {}", item.id)));
+            }
+        }
+    }
+}
+
+/// The component's properties, without the components nested in it.
+fn own_properties(component: &FlowComponent) -> Value {
+    let mut value = serde_json::to_value(component).unwrap_or(Value::Null);
+    if let Value::Object(map) = &mut value {
+        for key in NESTED_KEYS {
+            map.remove(*key);
+        }
+    }
+    value
+}
+
+/// Every `${...}` expression in the string values of `value`.
+fn expressions(value: &Value) -> Vec<String> {
+    let mut found = Vec::new();
+    match value {
+        Value::String(text) => {
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("${") {
+                let after = &rest[start + 2..];
+                match after.find('}') {
+                    Some(end) => {
+                        found.push(after[..end].to_string());
+                        rest = &after[end + 1..];
+                    }
+                    None => break,
+                }
+            }
+        }
+        Value::Array(items) => found.extend(items.iter().flat_map(expressions)),
+        Value::Object(map) => found.extend(map.values().flat_map(expressions)),
+        _ => {}
+    }
+    found
+}
+
+/// The `data.`, `form.` and `screen.` references in an expression.
+fn references(expression: &str) -> Vec<&str> {
+    expression
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .filter(|token| {
+            token.starts_with("data.") || token.starts_with("form.") || token.starts_with("screen.")
+        })
+        .collect()
+}
@@ -8,10 +8,29 @@ pub mod client;
 pub mod business;
 pub mod webhook;
 pub mod rate_limiter;
+pub mod broadcast;
+pub mod outbox;
+pub mod scheduler;
+pub mod template_cache;
+pub mod conversation_window;
+pub mod typing;
+pub mod dialog;
+pub mod media_download;
+pub mod flows;
 pub mod error;
 pub mod types;
 pub mod util;
+pub mod validation;
 
 pub use client::{WhatsAppClient, ClientConfig, create_client};
 pub use business::{BusinessClient, BusinessClientConfig, create_business_client};
-pub use webhook::{WebhookHandler, WebhookConfig, create_webhook_handler};
+pub use webhook::{WebhookHandler, WebhookConfig, WebhookRouter, create_webhook_handler};
+pub use template_cache::TemplateCache;
+pub use broadcast::{Broadcast, BroadcastHandle};
+pub use outbox::{Outbox, OutboxWorker};
+pub use scheduler::{JobStore, Scheduler};
+pub use conversation_window::ConversationWindow;
+pub use dialog::Dialog;
+pub use media_download::{MediaDownloader, MediaSink};
+pub use util::PhoneNumber;
+pub use validation::Validate;
@@ -0,0 +1,139 @@
+//! Media sink writing files to a directory
+
+use std::path::{Path, PathBuf};
+
+use tokio::fs::{self, File};
+use tokio::io::AsyncWriteExt;
+
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::media_download::{InboundMedia, MediaSink, MediaWriter};
+use crate::webhook::BoxFuture;
+
+
+/// Sink that writes each media object to `<directory>/<media id>.<extension>`.
+///
+/// The extension comes from the document's file name or else the MIME type.
+/// Bytes go to a `.part` file that is renamed once complete, so a file under
+/// its final name is never partial.
+#[derive(Debug, Clone)]
+pub struct FileSystemMediaSink {
+    directory: PathBuf,
+}
+
+impl FileSystemMediaSink {
+    /// Creates `directory` if it does not exist.
+    pub fn new(directory: impl Into<PathBuf>) -> WhatsAppResult<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory).map_err(storage_error)?;
+        Ok(Self { directory })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+}
+
+impl MediaSink for FileSystemMediaSink {
+    fn create<'a>(&'a self, media: &'a InboundMedia) -> BoxFuture<'a, WhatsAppResult<Box<dyn MediaWriter>>> {
+        Box::pin(async move {
+            let name = file_name(media);
+            let path = self.directory.join(&name);
+            let part = self.directory.join(format!("{}.part", name));
+            let file = File::create(&part).await.map_err(storage_error)?;
+
+            let writer: Box<dyn MediaWriter> = Box::new(FileSystemMediaWriter { file, part, path });
+            Ok(writer)
+        })
+    }
+}
+
+
+struct FileSystemMediaWriter {
+    file: File,
+    part: PathBuf,
+    path: PathBuf,
+}
+
+impl MediaWriter for FileSystemMediaWriter {
+    fn write<'a>(&'a mut self, chunk: &'a [u8]) -> BoxFuture<'a, WhatsAppResult<()>> {
+        Box::pin(async move { self.file.write_all(chunk).await.map_err(storage_error) })
+    }
+
+    fn finish(self: Box<Self>) -> BoxFuture<'static, WhatsAppResult<String>> {
+        Box::pin(async move {
+            let Self { mut file, part, path } = *self;
+            file.flush().await.map_err(storage_error)?;
+            file.sync_all().await.map_err(storage_error)?;
+            drop(file);
+            fs::rename(&part, &path).await.map_err(storage_error)?;
+            Ok(path.to_string_lossy().into_owned())
+        })
+    }
+
+    fn abort(self: Box<Self>) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            let Self { file, part, .. } = *self;
+            drop(file);
+            let _ = fs::remove_file(&part).await;
+        })
+    }
+}
+
+
+/// The media id, which the API guarantees to be unique, plus an extension.
+///
+/// Only alphanumeric extensions are kept, so a crafted file name cannot
+/// escape the directory.
+fn file_name(media: &InboundMedia) -> String {
+    let extension = media
+        .filename
+        .as_deref()
+        .and_then(|filename| Path::new(filename).extension())
+        .and_then(|extension| extension.to_str())
+        .map(str::to_string)
+        .or_else(|| media.mime_type.as_deref().and_then(mime_extension).map(str::to_string))
+        .filter(|extension| !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric()));
+
+    let id: String = media
+        .media_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect();
+
+    match extension {
+        Some(extension) => format!("{}.{}", id, extension.to_ascii_lowercase()),
+        None => id,
+    }
+}
+
+
+/// Extension for the MIME types the Cloud API accepts as media.
+fn mime_extension(mime_type: &str) -> Option<&'static str> {
+    let essence = mime_type.split(';').next().unwrap_or_default().trim();
+    Some(match essence {
+        "image/jpeg" => "jpg",
+        "image/png" => "png",
+        "image/webp" => "webp",
+        "audio/aac" => "aac",
+        "audio/amr" => "amr",
+        "audio/mpeg" => "mp3",
+        "audio/mp4" => "m4a",
+        "audio/ogg" => "ogg",
+        "video/3gpp" => "3gp",
+        "video/mp4" => "mp4",
+        "text/plain" => "txt",
+        "application/pdf" => "pdf",
+        "application/msword" => "doc",
+        "application/vnd.ms-excel" => "xls",
+        "application/vnd.ms-powerpoint" => "ppt",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "docx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => "xlsx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation" => "pptx",
+        _ => return None,
+    })
+}
+
+
+fn storage_error(error: std::io::Error) -> WhatsAppError {
+    WhatsAppError::StorageError(error.to_string())
+}
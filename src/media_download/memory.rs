@@ -0,0 +1,67 @@
+//! Media sink kept in memory
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::error::WhatsAppResult;
+use crate::media_download::{InboundMedia, MediaSink, MediaWriter};
+use crate::webhook::BoxFuture;
+
+
+/// Sink that keeps media bytes in memory, by media id.
+///
+/// Nothing is evicted; call `remove` once the media has been handled.
+#[derive(Debug, Default)]
+pub struct InMemoryMediaSink {
+    media: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl InMemoryMediaSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bytes stored at `location`, the media id.
+    pub fn get(&self, location: &str) -> Option<Vec<u8>> {
+        self.media.lock().unwrap().get(location).cloned()
+    }
+
+    pub fn remove(&self, location: &str) -> Option<Vec<u8>> {
+        self.media.lock().unwrap().remove(location)
+    }
+}
+
+impl MediaSink for InMemoryMediaSink {
+    fn create<'a>(&'a self, media: &'a InboundMedia) -> BoxFuture<'a, WhatsAppResult<Box<dyn MediaWriter>>> {
+        let writer: Box<dyn MediaWriter> = Box::new(InMemoryMediaWriter {
+            media: Arc::clone(&self.media),
+            media_id: media.media_id.clone(),
+            bytes: Vec::new(),
+        });
+        Box::pin(async move { Ok(writer) })
+    }
+}
+
+
+struct InMemoryMediaWriter {
+    media: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    media_id: String,
+    bytes: Vec<u8>,
+}
+
+impl MediaWriter for InMemoryMediaWriter {
+    fn write<'a>(&'a mut self, chunk: &'a [u8]) -> BoxFuture<'a, WhatsAppResult<()>> {
+        self.bytes.extend_from_slice(chunk);
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn finish(self: Box<Self>) -> BoxFuture<'static, WhatsAppResult<String>> {
+        let Self { media, media_id, bytes } = *self;
+        media.lock().unwrap().insert(media_id.clone(), bytes);
+        Box::pin(async move { Ok(media_id) })
+    }
+
+    fn abort(self: Box<Self>) -> BoxFuture<'static, ()> {
+        Box::pin(async {})
+    }
+}
@@ -0,0 +1,263 @@
+//! Automatic download of inbound media
+//!
+//! Webhooks for image, audio, video, document and sticker messages carry only
+//! a media id, and the URL it resolves to expires after five minutes. A
+//! [`MediaDownloader`] resolves the id with `WhatsAppClient::retrieve_media_url`
+//! as soon as the event arrives, streams the bytes to a [`MediaSink`] and
+//! attaches the result to the message as `WebhookMedia::stored`, with the
+//! SHA-256 of the bytes checked against the one in the webhook.
+//!
+//! Add the downloader to a `WebhookRouter` with `download_media`, so handlers
+//! see the stored media, or pass every webhook event to
+//! `MediaDownloader::handle_webhook_event` before handing it on.
+//! [`FileSystemMediaSink`] writes files to a directory and
+//! [`InMemoryMediaSink`] keeps the bytes in memory; implement [`MediaSink`] to
+//! store media anywhere else.
+
+pub mod fs;
+pub mod memory;
+
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use tokio::time::sleep;
+
+use crate::client::WhatsAppClient;
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::types::media::RetrieveMediaUrl;
+use crate::types::webhook::{
+    MediaChecksum, StoredMedia, WebhookChangeValue, WebhookEvent, WebhookMessage, WebhookMessageType,
+};
+use crate::webhook::BoxFuture;
+
+pub use fs::FileSystemMediaSink;
+pub use memory::InMemoryMediaSink;
+
+
+/// An inbound media object about to be stored.
+#[derive(Debug, Clone)]
+pub struct InboundMedia {
+
+    pub media_id: String,
+
+    /// Id of the message that carried the media.
+    pub message_id: String,
+
+    pub from: String,
+
+    pub kind: WebhookMessageType,
+
+    pub mime_type: Option<String>,
+
+    /// Original file name, sent with documents only.
+    pub filename: Option<String>,
+}
+
+
+/// Destination of downloaded media.
+pub trait MediaSink: Send + Sync {
+
+    /// Opens a writer for the bytes of `media`.
+    fn create<'a>(&'a self, media: &'a InboundMedia) -> BoxFuture<'a, WhatsAppResult<Box<dyn MediaWriter>>>;
+}
+
+
+/// Receives the bytes of one media object, in order.
+pub trait MediaWriter: Send {
+
+    fn write<'a>(&'a mut self, chunk: &'a [u8]) -> BoxFuture<'a, WhatsAppResult<()>>;
+
+    /// Completes the media after its last chunk and returns where it is stored.
+    fn finish(self: Box<Self>) -> BoxFuture<'static, WhatsAppResult<String>>;
+
+    /// Discards what was written, after the download failed.
+    fn abort(self: Box<Self>) -> BoxFuture<'static, ()>;
+}
+
+
+/// Media that could not be downloaded.
+#[derive(Debug)]
+pub struct MediaDownloadFailure {
+
+    pub message_id: String,
+
+    pub media_id: String,
+
+    pub error: WhatsAppError,
+}
+
+
+pub struct MediaDownloader {
+    client: WhatsAppClient,
+    sink: Arc<dyn MediaSink>,
+    max_size: Option<u64>,
+    stickers: bool,
+}
+
+impl MediaDownloader {
+
+    pub fn new(client: WhatsAppClient, sink: Arc<dyn MediaSink>) -> Self {
+        Self {
+            client,
+            sink,
+            max_size: None,
+            stickers: false,
+        }
+    }
+
+    /// Refuses media larger than `bytes`, which is then reported as a failure.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Downloads stickers as well; they are skipped by default.
+    pub fn stickers(mut self, stickers: bool) -> Self {
+        self.stickers = stickers;
+        self
+    }
+
+    pub fn sink(&self) -> &Arc<dyn MediaSink> {
+        &self.sink
+    }
+
+    /// Downloads the media of every inbound message in `event`, attaching
+    /// where each was stored to its `WebhookMedia::stored`.
+    ///
+    /// Media that fails to download is left without `stored` and reported in
+    /// the returned failures; the rest of the event is still processed.
+    pub async fn handle_webhook_event(&self, event: &mut WebhookEvent) -> Vec<MediaDownloadFailure> {
+        let mut failures = Vec::new();
+        for entry in &mut event.entry {
+            for change in &mut entry.changes {
+                if let WebhookChangeValue::Messages(value) = &mut change.value {
+                    for message in &mut value.messages {
+                        if let Err(error) = self.download(message).await {
+                            failures.push(MediaDownloadFailure {
+                                message_id: message.id.clone(),
+                                media_id: message.media().map(|media| media.id.clone()).unwrap_or_default(),
+                                error,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        failures
+    }
+
+    /// Downloads the media of `message` and attaches where it was stored.
+    ///
+    /// Messages without media, skipped stickers and media already stored are
+    /// left as they are.
+    pub async fn download(&self, message: &mut WebhookMessage) -> WhatsAppResult<()> {
+        if message.r#type == WebhookMessageType::Sticker && !self.stickers {
+            return Ok(());
+        }
+        let (message_id, from, kind) = (message.id.clone(), message.from.clone(), message.r#type.clone());
+        let media = match message.media_mut() {
+            Some(media) if media.stored.is_none() => media,
+            _ => return Ok(()),
+        };
+
+        let inbound = InboundMedia {
+            media_id: media.id.clone(),
+            message_id,
+            from,
+            kind,
+            mime_type: media.mime_type.clone(),
+            filename: media.filename.clone(),
+        };
+
+        let mut attempt = 0;
+        let stored = loop {
+            match self.fetch(&inbound, media.sha256.as_deref()).await {
+                Ok(stored) => break stored,
+                Err(error) => match self.client.rate_limiter().retry_delay(attempt, &error) {
+                    Some(delay) => {
+                        sleep(delay).await;
+                        attempt += 1;
+                    }
+                    None => return Err(error),
+                },
+            }
+        };
+
+        media.stored = Some(stored);
+        Ok(())
+    }
+
+    /// Resolves a fresh URL for `media` and streams its bytes to the sink.
+    async fn fetch(&self, media: &InboundMedia, expected_sha256: Option<&str>) -> WhatsAppResult<StoredMedia> {
+        let url = self
+            .client
+            .retrieve_media_url(RetrieveMediaUrl {
+                media_id: media.media_id.clone(),
+            })
+            .await?
+            .url;
+        let mut response = self.client.download_media(&url).await?;
+        if let (Some(max_size), Some(length)) = (self.max_size, response.content_length()) {
+            if length > max_size {
+                return Err(too_large(media, max_size));
+            }
+        }
+
+        let mut writer = self.sink.create(media).await?;
+        let mut hasher = Sha256::new();
+        let mut size = 0u64;
+
+        let streamed: WhatsAppResult<()> = async {
+            while let Some(chunk) = response.chunk().await? {
+                size += chunk.len() as u64;
+                if self.max_size.is_some_and(|max_size| size > max_size) {
+                    return Err(too_large(media, self.max_size.unwrap_or_default()));
+                }
+                hasher.update(&chunk);
+                writer.write(&chunk).await?;
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(error) = streamed {
+            writer.abort().await;
+            return Err(error);
+        }
+        let location = writer.finish().await?;
+
+        let digest = hasher.finalize();
+        Ok(StoredMedia {
+            location,
+            size,
+            sha256: hex::encode(digest),
+            checksum: checksum(&digest, expected_sha256),
+        })
+    }
+}
+
+
+fn too_large(media: &InboundMedia, max_size: u64) -> WhatsAppError {
+    WhatsAppError::Other(format!("media {} is larger than {} bytes", media.media_id, max_size))
+}
+
+
+/// Compares a digest with the webhook's hash, which may be hex or base64 encoded.
+fn checksum(digest: &[u8], expected: Option<&str>) -> MediaChecksum {
+    let expected = match expected {
+        Some(expected) if !expected.is_empty() => expected,
+        _ => return MediaChecksum::Unavailable,
+    };
+
+    let matches = hex::decode(expected).is_ok_and(|decoded| decoded == digest)
+        || BASE64.decode(expected).is_ok_and(|decoded| decoded == digest);
+    if matches {
+        MediaChecksum::Verified
+    } else {
+        MediaChecksum::Mismatch {
+            expected: expected.to_string(),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Outbox kept in memory
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::error::WhatsAppResult;
+use crate::outbox::{now_millis, Outbox, OutboxEntry, OutboxStatus};
+use crate::types::messages::OutboundMessage;
+
+
+/// Outbox that lives as long as the process; nothing survives a restart.
+#[derive(Debug, Default)]
+pub struct InMemoryOutbox {
+    entries: Mutex<HashMap<String, OutboxEntry>>,
+}
+
+impl InMemoryOutbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&self, key: &str, f: impl FnOnce(&mut OutboxEntry)) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(key) {
+            f(entry);
+            entry.updated_at = now_millis();
+        }
+    }
+}
+
+impl Outbox for InMemoryOutbox {
+    fn enqueue(&self, key: &str, message: &OutboundMessage) -> WhatsAppResult<OutboxEntry> {
+        let now = now_millis();
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(key.to_string()).or_insert_with(|| OutboxEntry {
+            key: key.to_string(),
+            message: message.clone(),
+            status: OutboxStatus::Pending,
+            message_id: None,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: now,
+            created_at: now,
+            updated_at: now,
+        });
+        Ok(entry.clone())
+    }
+
+    fn get(&self, key: &str) -> WhatsAppResult<Option<OutboxEntry>> {
+        Ok(self.entries.lock().unwrap().get(key).cloned())
+    }
+
+    fn due(&self, limit: usize) -> WhatsAppResult<Vec<OutboxEntry>> {
+        let now = now_millis();
+        let mut due: Vec<OutboxEntry> = self
+            .entries
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|entry| entry.status == OutboxStatus::Pending && entry.next_attempt_at <= now)
+            .cloned()
+            .collect();
+        due.sort_by_key(|entry| entry.created_at);
+        due.truncate(limit);
+        Ok(due)
+    }
+
+    fn claim(&self, key: &str) -> WhatsAppResult<bool> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get_mut(key) {
+            Some(entry) if entry.status == OutboxStatus::Pending => {
+                entry.status = OutboxStatus::Sending;
+                entry.attempts += 1;
+                entry.updated_at = now_millis();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn mark_sent(&self, key: &str, message_id: &str) -> WhatsAppResult<()> {
+        self.update(key, |entry| {
+            entry.status = OutboxStatus::Sent;
+            entry.message_id.get_or_insert_with(|| message_id.to_string());
+        });
+        Ok(())
+    }
+
+    fn mark_pending(&self, key: &str, error: &str, next_attempt_at: u64) -> WhatsAppResult<()> {
+        self.update(key, |entry| {
+            entry.status = OutboxStatus::Pending;
+            entry.last_error = Some(error.to_string());
+            entry.next_attempt_at = next_attempt_at;
+        });
+        Ok(())
+    }
+
+    fn mark_failed(&self, key: &str, error: &str) -> WhatsAppResult<()> {
+        self.update(key, |entry| {
+            entry.status = OutboxStatus::Failed;
+            entry.last_error = Some(error.to_string());
+        });
+        Ok(())
+    }
+
+    fn mark_unconfirmed(&self, key: &str, error: &str) -> WhatsAppResult<()> {
+        self.update(key, |entry| {
+            if entry.status == OutboxStatus::Sending {
+                entry.status = OutboxStatus::Unconfirmed;
+                entry.last_error = Some(error.to_string());
+            }
+        });
+        Ok(())
+    }
+
+    fn requeue(&self, key: &str) -> WhatsAppResult<bool> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get_mut(key) {
+            Some(entry) if matches!(entry.status, OutboxStatus::Unconfirmed | OutboxStatus::Failed) => {
+                let now = now_millis();
+                entry.status = OutboxStatus::Pending;
+                entry.next_attempt_at = now;
+                entry.updated_at = now;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn stale_sending(&self, before: u64) -> WhatsAppResult<Vec<OutboxEntry>> {
+        Ok(self
+            .entries
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|entry| entry.status == OutboxStatus::Sending && entry.updated_at < before)
+            .cloned()
+            .collect())
+    }
+
+    fn unconfirmed(&self, limit: usize) -> WhatsAppResult<Vec<OutboxEntry>> {
+        let mut unconfirmed: Vec<OutboxEntry> = self
+            .entries
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|entry| entry.status == OutboxStatus::Unconfirmed)
+            .cloned()
+            .collect();
+        unconfirmed.sort_by_key(|entry| entry.created_at);
+        unconfirmed.truncate(limit);
+        Ok(unconfirmed)
+    }
+}
@@ -0,0 +1,354 @@
+//! Durable outbox for sending each message once
+//!
+//! An [`Outbox`] stores a message under an idempotency key before it is sent
+//! and records the message id the API assigns once it is. An [`OutboxWorker`]
+//! sends stored messages with a `WhatsAppClient`, retrying retryable failures.
+//! Enqueuing a key that is already stored returns the stored entry instead of
+//! adding a second message, so replaying a request never sends twice.
+//!
+//! A crash or timeout after the API accepted a message but before its id was
+//! stored leaves the entry `Sending`. Every message is sent with its key as
+//! `biz_opaque_callback_data`, so the status webhooks for it settle the entry
+//! once passed to `OutboxWorker::handle_webhook_event`. Entries without a
+//! status after `confirmation_timeout` become `Unconfirmed`: the message may
+//! or may not have been delivered, so it is only sent again through
+//! `OutboxWorker::resend`, or automatically with `resend_unconfirmed(true)`.
+//!
+//! [`InMemoryOutbox`] suits tests and single runs; `SqliteOutbox`, behind the
+//! `sqlite` feature, survives restarts.
+
+pub mod memory;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+use crate::client::WhatsAppClient;
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::types::messages::OutboundMessage;
+use crate::types::webhook::{WebhookChangeValue, WebhookEvent, WebhookStatusType};
+use crate::validation::Validate;
+
+pub use memory::InMemoryOutbox;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteOutbox;
+
+const DEFAULT_BATCH_SIZE: usize = 50;
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+
+    /// Waiting to be sent, for the first time or again.
+    Pending,
+
+    /// Handed to the API without a known outcome yet.
+    Sending,
+
+    /// No outcome known after the confirmation timeout. A status webhook
+    /// still settles it; otherwise it waits for `OutboxWorker::resend` or
+    /// for an operator to mark it sent or failed.
+    Unconfirmed,
+
+    Sent,
+
+    /// Failed for good; it will not be retried.
+    Failed,
+}
+
+impl OutboxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Sending => "sending",
+            Self::Unconfirmed => "unconfirmed",
+            Self::Sent => "sent",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "pending" => Some(Self::Pending),
+            "sending" => Some(Self::Sending),
+            "unconfirmed" => Some(Self::Unconfirmed),
+            "sent" => Some(Self::Sent),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+
+/// A stored message. Times are Unix timestamps in milliseconds.
+#[derive(Debug, Clone)]
+pub struct OutboxEntry {
+
+    /// Idempotency key chosen by the caller.
+    pub key: String,
+
+    pub message: OutboundMessage,
+
+    pub status: OutboxStatus,
+
+    /// Id assigned by the API, once known.
+    pub message_id: Option<String>,
+
+    /// Number of times the message was handed to the API.
+    pub attempts: u32,
+
+    pub last_error: Option<String>,
+
+    /// Earliest time a pending entry may be sent.
+    pub next_attempt_at: u64,
+
+    pub created_at: u64,
+
+    pub updated_at: u64,
+}
+
+
+/// Storage of outbox entries.
+///
+/// Implementations must make `enqueue` and `claim` atomic, so that concurrent
+/// callers and workers never store or send a key twice.
+pub trait Outbox: Send + Sync {
+
+    /// Stores a pending entry, or returns the entry already stored under `key`
+    /// unchanged, whatever its message.
+    fn enqueue(&self, key: &str, message: &OutboundMessage) -> WhatsAppResult<OutboxEntry>;
+
+    fn get(&self, key: &str) -> WhatsAppResult<Option<OutboxEntry>>;
+
+    /// Pending entries whose `next_attempt_at` has passed, oldest first.
+    fn due(&self, limit: usize) -> WhatsAppResult<Vec<OutboxEntry>>;
+
+    /// Moves a pending entry to `Sending` and counts the attempt. Returns
+    /// `false` if the entry was not pending, e.g. because another worker claimed it.
+    fn claim(&self, key: &str) -> WhatsAppResult<bool>;
+
+    /// Records the message id. Entries already sent keep their first id, and
+    /// unknown keys are ignored.
+    fn mark_sent(&self, key: &str, message_id: &str) -> WhatsAppResult<()>;
+
+    /// Moves an entry back to `Pending`, to be sent at `next_attempt_at`.
+    fn mark_pending(&self, key: &str, error: &str, next_attempt_at: u64) -> WhatsAppResult<()>;
+
+    fn mark_failed(&self, key: &str, error: &str) -> WhatsAppResult<()>;
+
+    /// Moves a `Sending` entry to `Unconfirmed`; entries in any other status,
+    /// e.g. settled by a status webhook meanwhile, are left as they are.
+    fn mark_unconfirmed(&self, key: &str, error: &str) -> WhatsAppResult<()>;
+
+    /// Moves an `Unconfirmed` or `Failed` entry back to `Pending`, due now.
+    /// Returns `false` if the entry was in any other status.
+    fn requeue(&self, key: &str) -> WhatsAppResult<bool>;
+
+    /// Entries that have been `Sending` since before `before`.
+    fn stale_sending(&self, before: u64) -> WhatsAppResult<Vec<OutboxEntry>>;
+
+    /// `Unconfirmed` entries, oldest first.
+    fn unconfirmed(&self, limit: usize) -> WhatsAppResult<Vec<OutboxEntry>>;
+}
+
+
+/// Sends the messages of an [`Outbox`].
+pub struct OutboxWorker<O> {
+    client: WhatsAppClient,
+    outbox: Arc<O>,
+    batch_size: usize,
+    poll_interval: Duration,
+    confirmation_timeout: Duration,
+    resend_unconfirmed: bool,
+}
+
+impl<O: Outbox> OutboxWorker<O> {
+    /// Sends through `client`, paced and retried by its rate limiter settings.
+    pub fn new(client: WhatsAppClient, outbox: Arc<O>) -> Self {
+        Self {
+            client,
+            outbox,
+            batch_size: DEFAULT_BATCH_SIZE,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            confirmation_timeout: DEFAULT_CONFIRMATION_TIMEOUT,
+            resend_unconfirmed: false,
+        }
+    }
+
+    /// Maximum number of entries sent per pass.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// How long `run` waits after a pass that found nothing to send.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// How long a message may stay `Sending` without a status webhook before
+    /// it becomes `Unconfirmed`.
+    pub fn confirmation_timeout(mut self, confirmation_timeout: Duration) -> Self {
+        self.confirmation_timeout = confirmation_timeout;
+        self
+    }
+
+    /// Sends messages again once they time out instead of leaving them
+    /// `Unconfirmed`. A message that did reach the API is then delivered twice.
+    pub fn resend_unconfirmed(mut self, resend: bool) -> Self {
+        self.resend_unconfirmed = resend;
+        self
+    }
+
+    pub fn outbox(&self) -> &Arc<O> {
+        &self.outbox
+    }
+
+    /// Validates and stores a message for the next pass; see `Outbox::enqueue`.
+    pub fn enqueue(&self, key: &str, message: impl Into<OutboundMessage>) -> WhatsAppResult<OutboxEntry> {
+        let message = message.into();
+        message.validate()?;
+        self.outbox.enqueue(key, &message)
+    }
+
+    /// Stores a message and sends it right away if it was not stored before.
+    ///
+    /// Returns the entry as it stands afterwards; a replayed key returns the
+    /// stored entry, with the message id of the first send.
+    pub async fn send(&self, key: &str, message: impl Into<OutboundMessage>) -> WhatsAppResult<OutboxEntry> {
+        let entry = self.enqueue(key, message)?;
+        if entry.status == OutboxStatus::Pending && entry.attempts == 0 {
+            self.dispatch(entry).await
+        } else {
+            Ok(entry)
+        }
+    }
+
+    /// Sends an `Unconfirmed` or `Failed` entry again, accepting that an
+    /// unconfirmed message may then be delivered twice.
+    ///
+    /// Returns the entry as it stands afterwards; entries in any other status
+    /// are returned unchanged.
+    pub async fn resend(&self, key: &str) -> WhatsAppResult<OutboxEntry> {
+        self.outbox.requeue(key)?;
+        let entry = self
+            .outbox
+            .get(key)?
+            .ok_or_else(|| WhatsAppError::StorageError(format!("no outbox entry {}", key)))?;
+        if entry.status == OutboxStatus::Pending {
+            self.dispatch(entry).await
+        } else {
+            Ok(entry)
+        }
+    }
+
+    /// Times out entries left `Sending` and sends one batch of due entries.
+    /// Returns the number of entries handed to the API.
+    pub async fn dispatch_pending(&self) -> WhatsAppResult<usize> {
+        const NO_STATUS: &str = "no status received for the message";
+
+        let before = now_millis().saturating_sub(self.confirmation_timeout.as_millis() as u64);
+        for entry in self.outbox.stale_sending(before)? {
+            if self.resend_unconfirmed {
+                self.outbox.mark_pending(&entry.key, NO_STATUS, now_millis())?;
+            } else {
+                self.outbox.mark_unconfirmed(&entry.key, NO_STATUS)?;
+            }
+        }
+
+        let mut dispatched = 0;
+        for entry in self.outbox.due(self.batch_size)? {
+            let attempts = entry.attempts;
+            if self.dispatch(entry).await?.attempts > attempts {
+                dispatched += 1;
+            }
+        }
+        Ok(dispatched)
+    }
+
+    /// Sends due entries until a storage error occurs.
+    pub async fn run(&self) -> WhatsAppResult<()> {
+        loop {
+            if self.dispatch_pending().await? == 0 {
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        }
+    }
+
+    /// Settles outbox keys from their status webhooks: `sent`, `delivered`
+    /// and `read` record the message id, `failed` fails the entry with the
+    /// status error. Other statuses are ignored.
+    pub fn handle_webhook_event(&self, event: &WebhookEvent) -> WhatsAppResult<()> {
+        for change in event.changes() {
+            if let WebhookChangeValue::Messages(value) = &change.value {
+                for status in &value.statuses {
+                    let key = match &status.biz_opaque_callback_data {
+                        Some(key) => key,
+                        None => continue,
+                    };
+                    match status.status {
+                        WebhookStatusType::Sent | WebhookStatusType::Delivered | WebhookStatusType::Read => {
+                            self.outbox.mark_sent(key, &status.id)?;
+                        }
+                        WebhookStatusType::Failed => {
+                            let error = status
+                                .errors
+                                .first()
+                                .map(|error| format!("{} ({})", error.title, error.code))
+                                .unwrap_or_else(|| "the message failed to send".to_string());
+                            self.outbox.mark_failed(key, &error)?;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn dispatch(&self, entry: OutboxEntry) -> WhatsAppResult<OutboxEntry> {
+        if self.outbox.claim(&entry.key)? {
+            let rate_limiter = self.client.rate_limiter();
+            rate_limiter.acquire().await;
+
+            let result = self.client.with_callback_data(&entry.key).send(entry.message.clone()).await;
+            match result {
+                Ok(response) => {
+                    // Without an id the outcome is unknown, as after a timeout.
+                    if let Some(message) = response.messages.into_iter().next() {
+                        self.outbox.mark_sent(&entry.key, &message.id)?;
+                    }
+                }
+                // The request may have reached the API; leave it to a status
+                // webhook or the confirmation timeout.
+                Err(error) if error.is_unconfirmed() => {}
+                Err(error) => match rate_limiter.retry_delay(entry.attempts, &error) {
+                    Some(delay) => {
+                        let next_attempt_at = now_millis() + delay.as_millis() as u64;
+                        self.outbox.mark_pending(&entry.key, &error.to_string(), next_attempt_at)?;
+                    }
+                    None => self.outbox.mark_failed(&entry.key, &error.to_string())?,
+                },
+            }
+        }
+
+        self.outbox
+            .get(&entry.key)?
+            .ok_or_else(|| WhatsAppError::StorageError(format!("outbox entry {} disappeared", entry.key)))
+    }
+}
+
+
+pub(crate) fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,216 @@
+//! Outbox stored in a SQLite database
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::outbox::{now_millis, Outbox, OutboxEntry, OutboxStatus};
+use crate::types::messages::OutboundMessage;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS whatsapp_outbox (
+        key TEXT PRIMARY KEY,
+        message TEXT NOT NULL,
+        status TEXT NOT NULL,
+        message_id TEXT,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        last_error TEXT,
+        next_attempt_at INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS whatsapp_outbox_status
+        ON whatsapp_outbox (status, next_attempt_at);
+";
+
+const COLUMNS: &str =
+    "key, message, status, message_id, attempts, last_error, next_attempt_at, created_at, updated_at";
+
+
+/// Outbox in the `whatsapp_outbox` table of a SQLite database.
+#[derive(Debug)]
+pub struct SqliteOutbox {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteOutbox {
+    /// Opens or creates the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> WhatsAppResult<Self> {
+        Self::from_connection(Connection::open(path).map_err(storage_error)?)
+    }
+
+    pub fn open_in_memory() -> WhatsAppResult<Self> {
+        Self::from_connection(Connection::open_in_memory().map_err(storage_error)?)
+    }
+
+    /// Uses an existing connection, creating the table if needed.
+    pub fn from_connection(connection: Connection) -> WhatsAppResult<Self> {
+        connection.execute_batch(SCHEMA).map_err(storage_error)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn query(&self, filter: &str, params: impl rusqlite::Params) -> WhatsAppResult<Vec<OutboxEntry>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(&format!("SELECT {} FROM whatsapp_outbox {}", COLUMNS, filter))
+            .map_err(storage_error)?;
+        let rows = statement.query_map(params, read_row).map_err(storage_error)?;
+
+        rows.map(|row| row.map_err(storage_error)?).collect()
+    }
+
+    fn execute(&self, sql: &str, params: impl rusqlite::Params) -> WhatsAppResult<usize> {
+        self.connection.lock().unwrap().execute(sql, params).map_err(storage_error)
+    }
+}
+
+impl Outbox for SqliteOutbox {
+    fn enqueue(&self, key: &str, message: &OutboundMessage) -> WhatsAppResult<OutboxEntry> {
+        let now = now_millis() as i64;
+        self.execute(
+            "INSERT OR IGNORE INTO whatsapp_outbox
+                (key, message, status, attempts, next_attempt_at, created_at, updated_at)
+             VALUES (?1, ?2, ?3, 0, ?4, ?4, ?4)",
+            params![key, serde_json::to_string(message)?, OutboxStatus::Pending.as_str(), now],
+        )?;
+
+        self.get(key)?
+            .ok_or_else(|| WhatsAppError::StorageError(format!("outbox entry {} was not stored", key)))
+    }
+
+    fn get(&self, key: &str) -> WhatsAppResult<Option<OutboxEntry>> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .query_row(
+                &format!("SELECT {} FROM whatsapp_outbox WHERE key = ?1", COLUMNS),
+                params![key],
+                read_row,
+            )
+            .optional()
+            .map_err(storage_error)?
+            .transpose()
+    }
+
+    fn due(&self, limit: usize) -> WhatsAppResult<Vec<OutboxEntry>> {
+        self.query(
+            "WHERE status = ?1 AND next_attempt_at <= ?2 ORDER BY created_at LIMIT ?3",
+            params![OutboxStatus::Pending.as_str(), now_millis() as i64, limit as i64],
+        )
+    }
+
+    fn claim(&self, key: &str) -> WhatsAppResult<bool> {
+        let claimed = self.execute(
+            "UPDATE whatsapp_outbox SET status = ?1, attempts = attempts + 1, updated_at = ?2
+             WHERE key = ?3 AND status = ?4",
+            params![OutboxStatus::Sending.as_str(), now_millis() as i64, key, OutboxStatus::Pending.as_str()],
+        )?;
+        Ok(claimed == 1)
+    }
+
+    fn mark_sent(&self, key: &str, message_id: &str) -> WhatsAppResult<()> {
+        self.execute(
+            "UPDATE whatsapp_outbox SET status = ?1, message_id = COALESCE(message_id, ?2), updated_at = ?3
+             WHERE key = ?4",
+            params![OutboxStatus::Sent.as_str(), message_id, now_millis() as i64, key],
+        )?;
+        Ok(())
+    }
+
+    fn mark_pending(&self, key: &str, error: &str, next_attempt_at: u64) -> WhatsAppResult<()> {
+        self.execute(
+            "UPDATE whatsapp_outbox SET status = ?1, last_error = ?2, next_attempt_at = ?3, updated_at = ?4
+             WHERE key = ?5",
+            params![OutboxStatus::Pending.as_str(), error, next_attempt_at as i64, now_millis() as i64, key],
+        )?;
+        Ok(())
+    }
+
+    fn mark_failed(&self, key: &str, error: &str) -> WhatsAppResult<()> {
+        self.execute(
+            "UPDATE whatsapp_outbox SET status = ?1, last_error = ?2, updated_at = ?3 WHERE key = ?4",
+            params![OutboxStatus::Failed.as_str(), error, now_millis() as i64, key],
+        )?;
+        Ok(())
+    }
+
+    fn mark_unconfirmed(&self, key: &str, error: &str) -> WhatsAppResult<()> {
+        self.execute(
+            "UPDATE whatsapp_outbox SET status = ?1, last_error = ?2, updated_at = ?3 WHERE key = ?4 AND status = ?5",
+            params![
+                OutboxStatus::Unconfirmed.as_str(),
+                error,
+                now_millis() as i64,
+                key,
+                OutboxStatus::Sending.as_str()
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn requeue(&self, key: &str) -> WhatsAppResult<bool> {
+        let now = now_millis() as i64;
+        let requeued = self.execute(
+            "UPDATE whatsapp_outbox SET status = ?1, next_attempt_at = ?2, updated_at = ?2
+             WHERE key = ?3 AND status IN (?4, ?5)",
+            params![
+                OutboxStatus::Pending.as_str(),
+                now,
+                key,
+                OutboxStatus::Unconfirmed.as_str(),
+                OutboxStatus::Failed.as_str()
+            ],
+        )?;
+        Ok(requeued == 1)
+    }
+
+    fn stale_sending(&self, before: u64) -> WhatsAppResult<Vec<OutboxEntry>> {
+        self.query(
+            "WHERE status = ?1 AND updated_at < ?2",
+            params![OutboxStatus::Sending.as_str(), before.min(i64::MAX as u64) as i64],
+        )
+    }
+
+    fn unconfirmed(&self, limit: usize) -> WhatsAppResult<Vec<OutboxEntry>> {
+        self.query(
+            "WHERE status = ?1 ORDER BY created_at LIMIT ?2",
+            params![OutboxStatus::Unconfirmed.as_str(), limit as i64],
+        )
+    }
+}
+
+
+/// Reads a row selected with `COLUMNS`. The outer error is SQLite's, the inner
+/// one a row this version cannot read.
+fn read_row(row: &Row<'_>) -> rusqlite::Result<WhatsAppResult<OutboxEntry>> {
+    let message: String = row.get(1)?;
+    let status: String = row.get(2)?;
+
+    let message = match serde_json::from_str(&message) {
+        Ok(message) => message,
+        Err(error) => return Ok(Err(error.into())),
+    };
+    let status = match OutboxStatus::parse(&status) {
+        Some(status) => status,
+        None => return Ok(Err(WhatsAppError::StorageError(format!("unknown outbox status {}", status)))),
+    };
+
+    Ok(Ok(OutboxEntry {
+        key: row.get(0)?,
+        message,
+        status,
+        message_id: row.get(3)?,
+        attempts: row.get(4)?,
+        last_error: row.get(5)?,
+        next_attempt_at: row.get::<_, i64>(6)? as u64,
+        created_at: row.get::<_, i64>(7)? as u64,
+        updated_at: row.get::<_, i64>(8)? as u64,
+    }))
+}
+
+fn storage_error(error: rusqlite::Error) -> WhatsAppError {
+    WhatsAppError::StorageError(error.to_string())
+}
@@ -0,0 +1,70 @@
+//! Request pacing and retry policy
+//!
+//! [`RateLimiter`] spaces requests evenly so that no more than the configured
+//! number start per minute, however many tasks share it, and decides whether
+//! and when a failed request is worth retrying.
+
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::{sleep_until, Instant};
+
+use crate::error::{ErrorKind, WhatsAppError};
+
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+    retry_after_too_many_requests: bool,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl RateLimiter {
+
+    pub fn new(
+        max_requests_per_minute: u32,
+        retry_after_too_many_requests: bool,
+        max_retries: u32,
+        retry_delay_ms: u64,
+    ) -> Self {
+        Self {
+            interval: Duration::from_secs(60) / max_requests_per_minute.max(1),
+            next_slot: Mutex::new(Instant::now()),
+            retry_after_too_many_requests,
+            max_retries,
+            retry_delay: Duration::from_millis(retry_delay_ms),
+        }
+    }
+
+    /// Waits for the next free slot. Slots are handed out in call order.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        sleep_until(slot).await;
+    }
+
+    /// How long to wait before retry number `attempt` (starting at 0) of a
+    /// request that failed with `error`, or `None` if it should not be retried.
+    ///
+    /// Delays double with every attempt; `RateLimitExceeded` waits as long as
+    /// the API asked for.
+    pub fn retry_delay(&self, attempt: u32, error: &WhatsAppError) -> Option<Duration> {
+        if attempt >= self.max_retries || !error.is_retryable() {
+            return None;
+        }
+        if error.kind() == ErrorKind::RateLimited && !self.retry_after_too_many_requests {
+            return None;
+        }
+
+        match error {
+            WhatsAppError::RateLimitExceeded { retry_after_secs } => Some(Duration::from_secs(*retry_after_secs)),
+            _ => Some(self.retry_delay * 2u32.saturating_pow(attempt)),
+        }
+    }
+}
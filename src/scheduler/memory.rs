@@ -0,0 +1,115 @@
+//! Job store kept in memory
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+use crate::error::WhatsAppResult;
+use crate::scheduler::{JobStatus, JobStore, QuietHours, ScheduledJob};
+
+
+/// Job store that lives as long as the process; nothing survives a restart.
+#[derive(Debug, Default)]
+pub struct InMemoryJobStore {
+    jobs: Mutex<HashMap<String, ScheduledJob>>,
+    quiet_hours: Mutex<HashMap<String, QuietHours>>,
+}
+
+impl InMemoryJobStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn transition(&self, id: &str, from: JobStatus, to: JobStatus) -> bool {
+        match self.jobs.lock().unwrap().get_mut(id) {
+            Some(job) if job.status == from => {
+                job.status = to;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl JobStore for InMemoryJobStore {
+    fn insert(&self, job: &ScheduledJob) -> WhatsAppResult<ScheduledJob> {
+        let mut jobs = self.jobs.lock().unwrap();
+        Ok(jobs.entry(job.id.clone()).or_insert_with(|| job.clone()).clone())
+    }
+
+    fn get(&self, id: &str) -> WhatsAppResult<Option<ScheduledJob>> {
+        Ok(self.jobs.lock().unwrap().get(id).cloned())
+    }
+
+    fn due(&self, until: DateTime<Utc>, limit: usize) -> WhatsAppResult<Vec<ScheduledJob>> {
+        let mut due: Vec<ScheduledJob> = self
+            .jobs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|job| job.status == JobStatus::Scheduled && job.due_at <= until)
+            .cloned()
+            .collect();
+        due.sort_by_key(|job| job.due_at);
+        due.truncate(limit);
+        Ok(due)
+    }
+
+    fn next_due_at(&self) -> WhatsAppResult<Option<DateTime<Utc>>> {
+        Ok(self
+            .jobs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|job| job.status == JobStatus::Scheduled)
+            .map(|job| job.due_at)
+            .min())
+    }
+
+    fn claim(&self, id: &str, now: DateTime<Utc>) -> WhatsAppResult<bool> {
+        match self.jobs.lock().unwrap().get_mut(id) {
+            Some(job) if job.status == JobStatus::Scheduled => {
+                job.status = JobStatus::Sending;
+                job.claimed_at = Some(now);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn update(&self, job: &ScheduledJob) -> WhatsAppResult<()> {
+        if let Some(stored) = self.jobs.lock().unwrap().get_mut(&job.id) {
+            *stored = job.clone();
+        }
+        Ok(())
+    }
+
+    fn cancel(&self, id: &str) -> WhatsAppResult<bool> {
+        Ok(self.transition(id, JobStatus::Scheduled, JobStatus::Cancelled))
+    }
+
+    fn reset_interrupted(&self, claimed_before: DateTime<Utc>) -> WhatsAppResult<usize> {
+        let mut reset = 0;
+        for job in self.jobs.lock().unwrap().values_mut() {
+            if job.status == JobStatus::Sending && job.claimed_at.map_or(true, |at| at < claimed_before) {
+                job.status = JobStatus::Scheduled;
+                reset += 1;
+            }
+        }
+        Ok(reset)
+    }
+
+    fn quiet_hours(&self, wa_id: &str) -> WhatsAppResult<Option<QuietHours>> {
+        Ok(self.quiet_hours.lock().unwrap().get(wa_id).copied())
+    }
+
+    fn set_quiet_hours(&self, wa_id: &str, quiet_hours: Option<QuietHours>) -> WhatsAppResult<()> {
+        let mut stored = self.quiet_hours.lock().unwrap();
+        match quiet_hours {
+            Some(quiet_hours) => stored.insert(wa_id.to_string(), quiet_hours),
+            None => stored.remove(wa_id),
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,438 @@
+//! Scheduled delivery of template messages
+//!
+//! A [`Scheduler`] stores template messages in a [`JobStore`] with the time
+//! they are due, and sends them when a tokio timer fires. Jobs are kept in the
+//! store until sent, so a scheduler started again after a restart picks them
+//! up where the previous one left off. A job claimed for sending but not
+//! settled within the lease timeout, because its scheduler stopped mid-send,
+//! is sent again by whichever scheduler shares the store. A send that may
+//! have reached WhatsApp without a response confirming it, e.g. after a
+//! timeout, leaves the job `Unconfirmed` instead of retrying it. Jobs can be
+//! cancelled until they are sent.
+//!
+//! Due times can be given in the recipient's timezone, and [`QuietHours`] set
+//! for a recipient, kept in the store too, hold their messages back until the
+//! quiet hours end.
+
+pub mod memory;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Days, LocalResult, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::client::WhatsAppClient;
+use crate::error::WhatsAppResult;
+use crate::types::messages::SendTemplateMessage;
+use crate::util::phone::wa_id_key;
+use crate::validation::Validate;
+
+pub use memory::InMemoryJobStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteJobStore;
+
+const DEFAULT_BATCH_SIZE: usize = 50;
+const DEFAULT_LEASE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Longest the scheduler sleeps before checking the store again, so jobs
+/// added by other processes sharing the store are not missed for long.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// First wait after a storage error, doubled on every further one up to `MAX_SLEEP`.
+const ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+
+    Scheduled,
+
+    /// Being sent right now, or interrupted while it was; see `claimed_at`.
+    Sending,
+
+    Sent,
+
+    /// May have been sent: the send timed out or its response could not be
+    /// read. It is not sent again, so the recipient never gets it twice.
+    Unconfirmed,
+
+    /// Failed for good; it will not be retried.
+    Failed,
+
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Sending => "sending",
+            Self::Sent => "sent",
+            Self::Unconfirmed => "unconfirmed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "scheduled" => Some(Self::Scheduled),
+            "sending" => Some(Self::Sending),
+            "sent" => Some(Self::Sent),
+            "unconfirmed" => Some(Self::Unconfirmed),
+            "failed" => Some(Self::Failed),
+            "cancelled" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+
+/// Daily period in which a recipient should not receive messages, in their timezone.
+///
+/// A period whose end is before its start spans midnight, e.g. 21:00 to 08:00.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QuietHours {
+
+    pub start: NaiveTime,
+
+    pub end: NaiveTime,
+
+    pub timezone: Tz,
+}
+
+impl QuietHours {
+    pub fn new(start: NaiveTime, end: NaiveTime, timezone: Tz) -> Self {
+        Self { start, end, timezone }
+    }
+
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let time = at.with_timezone(&self.timezone).time();
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// The first time at or after `at` outside quiet hours.
+    ///
+    /// An end skipped by a daylight saving change is moved past the gap; an
+    /// end that occurs twice uses the first occurrence not before `at`.
+    pub fn next_allowed(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        if !self.contains(at) {
+            return at;
+        }
+
+        let local = at.with_timezone(&self.timezone);
+        let date = if local.time() < self.end {
+            local.date_naive()
+        } else {
+            local.date_naive() + Days::new(1)
+        };
+        let mut end = date.and_time(self.end);
+        loop {
+            match self.timezone.from_local_datetime(&end) {
+                LocalResult::Single(end) => return end.with_timezone(&Utc).max(at),
+                LocalResult::Ambiguous(first, second) => {
+                    let first = first.with_timezone(&Utc);
+                    return if first >= at { first } else { second.with_timezone(&Utc).max(at) };
+                }
+                LocalResult::None => end += chrono::Duration::minutes(15),
+            }
+        }
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct ScheduledJob {
+
+    /// Chosen by the caller, e.g. `appointment-1234-reminder`.
+    pub id: String,
+
+    pub message: SendTemplateMessage,
+
+    pub due_at: DateTime<Utc>,
+
+    /// The recipient's quiet hours when the job was scheduled.
+    pub quiet_hours: Option<QuietHours>,
+
+    pub status: JobStatus,
+
+    pub attempts: u32,
+
+    pub last_error: Option<String>,
+
+    pub message_id: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+
+    /// When the job was last claimed for sending.
+    pub claimed_at: Option<DateTime<Utc>>,
+}
+
+
+/// Storage of scheduled jobs.
+///
+/// Implementations must make `insert`, `claim` and `cancel` atomic, so that a
+/// job is never stored twice nor sent after it was cancelled.
+pub trait JobStore: Send + Sync {
+
+    /// Stores a job, or returns the job already stored under its id unchanged.
+    fn insert(&self, job: &ScheduledJob) -> WhatsAppResult<ScheduledJob>;
+
+    fn get(&self, id: &str) -> WhatsAppResult<Option<ScheduledJob>>;
+
+    /// Scheduled jobs due at or before `until`, earliest first.
+    fn due(&self, until: DateTime<Utc>, limit: usize) -> WhatsAppResult<Vec<ScheduledJob>>;
+
+    /// Due time of the earliest scheduled job.
+    fn next_due_at(&self) -> WhatsAppResult<Option<DateTime<Utc>>>;
+
+    /// Moves a scheduled job to `Sending` and sets `claimed_at` to `now`.
+    /// Returns `false` if it is no longer scheduled, e.g. because it was cancelled.
+    fn claim(&self, id: &str, now: DateTime<Utc>) -> WhatsAppResult<bool>;
+
+    /// Stores the status, due time, attempts and outcome of a claimed job.
+    fn update(&self, job: &ScheduledJob) -> WhatsAppResult<()>;
+
+    /// Cancels a scheduled job. Returns `false` if it is not scheduled.
+    fn cancel(&self, id: &str) -> WhatsAppResult<bool>;
+
+    /// Moves jobs claimed before `claimed_before` and still `Sending`, left so
+    /// by an interrupted run, back to `Scheduled`.
+    fn reset_interrupted(&self, claimed_before: DateTime<Utc>) -> WhatsAppResult<usize>;
+
+    /// Quiet hours of a recipient, keyed by `wa_id`.
+    fn quiet_hours(&self, wa_id: &str) -> WhatsAppResult<Option<QuietHours>>;
+
+    /// Sets or, with `None`, clears the quiet hours of a recipient.
+    fn set_quiet_hours(&self, wa_id: &str, quiet_hours: Option<QuietHours>) -> WhatsAppResult<()>;
+}
+
+
+pub struct Scheduler<S> {
+    client: WhatsAppClient,
+    store: Arc<S>,
+    wake: Notify,
+    batch_size: usize,
+    lease_timeout: Duration,
+}
+
+impl<S: JobStore + 'static> Scheduler<S> {
+    /// Sends through `client`, paced and retried by its rate limiter settings.
+    pub fn new(client: WhatsAppClient, store: Arc<S>) -> Self {
+        Self {
+            client,
+            store,
+            wake: Notify::new(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            lease_timeout: DEFAULT_LEASE_TIMEOUT,
+        }
+    }
+
+    /// Maximum number of jobs sent per wake-up.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// How long a job may stay claimed before it is taken to have been
+    /// interrupted and is sent again. Keep it above the longest send.
+    pub fn lease_timeout(mut self, lease_timeout: Duration) -> Self {
+        self.lease_timeout = lease_timeout;
+        self
+    }
+
+    pub fn store(&self) -> &Arc<S> {
+        &self.store
+    }
+
+    /// Sets the quiet hours of `to` for jobs scheduled from now on.
+    ///
+    /// Jobs keep the quiet hours they were scheduled with.
+    pub fn set_quiet_hours(&self, to: &str, quiet_hours: QuietHours) -> WhatsAppResult<()> {
+        self.store.set_quiet_hours(&wa_id_key(to), Some(quiet_hours))
+    }
+
+    pub fn clear_quiet_hours(&self, to: &str) -> WhatsAppResult<()> {
+        self.store.set_quiet_hours(&wa_id_key(to), None)
+    }
+
+    pub fn quiet_hours(&self, to: &str) -> WhatsAppResult<Option<QuietHours>> {
+        self.store.quiet_hours(&wa_id_key(to))
+    }
+
+    /// Schedules `message` for `due_at`, or for the end of the recipient's
+    /// quiet hours if it falls within them.
+    ///
+    /// Scheduling an id again returns the job already stored under it.
+    pub fn schedule(&self, id: &str, message: SendTemplateMessage, due_at: DateTime<Utc>) -> WhatsAppResult<ScheduledJob> {
+        message.validate()?;
+
+        let quiet_hours = self.quiet_hours(&message.to)?;
+        let job = ScheduledJob {
+            id: id.to_string(),
+            due_at: quiet_hours.map_or(due_at, |quiet_hours| quiet_hours.next_allowed(due_at)),
+            message,
+            quiet_hours,
+            status: JobStatus::Scheduled,
+            attempts: 0,
+            last_error: None,
+            message_id: None,
+            created_at: Utc::now(),
+            claimed_at: None,
+        };
+
+        let job = self.store.insert(&job)?;
+        self.wake.notify_one();
+        Ok(job)
+    }
+
+    /// Schedules `message` for a wall-clock time in `timezone`, usually the recipient's.
+    ///
+    /// A time skipped by a daylight saving change is moved past the gap; a
+    /// time that occurs twice uses the first occurrence.
+    pub fn schedule_local(
+        &self,
+        id: &str,
+        message: SendTemplateMessage,
+        due_at: NaiveDateTime,
+        timezone: Tz,
+    ) -> WhatsAppResult<ScheduledJob> {
+        self.schedule(id, message, resolve_local(timezone, due_at))
+    }
+
+    pub fn cancel(&self, id: &str) -> WhatsAppResult<bool> {
+        self.store.cancel(id)
+    }
+
+    pub fn job(&self, id: &str) -> WhatsAppResult<Option<ScheduledJob>> {
+        self.store.get(id)
+    }
+
+    /// Runs `run` on a tokio task.
+    pub fn start(self: &Arc<Self>) -> JoinHandle<()> {
+        let scheduler = Arc::clone(self);
+        tokio::spawn(async move { scheduler.run().await })
+    }
+
+    /// Sends jobs as they come due, forever.
+    ///
+    /// Storage errors are logged and retried after a growing backoff, so a
+    /// store that is briefly unavailable does not stop the scheduler.
+    pub async fn run(&self) {
+        let mut backoff = ERROR_BACKOFF;
+        loop {
+            let sleep = match self.tick().await {
+                Ok(sleep) => {
+                    backoff = ERROR_BACKOFF;
+                    sleep
+                }
+                Err(error) => {
+                    log::warn!("scheduler store failed, retrying in {:?}: {}", backoff, error);
+                    let sleep = backoff;
+                    backoff = (backoff * 2).min(MAX_SLEEP);
+                    sleep
+                }
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(sleep) => {}
+                _ = self.wake.notified() => {}
+            }
+        }
+    }
+
+    /// Fires due jobs and returns how long to sleep until the next one.
+    async fn tick(&self) -> WhatsAppResult<Duration> {
+        self.fire_due().await?;
+
+        Ok(match self.store.next_due_at()? {
+            Some(due_at) => (due_at - Utc::now()).to_std().unwrap_or(Duration::ZERO).min(MAX_SLEEP),
+            None => MAX_SLEEP,
+        })
+    }
+
+    /// Requeues jobs whose lease expired and sends one batch of due jobs.
+    pub async fn fire_due(&self) -> WhatsAppResult<()> {
+        let rate_limiter = self.client.rate_limiter();
+
+        let lease_timeout = chrono::Duration::from_std(self.lease_timeout).ok();
+        if let Some(claimed_before) = lease_timeout.and_then(|lease| Utc::now().checked_sub_signed(lease)) {
+            self.store.reset_interrupted(claimed_before)?;
+        }
+
+        for mut job in self.store.due(Utc::now(), self.batch_size)? {
+            let now = Utc::now();
+            if !self.store.claim(&job.id, now)? {
+                continue;
+            }
+            job.status = JobStatus::Sending;
+            job.claimed_at = Some(now);
+
+            // Quiet hours may have started while the job waited for a retry.
+            if let Some(quiet_hours) = job.quiet_hours {
+                let allowed = quiet_hours.next_allowed(Utc::now());
+                if allowed > Utc::now() {
+                    job.status = JobStatus::Scheduled;
+                    job.due_at = allowed;
+                    self.store.update(&job)?;
+                    continue;
+                }
+            }
+
+            rate_limiter.acquire().await;
+            let result = self.client.send_template_message(job.message.clone()).await;
+            let retries = job.attempts;
+            job.attempts += 1;
+
+            match result {
+                Ok(response) => {
+                    job.message_id = response.messages.into_iter().next().map(|m| m.id);
+                    job.status = match job.message_id {
+                        Some(_) => JobStatus::Sent,
+                        None => JobStatus::Unconfirmed,
+                    };
+                }
+                Err(error) if error.is_unconfirmed() => {
+                    job.last_error = Some(error.to_string());
+                    job.status = JobStatus::Unconfirmed;
+                }
+                Err(error) => {
+                    job.last_error = Some(error.to_string());
+                    match rate_limiter.retry_delay(retries, &error) {
+                        Some(delay) => {
+                            job.status = JobStatus::Scheduled;
+                            job.due_at = Utc::now() + delay;
+                        }
+                        None => job.status = JobStatus::Failed,
+                    }
+                }
+            }
+            self.store.update(&job)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+/// The instant a wall-clock time in `timezone` denotes, moving times skipped by
+/// a daylight saving change past the gap.
+fn resolve_local(timezone: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    let mut local = local;
+    loop {
+        match timezone.from_local_datetime(&local) {
+            LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => return at.with_timezone(&Utc),
+            LocalResult::None => local += chrono::Duration::minutes(15),
+        }
+    }
+}
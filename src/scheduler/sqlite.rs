@@ -0,0 +1,242 @@
+//! Job store in a SQLite database
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::scheduler::{JobStatus, JobStore, QuietHours, ScheduledJob};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS whatsapp_scheduled_jobs (
+        id TEXT PRIMARY KEY,
+        message TEXT NOT NULL,
+        due_at INTEGER NOT NULL,
+        quiet_hours TEXT,
+        status TEXT NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        last_error TEXT,
+        message_id TEXT,
+        created_at INTEGER NOT NULL,
+        claimed_at INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS whatsapp_scheduled_jobs_due
+        ON whatsapp_scheduled_jobs (status, due_at);
+    CREATE TABLE IF NOT EXISTS whatsapp_quiet_hours (
+        wa_id TEXT PRIMARY KEY,
+        quiet_hours TEXT NOT NULL
+    );
+";
+
+const COLUMNS: &str =
+    "id, message, due_at, quiet_hours, status, attempts, last_error, message_id, created_at, claimed_at";
+
+
+/// Job store in the `whatsapp_scheduled_jobs` table of a SQLite database, with
+/// quiet hours in `whatsapp_quiet_hours`. Times are stored as Unix timestamps
+/// in milliseconds.
+#[derive(Debug)]
+pub struct SqliteJobStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteJobStore {
+    /// Opens or creates the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> WhatsAppResult<Self> {
+        Self::from_connection(Connection::open(path).map_err(storage_error)?)
+    }
+
+    pub fn open_in_memory() -> WhatsAppResult<Self> {
+        Self::from_connection(Connection::open_in_memory().map_err(storage_error)?)
+    }
+
+    /// Uses an existing connection, creating the table if needed.
+    pub fn from_connection(connection: Connection) -> WhatsAppResult<Self> {
+        connection.execute_batch(SCHEMA).map_err(storage_error)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn execute(&self, sql: &str, params: impl rusqlite::Params) -> WhatsAppResult<usize> {
+        self.connection.lock().unwrap().execute(sql, params).map_err(storage_error)
+    }
+}
+
+impl JobStore for SqliteJobStore {
+    fn insert(&self, job: &ScheduledJob) -> WhatsAppResult<ScheduledJob> {
+        let quiet_hours = job.quiet_hours.map(|quiet_hours| serde_json::to_string(&quiet_hours)).transpose()?;
+        self.execute(
+            &format!("INSERT OR IGNORE INTO whatsapp_scheduled_jobs ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)", COLUMNS),
+            params![
+                job.id,
+                serde_json::to_string(&job.message)?,
+                job.due_at.timestamp_millis(),
+                quiet_hours,
+                job.status.as_str(),
+                job.attempts,
+                job.last_error,
+                job.message_id,
+                job.created_at.timestamp_millis(),
+                job.claimed_at.map(|claimed_at| claimed_at.timestamp_millis()),
+            ],
+        )?;
+
+        self.get(&job.id)?
+            .ok_or_else(|| WhatsAppError::StorageError(format!("scheduled job {} was not stored", job.id)))
+    }
+
+    fn get(&self, id: &str) -> WhatsAppResult<Option<ScheduledJob>> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .query_row(
+                &format!("SELECT {} FROM whatsapp_scheduled_jobs WHERE id = ?1", COLUMNS),
+                params![id],
+                read_row,
+            )
+            .optional()
+            .map_err(storage_error)?
+            .transpose()
+    }
+
+    fn due(&self, until: DateTime<Utc>, limit: usize) -> WhatsAppResult<Vec<ScheduledJob>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(&format!(
+                "SELECT {} FROM whatsapp_scheduled_jobs WHERE status = ?1 AND due_at <= ?2 ORDER BY due_at LIMIT ?3",
+                COLUMNS
+            ))
+            .map_err(storage_error)?;
+        let rows = statement
+            .query_map(
+                params![JobStatus::Scheduled.as_str(), until.timestamp_millis(), limit as i64],
+                read_row,
+            )
+            .map_err(storage_error)?;
+
+        rows.map(|row| row.map_err(storage_error)?).collect()
+    }
+
+    fn next_due_at(&self) -> WhatsAppResult<Option<DateTime<Utc>>> {
+        let connection = self.connection.lock().unwrap();
+        let due_at: Option<i64> = connection
+            .query_row(
+                "SELECT MIN(due_at) FROM whatsapp_scheduled_jobs WHERE status = ?1",
+                params![JobStatus::Scheduled.as_str()],
+                |row| row.get(0),
+            )
+            .map_err(storage_error)?;
+
+        Ok(due_at.and_then(DateTime::from_timestamp_millis))
+    }
+
+    fn claim(&self, id: &str, now: DateTime<Utc>) -> WhatsAppResult<bool> {
+        let claimed = self.execute(
+            "UPDATE whatsapp_scheduled_jobs SET status = ?1, claimed_at = ?2 WHERE id = ?3 AND status = ?4",
+            params![JobStatus::Sending.as_str(), now.timestamp_millis(), id, JobStatus::Scheduled.as_str()],
+        )?;
+        Ok(claimed == 1)
+    }
+
+    fn update(&self, job: &ScheduledJob) -> WhatsAppResult<()> {
+        self.execute(
+            "UPDATE whatsapp_scheduled_jobs
+             SET due_at = ?1, status = ?2, attempts = ?3, last_error = ?4, message_id = ?5
+             WHERE id = ?6",
+            params![
+                job.due_at.timestamp_millis(),
+                job.status.as_str(),
+                job.attempts,
+                job.last_error,
+                job.message_id,
+                job.id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn cancel(&self, id: &str) -> WhatsAppResult<bool> {
+        let cancelled = self.execute(
+            "UPDATE whatsapp_scheduled_jobs SET status = ?1 WHERE id = ?2 AND status = ?3",
+            params![JobStatus::Cancelled.as_str(), id, JobStatus::Scheduled.as_str()],
+        )?;
+        Ok(cancelled == 1)
+    }
+
+    fn reset_interrupted(&self, claimed_before: DateTime<Utc>) -> WhatsAppResult<usize> {
+        self.execute(
+            "UPDATE whatsapp_scheduled_jobs SET status = ?1
+             WHERE status = ?2 AND (claimed_at IS NULL OR claimed_at < ?3)",
+            params![
+                JobStatus::Scheduled.as_str(),
+                JobStatus::Sending.as_str(),
+                claimed_before.timestamp_millis()
+            ],
+        )
+    }
+
+    fn quiet_hours(&self, wa_id: &str) -> WhatsAppResult<Option<QuietHours>> {
+        let connection = self.connection.lock().unwrap();
+        let quiet_hours: Option<String> = connection
+            .query_row(
+                "SELECT quiet_hours FROM whatsapp_quiet_hours WHERE wa_id = ?1",
+                params![wa_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(storage_error)?;
+
+        Ok(quiet_hours.as_deref().map(serde_json::from_str).transpose()?)
+    }
+
+    fn set_quiet_hours(&self, wa_id: &str, quiet_hours: Option<QuietHours>) -> WhatsAppResult<()> {
+        match quiet_hours {
+            Some(quiet_hours) => self.execute(
+                "INSERT OR REPLACE INTO whatsapp_quiet_hours (wa_id, quiet_hours) VALUES (?1, ?2)",
+                params![wa_id, serde_json::to_string(&quiet_hours)?],
+            )?,
+            None => self.execute("DELETE FROM whatsapp_quiet_hours WHERE wa_id = ?1", params![wa_id])?,
+        };
+        Ok(())
+    }
+}
+
+
+/// Reads a row selected with `COLUMNS`. The outer error is SQLite's, the inner
+/// one a row this version cannot read.
+fn read_row(row: &Row<'_>) -> rusqlite::Result<WhatsAppResult<ScheduledJob>> {
+    let message: String = row.get(1)?;
+    let due_at: i64 = row.get(2)?;
+    let quiet_hours: Option<String> = row.get(3)?;
+    let status: String = row.get(4)?;
+    let created_at: i64 = row.get(8)?;
+    let claimed_at: Option<i64> = row.get(9)?;
+
+    let job = || -> WhatsAppResult<ScheduledJob> {
+        Ok(ScheduledJob {
+            id: row.get(0).map_err(storage_error)?,
+            message: serde_json::from_str(&message)?,
+            due_at: timestamp(due_at)?,
+            quiet_hours: quiet_hours.as_deref().map(serde_json::from_str).transpose()?,
+            status: JobStatus::parse(&status)
+                .ok_or_else(|| WhatsAppError::StorageError(format!("unknown job status {}", status)))?,
+            attempts: row.get(5).map_err(storage_error)?,
+            last_error: row.get(6).map_err(storage_error)?,
+            message_id: row.get(7).map_err(storage_error)?,
+            created_at: timestamp(created_at)?,
+            claimed_at: claimed_at.map(timestamp).transpose()?,
+        })
+    };
+    Ok(job())
+}
+
+fn timestamp(millis: i64) -> WhatsAppResult<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(millis)
+        .ok_or_else(|| WhatsAppError::StorageError(format!("timestamp {} out of range", millis)))
+}
+
+fn storage_error(error: rusqlite::Error) -> WhatsAppError {
+    WhatsAppError::StorageError(error.to_string())
+}
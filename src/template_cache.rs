@@ -0,0 +1,202 @@
+//! Local view of message template state
+//!
+//! The cache is filled from `get_templates` responses and kept current by the
+//! template webhook fields, so sends referencing a template that has been
+//! paused, rejected or removed can fail before reaching the API.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::types::templates::{Template, TemplateCategory, TemplateQualityScore, TemplateStatus};
+use crate::types::webhook::{
+    TemplateCategoryUpdate, TemplateQualityUpdate, TemplateStatusUpdate, WebhookChangeValue,
+    WebhookEvent,
+};
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedTemplate {
+
+    pub id: String,
+
+    pub name: String,
+
+    pub language: String,
+
+    pub status: TemplateStatus,
+
+    pub category: Option<TemplateCategory>,
+
+    pub quality_score: Option<TemplateQualityScore>,
+
+    /// Reason given by the last status update, if any.
+    pub reason: Option<String>,
+}
+
+
+#[derive(Debug, Default)]
+pub struct TemplateCache {
+    templates: RwLock<HashMap<(String, String), CachedTemplate>>,
+}
+
+impl TemplateCache {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str, language: &str) -> Option<CachedTemplate> {
+        self.templates
+            .read()
+            .unwrap()
+            .get(&(name.to_string(), language.to_string()))
+            .cloned()
+    }
+
+    pub fn insert(&self, template: &Template) {
+        let entry = CachedTemplate {
+            id: template.id.clone(),
+            name: template.name.clone(),
+            language: template.language.clone(),
+            status: template.status,
+            category: Some(template.category),
+            quality_score: template.quality_score.as_ref().map(|quality| quality.score),
+            reason: None,
+        };
+
+        self.templates
+            .write()
+            .unwrap()
+            .insert((entry.name.clone(), entry.language.clone()), entry);
+    }
+
+    pub fn insert_all<'a>(&self, templates: impl IntoIterator<Item = &'a Template>) {
+        for template in templates {
+            self.insert(template);
+        }
+    }
+
+    pub fn remove(&self, name: &str, language: &str) -> Option<CachedTemplate> {
+        self.templates
+            .write()
+            .unwrap()
+            .remove(&(name.to_string(), language.to_string()))
+    }
+
+    /// Removes the template with this id, which identifies a single language.
+    pub fn remove_by_id(&self, id: &str) -> Option<CachedTemplate> {
+        let mut templates = self.templates.write().unwrap();
+        let key = templates.iter().find(|(_, entry)| entry.id == id).map(|(key, _)| key.clone())?;
+        templates.remove(&key)
+    }
+
+    /// Removes every language of the named template.
+    pub fn remove_all(&self, name: &str) {
+        self.templates
+            .write()
+            .unwrap()
+            .retain(|(cached_name, _), _| cached_name != name);
+    }
+
+    pub fn clear(&self) {
+        self.templates.write().unwrap().clear();
+    }
+
+    /// Applies every template related change in a webhook event.
+    ///
+    /// Other fields are ignored, so this can be called with every event the
+    /// webhook endpoint receives.
+    pub fn handle_webhook_event(&self, event: &WebhookEvent) {
+        for change in event.changes() {
+            match &change.value {
+                WebhookChangeValue::MessageTemplateStatusUpdate(update) => {
+                    self.apply_status_update(update)
+                }
+                WebhookChangeValue::MessageTemplateQualityUpdate(update) => {
+                    self.apply_quality_update(update)
+                }
+                WebhookChangeValue::TemplateCategoryUpdate(update) => {
+                    self.apply_category_update(update)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn apply_status_update(&self, update: &TemplateStatusUpdate) {
+        let reason = update
+            .reason
+            .clone()
+            .filter(|reason| reason != "NONE");
+
+        self.update_or_insert(
+            update.message_template_id,
+            &update.message_template_name,
+            &update.message_template_language,
+            |entry| {
+                entry.status = update.event;
+                entry.reason = reason;
+            },
+        );
+    }
+
+    pub fn apply_quality_update(&self, update: &TemplateQualityUpdate) {
+        self.update_or_insert(
+            update.message_template_id,
+            &update.message_template_name,
+            &update.message_template_language,
+            |entry| entry.quality_score = Some(update.new_quality_score),
+        );
+    }
+
+    pub fn apply_category_update(&self, update: &TemplateCategoryUpdate) {
+        self.update_or_insert(
+            update.message_template_id,
+            &update.message_template_name,
+            &update.message_template_language,
+            |entry| entry.category = Some(update.new_category),
+        );
+    }
+
+    /// Fails with a `ValidationError` when the template is known to be unusable.
+    ///
+    /// Templates that are not in the cache are allowed through.
+    pub fn ensure_usable(&self, name: &str, language: &str) -> WhatsAppResult<()> {
+        match self.get(name, language) {
+            Some(entry) if !entry.status.is_usable() => {
+                let mut message = format!(
+                    "Template '{}' ({}) cannot be used for sending: status is {}",
+                    entry.name, entry.language, entry.status
+                );
+                if let Some(reason) = &entry.reason {
+                    message.push_str(&format!(" (reason: {})", reason));
+                }
+                Err(WhatsAppError::ValidationError(message))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn update_or_insert(
+        &self,
+        id: u64,
+        name: &str,
+        language: &str,
+        apply: impl FnOnce(&mut CachedTemplate),
+    ) {
+        let mut templates = self.templates.write().unwrap();
+        let entry = templates
+            .entry((name.to_string(), language.to_string()))
+            .or_insert_with(|| CachedTemplate {
+                id: id.to_string(),
+                name: name.to_string(),
+                language: language.to_string(),
+                status: TemplateStatus::Unknown,
+                category: None,
+                quality_score: None,
+                reason: None,
+            });
+        apply(entry);
+    }
+}
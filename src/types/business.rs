@@ -0,0 +1,411 @@
+//! Types for WhatsApp Business Account and Business Manager administration
+
+use serde::{Serialize, Deserialize};
+
+use crate::types::common::Paging;
+use crate::types::profile::RetrievePhoneNumbersResponse;
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BusinessInfo {
+
+    pub id: String,
+
+    pub name: String,
+
+    #[serde(default)]
+    pub verification_status: Option<String>,
+
+    #[serde(default)]
+    pub timezone_id: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateBusiness {
+
+    pub name: String,
+
+    pub vertical: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone_id: Option<u32>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateBusinessResponse {
+
+    pub id: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WhatsAppBusinessAccountResponse {
+
+    pub id: String,
+
+    #[serde(default)]
+    pub name: Option<String>,
+
+    #[serde(default)]
+    pub currency: Option<String>,
+
+    #[serde(default)]
+    pub timezone_id: Option<String>,
+
+    #[serde(default)]
+    pub message_template_namespace: Option<String>,
+
+    #[serde(default)]
+    pub account_review_status: Option<String>,
+
+    #[serde(default)]
+    pub business_verification_status: Option<String>,
+
+    /// Credential paying for the account's messages, such as a shared credit line.
+    #[serde(default)]
+    pub primary_funding_id: Option<String>,
+}
+
+
+/// Which of the business' WhatsApp Business Accounts to list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GetBusinessAssets {
+
+    /// Accounts owned by the business.
+    Owned,
+
+    /// Accounts shared with the business by its clients.
+    Client,
+}
+
+impl GetBusinessAssets {
+
+    pub fn edge(&self) -> &'static str {
+        match self {
+            GetBusinessAssets::Owned => "owned_whatsapp_business_accounts",
+            GetBusinessAssets::Client => "client_whatsapp_business_accounts",
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BusinessAssetsResponse {
+
+    pub data: Vec<WhatsAppBusinessAccountResponse>,
+
+    #[serde(default)]
+    pub paging: Option<Paging>,
+}
+
+
+/// Paging parameters for WhatsApp Business Account listings.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WhatsAppBusinessAccountParams {
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+
+pub type BusinessPhoneNumbersResponse = RetrievePhoneNumbersResponse;
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WhatsAppBusinessTask {
+
+    Manage,
+
+    Develop,
+
+    ManageTemplates,
+
+    ManagePhone,
+
+    ManagePhoneAssets,
+
+    ManageExtensions,
+
+    ViewCost,
+
+    ViewTemplates,
+
+    ViewPhoneAssets,
+
+    ViewInsights,
+
+    Messaging,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct AssignUserToWhatsAppBusiness {
+
+    /// Id of the business user or system user.
+    pub user: String,
+
+    pub tasks: Vec<WhatsAppBusinessTask>,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SystemUserRole {
+
+    Admin,
+
+    Employee,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SystemUser {
+
+    pub id: String,
+
+    pub name: String,
+
+    #[serde(default)]
+    pub role: Option<SystemUserRole>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SystemUsersResponse {
+
+    pub data: Vec<SystemUser>,
+
+    #[serde(default)]
+    pub paging: Option<Paging>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateSystemUser {
+
+    pub name: String,
+
+    pub role: SystemUserRole,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateSystemUserResponse {
+
+    pub id: String,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct GenerateSystemUserToken {
+
+    pub system_user_id: String,
+
+    /// App the token is issued for.
+    pub app_id: String,
+
+    /// Permissions such as `whatsapp_business_messaging` and `whatsapp_business_management`.
+    pub scopes: Vec<String>,
+
+    /// Request a token that expires after 60 days instead of a non-expiring one.
+    pub expires_in_60_days: bool,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccessTokenResponse {
+
+    pub access_token: String,
+
+    #[serde(default)]
+    pub token_type: Option<String>,
+
+    #[serde(default)]
+    pub expires_in: Option<u64>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtendedCredit {
+
+    pub id: String,
+
+    #[serde(default)]
+    pub legal_entity_name: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtendedCreditsResponse {
+
+    pub data: Vec<ExtendedCredit>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreditLineAllocationResponse {
+
+    pub allocation_config_id: String,
+
+    pub waba_id: String,
+}
+
+
+/// A credit line allocation config, as listed under the credit line.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreditAllocationConfig {
+
+    pub id: String,
+
+    /// The credential the credit line was shared as; a WABA it is attached to
+    /// has it as `primary_funding_id`.
+    #[serde(default)]
+    pub receiving_credential: Option<CreditAllocationCredential>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreditAllocationCredential {
+
+    pub id: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreditAllocationConfigsResponse {
+
+    pub data: Vec<CreditAllocationConfig>,
+}
+
+
+/// State of an Embedded Signup onboarding.
+///
+/// Every step records its outcome here, so a failed onboarding can be persisted
+/// and passed back to `BusinessClient::continue_embedded_signup`, which skips the
+/// steps that already succeeded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddedSignupProgress {
+
+    pub code: String,
+
+    pub waba_id: String,
+
+    pub phone_number_id: String,
+
+    /// Business integration token obtained from the code exchange.
+    pub business_token: Option<String>,
+
+    pub app_subscribed: bool,
+
+    pub phone_number_registered: bool,
+
+    pub credit_line_allocation_id: Option<String>,
+}
+
+impl EmbeddedSignupProgress {
+
+    pub fn new(code: &str, waba_id: &str, phone_number_id: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            waba_id: waba_id.to_string(),
+            phone_number_id: phone_number_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.business_token.is_some()
+            && self.app_subscribed
+            && self.phone_number_registered
+            && self.credit_line_allocation_id.is_some()
+    }
+}
+
+
+/// Options for subscribing an app to a WhatsApp Business Account's webhooks.
+///
+/// `override_callback_uri` and `verify_token` must be set together, and replace
+/// the app's callback URL for this account only.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SubscribeApp {
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub override_callback_uri: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_token: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscribedAppData {
+
+    pub id: String,
+
+    #[serde(default)]
+    pub name: Option<String>,
+
+    #[serde(default)]
+    pub link: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscribedApp {
+
+    pub whatsapp_business_api_data: SubscribedAppData,
+
+    #[serde(default)]
+    pub override_callback_uri: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscribedAppsResponse {
+
+    pub data: Vec<SubscribedApp>,
+}
+
+
+/// Callback URL override for a single phone number.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookOverride {
+
+    pub override_callback_uri: String,
+
+    pub verify_token: String,
+}
+
+
+/// Callback URLs that apply to a phone number, from the most to the least specific.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfiguration {
+
+    #[serde(default)]
+    pub phone_number: Option<String>,
+
+    #[serde(default)]
+    pub whatsapp_business_account: Option<String>,
+
+    #[serde(default)]
+    pub application: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PhoneNumberWebhookConfigurationResponse {
+
+    pub id: String,
+
+    #[serde(default)]
+    pub webhook_configuration: Option<WebhookConfiguration>,
+}
@@ -0,0 +1,33 @@
+//! Types shared by several Graph API endpoints
+
+use serde::Deserialize;
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Paging {
+
+    #[serde(default)]
+    pub cursors: Option<PagingCursors>,
+
+    #[serde(default)]
+    pub next: Option<String>,
+
+    #[serde(default)]
+    pub previous: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PagingCursors {
+
+    pub before: String,
+
+    pub after: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SuccessResponse {
+
+    pub success: bool,
+}
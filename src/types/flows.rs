@@ -0,0 +1,188 @@
+//! Types for managing WhatsApp Flows
+//!
+//! A Flow is created as a draft, receives its Flow JSON as an uploaded asset,
+//! and can only be sent to users once published. Published Flows can be
+//! deprecated but not edited or deleted.
+
+use serde::{Serialize, Deserialize};
+
+use crate::types::common::Paging;
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FlowStatus {
+
+    Draft,
+
+    Published,
+
+    Deprecated,
+
+    Blocked,
+
+    Throttled,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FlowCategory {
+
+    SignUp,
+
+    SignIn,
+
+    AppointmentBooking,
+
+    LeadGeneration,
+
+    ContactUs,
+
+    CustomerSupport,
+
+    Survey,
+
+    /// Also stands for categories added to the API after this version.
+    #[serde(other)]
+    Other,
+}
+
+
+/// A problem the API found in a Flow's JSON.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlowValidationError {
+
+    pub error: String,
+
+    pub error_type: String,
+
+    pub message: String,
+
+    pub line_start: Option<u32>,
+
+    pub line_end: Option<u32>,
+
+    pub column_start: Option<u32>,
+
+    pub column_end: Option<u32>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlowPreview {
+
+    pub preview_url: String,
+
+    /// ISO 8601 time after which the preview URL stops working.
+    pub expires_at: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Flow {
+
+    pub id: String,
+
+    pub name: String,
+
+    pub status: FlowStatus,
+
+    #[serde(default)]
+    pub categories: Vec<FlowCategory>,
+
+    #[serde(default)]
+    pub validation_errors: Vec<FlowValidationError>,
+
+    pub json_version: Option<String>,
+
+    pub data_api_version: Option<String>,
+
+    pub endpoint_uri: Option<String>,
+
+    pub preview: Option<FlowPreview>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetFlowsResponse {
+
+    pub data: Vec<Flow>,
+
+    pub paging: Option<Paging>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateFlow {
+
+    pub name: String,
+
+    pub categories: Vec<FlowCategory>,
+
+    /// Starts the new Flow from a copy of an existing one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clone_flow_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint_uri: Option<String>,
+
+    /// Flow JSON to upload right away, as a string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flow_json: Option<String>,
+
+    /// Publishes the Flow on creation; requires a valid `flow_json`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publish: Option<bool>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateFlowResponse {
+
+    pub id: String,
+
+    pub success: Option<bool>,
+
+    #[serde(default)]
+    pub validation_errors: Vec<FlowValidationError>,
+}
+
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateFlowMetadata {
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub categories: Option<Vec<FlowCategory>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint_uri: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateFlowJsonResponse {
+
+    pub success: bool,
+
+    #[serde(default)]
+    pub validation_errors: Vec<FlowValidationError>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlowPreviewResponse {
+
+    pub id: String,
+
+    pub preview: FlowPreview,
+}
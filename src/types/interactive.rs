@@ -0,0 +1,596 @@
+//! Fluent builders for interactive messages
+//!
+//! Each constructor on [`Interactive`] starts a builder for one kind of
+//! interactive message. The builder only offers the headers, footers and
+//! action items the API accepts for that kind, and `build` is only available
+//! once a body has been set.
+
+use std::marker::PhantomData;
+
+use serde_json::Value;
+
+use crate::types::messages::*;
+use crate::util::PhoneNumber;
+
+/// Builder state before a body has been set.
+#[derive(Debug, Clone, Copy)]
+pub struct NoBody;
+
+/// Builder state once a body has been set.
+#[derive(Debug, Clone, Copy)]
+pub struct WithBody;
+
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A kind of interactive message and the action data collected for it.
+pub trait InteractiveKind: sealed::Sealed {
+    fn into_action(self) -> (InteractiveType, InteractiveAction);
+}
+
+/// Kinds that accept a text header.
+pub trait TextHeader: sealed::Sealed {}
+
+/// Kinds that accept an image, video or document header.
+pub trait MediaHeader: sealed::Sealed {}
+
+/// Kinds that accept a footer.
+pub trait Footer: sealed::Sealed {}
+
+
+#[derive(Debug, Clone)]
+pub struct InteractiveBuilder<K, B = NoBody> {
+    kind: K,
+    body: Option<String>,
+    header: Option<InteractiveHeader>,
+    footer: Option<String>,
+    state: PhantomData<B>,
+}
+
+impl<K: sealed::Sealed> InteractiveBuilder<K, NoBody> {
+    fn new(kind: K) -> Self {
+        Self {
+            kind,
+            body: None,
+            header: None,
+            footer: None,
+            state: PhantomData,
+        }
+    }
+
+    pub fn body(self, text: impl Into<String>) -> InteractiveBuilder<K, WithBody> {
+        InteractiveBuilder {
+            kind: self.kind,
+            body: Some(text.into()),
+            header: self.header,
+            footer: self.footer,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<K, B> InteractiveBuilder<K, B> {
+    fn with_kind<L>(self, kind: L) -> InteractiveBuilder<L, B> {
+        InteractiveBuilder {
+            kind,
+            body: self.body,
+            header: self.header,
+            footer: self.footer,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<K: TextHeader, B> InteractiveBuilder<K, B> {
+    pub fn header_text(mut self, text: impl Into<String>) -> Self {
+        self.header = Some(InteractiveHeader::Text { text: text.into() });
+        self
+    }
+}
+
+impl<K: MediaHeader, B> InteractiveBuilder<K, B> {
+    pub fn header_image(mut self, link: impl Into<String>) -> Self {
+        self.header = Some(InteractiveHeader::Image {
+            image: InteractiveHeaderMedia { link: link.into() },
+        });
+        self
+    }
+
+    pub fn header_video(mut self, link: impl Into<String>) -> Self {
+        self.header = Some(InteractiveHeader::Video {
+            video: InteractiveHeaderMedia { link: link.into() },
+        });
+        self
+    }
+
+    pub fn header_document(mut self, link: impl Into<String>, filename: Option<&str>) -> Self {
+        self.header = Some(InteractiveHeader::Document {
+            document: InteractiveHeaderDocument {
+                link: link.into(),
+                filename: filename.map(str::to_string),
+            },
+        });
+        self
+    }
+}
+
+impl<K: Footer, B> InteractiveBuilder<K, B> {
+    pub fn footer(mut self, text: impl Into<String>) -> Self {
+        self.footer = Some(text.into());
+        self
+    }
+}
+
+impl<K: InteractiveKind> InteractiveBuilder<K, WithBody> {
+    pub fn build(self) -> Interactive {
+        let (r#type, action) = self.kind.into_action();
+
+        Interactive {
+            r#type,
+            body: InteractiveBody {
+                text: self.body.unwrap_or_default(),
+            },
+            action,
+            header: self.header,
+            footer: self.footer.map(|text| InteractiveFooter { text }),
+            flow: None,
+        }
+    }
+
+    /// Builds the message and addresses it to `to`.
+    pub fn send_to(self, to: impl Into<PhoneNumber>) -> SendInteractiveMessage {
+        SendInteractiveMessage {
+            to: String::from(to.into()),
+            interactive: self.build(),
+            context: None,
+        }
+    }
+}
+
+
+/// Number of reply buttons added so far, tracked in the type so that only
+/// messages with one to three buttons can be built.
+pub trait ButtonCount: sealed::Sealed {}
+
+/// A count that allows one more button.
+pub trait BelowMaxButtons: ButtonCount {
+    type Next: ButtonCount;
+}
+
+/// A count of at least one button.
+pub trait SomeButtons: ButtonCount {}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoButtons;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OneButton;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TwoButtons;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreeButtons;
+
+impl sealed::Sealed for NoButtons {}
+impl sealed::Sealed for OneButton {}
+impl sealed::Sealed for TwoButtons {}
+impl sealed::Sealed for ThreeButtons {}
+impl ButtonCount for NoButtons {}
+impl ButtonCount for OneButton {}
+impl ButtonCount for TwoButtons {}
+impl ButtonCount for ThreeButtons {}
+impl BelowMaxButtons for NoButtons {
+    type Next = OneButton;
+}
+impl BelowMaxButtons for OneButton {
+    type Next = TwoButtons;
+}
+impl BelowMaxButtons for TwoButtons {
+    type Next = ThreeButtons;
+}
+impl SomeButtons for OneButton {}
+impl SomeButtons for TwoButtons {}
+impl SomeButtons for ThreeButtons {}
+
+
+/// One to three reply buttons.
+///
+/// Messages without buttons or with more than three do not compile:
+///
+/// ```compile_fail
+/// use whatsapp_cloud_sdk::types::messages::Interactive;
+///
+/// Interactive::buttons().body("Pick one").build();
+/// ```
+///
+/// ```compile_fail
+/// use whatsapp_cloud_sdk::types::messages::Interactive;
+///
+/// Interactive::buttons()
+///     .body("Pick one")
+///     .reply_button("a", "A")
+///     .reply_button("b", "B")
+///     .reply_button("c", "C")
+///     .reply_button("d", "D");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ButtonsKind<N = NoButtons> {
+    buttons: Vec<InteractiveButton>,
+    count: PhantomData<N>,
+}
+
+impl<N: ButtonCount> sealed::Sealed for ButtonsKind<N> {}
+impl<N: ButtonCount> TextHeader for ButtonsKind<N> {}
+impl<N: ButtonCount> MediaHeader for ButtonsKind<N> {}
+impl<N: ButtonCount> Footer for ButtonsKind<N> {}
+
+impl<N: SomeButtons> InteractiveKind for ButtonsKind<N> {
+    fn into_action(self) -> (InteractiveType, InteractiveAction) {
+        (
+            InteractiveType::Button,
+            InteractiveAction::Buttons(InteractiveButtons { buttons: self.buttons }),
+        )
+    }
+}
+
+impl<N: BelowMaxButtons, B> InteractiveBuilder<ButtonsKind<N>, B> {
+    pub fn reply_button(
+        mut self,
+        id: impl Into<String>,
+        title: impl Into<String>,
+    ) -> InteractiveBuilder<ButtonsKind<N::Next>, B> {
+        let mut buttons = std::mem::take(&mut self.kind.buttons);
+        buttons.push(InteractiveButton {
+            r#type: InteractiveButtonType::Reply,
+            reply: Some(InteractiveButtonReply {
+                id: id.into(),
+                title: title.into(),
+            }),
+            url: None,
+            title: None,
+        });
+        self.with_kind(ButtonsKind {
+            buttons,
+            count: PhantomData,
+        })
+    }
+}
+
+
+/// A list of rows opened by a button.
+#[derive(Debug, Clone)]
+pub struct ListKind {
+    button: String,
+    sections: Vec<InteractiveSection>,
+}
+
+impl sealed::Sealed for ListKind {}
+impl TextHeader for ListKind {}
+impl Footer for ListKind {}
+
+impl InteractiveKind for ListKind {
+    fn into_action(self) -> (InteractiveType, InteractiveAction) {
+        (
+            InteractiveType::List,
+            InteractiveAction::Sections(InteractiveSections {
+                button: self.button,
+                sections: self.sections,
+            }),
+        )
+    }
+}
+
+impl<B> InteractiveBuilder<ListKind, B> {
+    /// Starts a new section; following rows are added to it.
+    pub fn section(mut self, title: impl Into<String>) -> Self {
+        self.kind.sections.push(InteractiveSection {
+            title: Some(title.into()),
+            rows: Vec::new(),
+        });
+        self
+    }
+
+    /// Adds a row to the current section, starting an untitled one if needed.
+    pub fn row(mut self, id: impl Into<String>, title: impl Into<String>, description: Option<&str>) -> Self {
+        if self.kind.sections.is_empty() {
+            self.kind.sections.push(InteractiveSection {
+                title: None,
+                rows: Vec::new(),
+            });
+        }
+        if let Some(section) = self.kind.sections.last_mut() {
+            section.rows.push(InteractiveSectionRow {
+                id: id.into(),
+                title: title.into(),
+                description: description.map(str::to_string),
+            });
+        }
+        self
+    }
+}
+
+
+/// A single button that opens a URL.
+#[derive(Debug, Clone)]
+pub struct CtaUrlKind {
+    display_text: String,
+    url: String,
+}
+
+impl sealed::Sealed for CtaUrlKind {}
+impl TextHeader for CtaUrlKind {}
+impl MediaHeader for CtaUrlKind {}
+impl Footer for CtaUrlKind {}
+
+impl InteractiveKind for CtaUrlKind {
+    fn into_action(self) -> (InteractiveType, InteractiveAction) {
+        (
+            InteractiveType::CtaUrl,
+            InteractiveAction::CtaUrl(InteractiveCtaUrlAction {
+                name: "cta_url".to_string(),
+                parameters: InteractiveCtaUrlButton {
+                    display_text: self.display_text,
+                    url: self.url,
+                },
+            }),
+        )
+    }
+}
+
+
+/// A button that opens a published Flow.
+#[derive(Debug, Clone)]
+pub struct FlowKind {
+    flow_id: String,
+    version: Option<String>,
+    flow_cta: String,
+    flow_token: Option<String>,
+    flow_action: Option<FlowAction>,
+    payload: Option<InteractiveFlowData>,
+    mode: Option<FlowMode>,
+}
+
+impl sealed::Sealed for FlowKind {}
+impl TextHeader for FlowKind {}
+impl Footer for FlowKind {}
+
+impl InteractiveKind for FlowKind {
+    fn into_action(self) -> (InteractiveType, InteractiveAction) {
+        (
+            InteractiveType::Flow,
+            InteractiveAction::Flow(InteractiveFlowAction {
+                name: "flow".to_string(),
+                parameters: InteractiveFlowParameters {
+                    flow_message_version: self.version.unwrap_or_else(|| FLOW_MESSAGE_VERSION.to_string()),
+                    flow_token: self.flow_token,
+                    flow_id: self.flow_id,
+                    flow_cta: self.flow_cta,
+                    flow_action: self.flow_action,
+                    flow_action_payload: self.payload,
+                    mode: self.mode,
+                },
+            }),
+        )
+    }
+}
+
+impl<B> InteractiveBuilder<FlowKind, B> {
+    /// Flow message format version; `FLOW_MESSAGE_VERSION` if not set.
+    pub fn flow_message_version(mut self, version: impl Into<String>) -> Self {
+        self.kind.version = Some(version.into());
+        self
+    }
+
+    /// Token echoed back in the Flow's responses; required with `data_exchange`.
+    pub fn flow_token(mut self, token: impl Into<String>) -> Self {
+        self.kind.flow_token = Some(token.into());
+        self
+    }
+
+    /// Opens the Flow on `screen`, passing it `data`.
+    pub fn navigate(mut self, screen: impl Into<String>, data: Option<Value>) -> Self {
+        self.kind.flow_action = Some(FlowAction::Navigate);
+        self.kind.payload = Some(InteractiveFlowData {
+            screen: Some(screen.into()),
+            data,
+        });
+        self
+    }
+
+    /// Asks the Flow's data endpoint for the first screen.
+    pub fn data_exchange(mut self) -> Self {
+        self.kind.flow_action = Some(FlowAction::DataExchange);
+        self.kind.payload = None;
+        self
+    }
+
+    /// Sends the Flow's current draft, for testing before it is published.
+    pub fn draft(mut self) -> Self {
+        self.kind.mode = Some(FlowMode::Draft);
+        self
+    }
+}
+
+
+/// A single product from a catalog.
+#[derive(Debug, Clone)]
+pub struct ProductKind {
+    catalog_id: String,
+    product_retailer_id: String,
+}
+
+impl sealed::Sealed for ProductKind {}
+impl Footer for ProductKind {}
+
+impl InteractiveKind for ProductKind {
+    fn into_action(self) -> (InteractiveType, InteractiveAction) {
+        (
+            InteractiveType::Product,
+            InteractiveAction::Product(InteractiveProduct {
+                catalog_id: self.catalog_id,
+                product_retailer_id: self.product_retailer_id,
+            }),
+        )
+    }
+}
+
+
+/// Sections of products from a catalog. The API requires a text header, so
+/// it is given to the constructor.
+#[derive(Debug, Clone)]
+pub struct ProductListKind {
+    catalog_id: String,
+    sections: Vec<InteractiveProductSection>,
+}
+
+impl sealed::Sealed for ProductListKind {}
+impl Footer for ProductListKind {}
+
+impl InteractiveKind for ProductListKind {
+    fn into_action(self) -> (InteractiveType, InteractiveAction) {
+        (
+            InteractiveType::ProductList,
+            InteractiveAction::ProductList(InteractiveProductList {
+                catalog_id: self.catalog_id,
+                sections: self.sections,
+            }),
+        )
+    }
+}
+
+impl<B> InteractiveBuilder<ProductListKind, B> {
+    pub fn section<I, S>(mut self, title: impl Into<String>, product_retailer_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.kind.sections.push(InteractiveProductSection {
+            title: title.into(),
+            product_items: product_retailer_ids
+                .into_iter()
+                .map(|id| InteractiveProductItem {
+                    product_retailer_id: id.into(),
+                })
+                .collect(),
+        });
+        self
+    }
+}
+
+
+/// The business's whole catalog.
+#[derive(Debug, Clone, Default)]
+pub struct CatalogKind {
+    thumbnail_product_retailer_id: Option<String>,
+}
+
+impl sealed::Sealed for CatalogKind {}
+impl Footer for CatalogKind {}
+
+impl InteractiveKind for CatalogKind {
+    fn into_action(self) -> (InteractiveType, InteractiveAction) {
+        (
+            InteractiveType::CatalogMessage,
+            InteractiveAction::Catalog(InteractiveCatalog {
+                name: "catalog_message".to_string(),
+                parameters: self
+                    .thumbnail_product_retailer_id
+                    .map(|id| InteractiveCatalogParameters {
+                        thumbnail_product_retailer_id: id,
+                    }),
+            }),
+        )
+    }
+}
+
+impl<B> InteractiveBuilder<CatalogKind, B> {
+    pub fn thumbnail(mut self, product_retailer_id: impl Into<String>) -> Self {
+        self.kind.thumbnail_product_retailer_id = Some(product_retailer_id.into());
+        self
+    }
+}
+
+
+/// Asks the user to share their location.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocationRequestKind;
+
+impl sealed::Sealed for LocationRequestKind {}
+
+impl InteractiveKind for LocationRequestKind {
+    fn into_action(self) -> (InteractiveType, InteractiveAction) {
+        (
+            InteractiveType::LocationRequestMessage,
+            InteractiveAction::LocationRequest(InteractiveLocationRequestAction {
+                name: "send_location".to_string(),
+            }),
+        )
+    }
+}
+
+
+impl Interactive {
+    pub fn buttons() -> InteractiveBuilder<ButtonsKind> {
+        InteractiveBuilder::new(ButtonsKind::default())
+    }
+
+    /// `button` is the label of the button that opens the list.
+    pub fn list(button: impl Into<String>) -> InteractiveBuilder<ListKind> {
+        InteractiveBuilder::new(ListKind {
+            button: button.into(),
+            sections: Vec::new(),
+        })
+    }
+
+    pub fn cta_url(display_text: impl Into<String>, url: impl Into<String>) -> InteractiveBuilder<CtaUrlKind> {
+        InteractiveBuilder::new(CtaUrlKind {
+            display_text: display_text.into(),
+            url: url.into(),
+        })
+    }
+
+    pub fn flow(flow_id: impl Into<String>, flow_cta: impl Into<String>) -> InteractiveBuilder<FlowKind> {
+        InteractiveBuilder::new(FlowKind {
+            flow_id: flow_id.into(),
+            version: None,
+            flow_cta: flow_cta.into(),
+            flow_token: None,
+            flow_action: None,
+            payload: None,
+            mode: None,
+        })
+    }
+
+    pub fn product(
+        catalog_id: impl Into<String>,
+        product_retailer_id: impl Into<String>,
+    ) -> InteractiveBuilder<ProductKind> {
+        InteractiveBuilder::new(ProductKind {
+            catalog_id: catalog_id.into(),
+            product_retailer_id: product_retailer_id.into(),
+        })
+    }
+
+    pub fn product_list(
+        catalog_id: impl Into<String>,
+        header: impl Into<String>,
+    ) -> InteractiveBuilder<ProductListKind> {
+        let mut builder = InteractiveBuilder::new(ProductListKind {
+            catalog_id: catalog_id.into(),
+            sections: Vec::new(),
+        });
+        builder.header = Some(InteractiveHeader::Text { text: header.into() });
+        builder
+    }
+
+    pub fn catalog() -> InteractiveBuilder<CatalogKind> {
+        InteractiveBuilder::new(CatalogKind::default())
+    }
+
+    pub fn location_request() -> InteractiveBuilder<LocationRequestKind> {
+        InteractiveBuilder::new(LocationRequestKind)
+    }
+}
@@ -0,0 +1,73 @@
+//! Types for uploading, retrieving and deleting media
+
+use serde::{Deserialize, Deserializer};
+
+
+/// A local file to upload for use in media messages.
+#[derive(Debug, Clone)]
+pub struct UploadMedia {
+
+    pub file_path: String,
+
+    /// MIME type of the file, e.g. `image/jpeg`.
+    pub mime_type: String,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct RetrieveMediaUrl {
+
+    pub media_id: String,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct DeleteMedia {
+
+    pub media_id: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadMediaResponse {
+
+    pub id: String,
+}
+
+
+/// Download URL of a media object. The URL expires after five minutes and
+/// must be fetched with the access token.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetrieveMediaUrlResponse {
+
+    pub id: String,
+
+    pub url: String,
+
+    pub mime_type: Option<String>,
+
+    /// SHA-256 of the media, hex encoded.
+    pub sha256: Option<String>,
+
+    /// Size in bytes; the API sends it as a number or a string.
+    #[serde(default, deserialize_with = "deserialize_file_size")]
+    pub file_size: Option<u64>,
+
+    pub messaging_product: Option<String>,
+}
+
+
+fn deserialize_file_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum FileSize {
+        Number(u64),
+        Text(String),
+    }
+
+    Ok(match Option::<FileSize>::deserialize(deserializer)? {
+        Some(FileSize::Number(size)) => Some(size),
+        Some(FileSize::Text(size)) => size.parse().ok(),
+        None => None,
+    })
+}
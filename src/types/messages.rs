@@ -2,6 +2,9 @@
 
 use serde::{Serialize, Deserialize};
 
+use crate::types::webhook::WebhookMessage;
+use crate::util::PhoneNumber;
+
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -18,8 +21,21 @@ pub enum MediaType {
     Video,
 }
 
+impl MediaType {
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaType::Audio => "audio",
+            MediaType::Document => "document",
+            MediaType::Image => "image",
+            MediaType::Sticker => "sticker",
+            MediaType::Video => "video",
+        }
+    }
+}
+
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendTextMessage {
 
     pub to: String,
@@ -28,10 +44,13 @@ pub struct SendTextMessage {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub preview_url: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<MessageContext>,
 }
 
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendMediaMessage {
   
     pub to: String,
@@ -49,10 +68,13 @@ pub struct SendMediaMessage {
    
     #[serde(skip_serializing_if = "Option::is_none")]
     pub filename: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<MessageContext>,
 }
 
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendLocationMessage {
   
     pub to: String,
@@ -66,6 +88,9 @@ pub struct SendLocationMessage {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub address: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<MessageContext>,
 }
 
 
@@ -168,7 +193,7 @@ pub struct Component {
 }
 
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendTemplateMessage {
   
     pub to: String,
@@ -179,6 +204,9 @@ pub struct SendTemplateMessage {
  
     #[serde(skip_serializing_if = "Option::is_none")]
     pub components: Option<Vec<Component>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<MessageContext>,
 }
 
 
@@ -215,6 +243,10 @@ pub enum InteractiveType {
     CtaUrl,
  
     LocationRequestMessage,
+
+    CatalogMessage,
+
+    AddressMessage,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -288,22 +320,105 @@ pub struct InteractiveSection {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InteractiveSections {
+
+    /// Label of the button that opens the list.
+    pub button: String,
  
     pub sections: Vec<InteractiveSection>,
 }
 
 
+/// Action of a single product message.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InteractiveProductRetailers {
-    
-    pub product_retailers: Vec<String>,
+pub struct InteractiveProduct {
+
+    pub catalog_id: String,
+
+    pub product_retailer_id: String,
 }
 
 
+/// Action of a catalog message, which opens the business's whole catalog.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InteractiveCatalog {
-  
+
+    /// Always `catalog_message`.
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<InteractiveCatalogParameters>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveCatalogParameters {
+
+    /// Product shown as the message thumbnail; the first product if omitted.
+    pub thumbnail_product_retailer_id: String,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveProductItem {
+
+    pub product_retailer_id: String,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveProductSection {
+
+    pub title: String,
+
+    pub product_items: Vec<InteractiveProductItem>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveProductList {
+
     pub catalog_id: String,
+
+    pub sections: Vec<InteractiveProductSection>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveCtaUrlAction {
+
+    /// Always `cta_url`.
+    pub name: String,
+
+    pub parameters: InteractiveCtaUrlButton,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveAddressAction {
+
+    /// Always `address_message`.
+    pub name: String,
+
+    pub parameters: InteractiveAddressParameters,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveAddressParameters {
+
+    /// ISO 3166-1 alpha-2 code of the country the address form is for.
+    pub country: String,
+
+    /// Values to prefill the form with, keyed by field name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<serde_json::Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved_addresses: Option<Vec<serde_json::Value>>,
+
+    /// Errors to show next to fields, keyed by field name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_errors: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -327,7 +442,12 @@ pub struct InteractiveFlowData {
 }
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The `action` object of an interactive message.
+///
+/// Serialized without a tag. Deserialization picks the variant from the
+/// action's `name`, or from its distinguishing fields when it has none, so an
+/// action is never read as a different variant that happens to fit.
+#[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 pub enum InteractiveAction {
  
@@ -335,41 +455,119 @@ pub enum InteractiveAction {
  
     Sections(InteractiveSections),
 
-    ProductRetailers(InteractiveProductRetailers),
+    Product(InteractiveProduct),
  
     Catalog(InteractiveCatalog),
 
+    ProductList(InteractiveProductList),
+
     Flow(InteractiveFlowAction),
 
+    CtaUrl(InteractiveCtaUrlAction),
+
     LocationRequest(InteractiveLocationRequestAction),
+
+    Address(InteractiveAddressAction),
+}
+
+impl<'de> Deserialize<'de> for InteractiveAction {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let has = |field: &str| value.get(field).is_some();
+        let name = value.get("name").and_then(|name| name.as_str());
+
+        let action = match name {
+            Some("flow") => serde_json::from_value(value).map(InteractiveAction::Flow),
+            Some("cta_url") => serde_json::from_value(value).map(InteractiveAction::CtaUrl),
+            Some("send_location") => serde_json::from_value(value).map(InteractiveAction::LocationRequest),
+            Some("catalog_message") => serde_json::from_value(value).map(InteractiveAction::Catalog),
+            Some("address_message") => serde_json::from_value(value).map(InteractiveAction::Address),
+            Some(other) => return Err(D::Error::custom(format!("unknown interactive action `{}`", other))),
+            None if has("buttons") => serde_json::from_value(value).map(InteractiveAction::Buttons),
+            None if has("catalog_id") && has("sections") => {
+                serde_json::from_value(value).map(InteractiveAction::ProductList)
+            }
+            None if has("sections") => serde_json::from_value(value).map(InteractiveAction::Sections),
+            None if has("product_retailer_id") => serde_json::from_value(value).map(InteractiveAction::Product),
+            None => return Err(D::Error::custom("unrecognized interactive action")),
+        };
+
+        action.map_err(D::Error::custom)
+    }
 }
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InteractiveFlowAction {
 
+    /// Always `flow`.
     pub name: String,
 
     pub parameters: InteractiveFlowParameters,
 }
 
 
+/// Version of the Flow message format sent unless another one is requested.
+pub const FLOW_MESSAGE_VERSION: &str = "3";
+
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InteractiveFlowParameters {
 
-    pub flow_token: String,
+    pub flow_message_version: String,
+
+    /// Echoed back in the Flow's responses; required by `data_exchange` Flows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flow_token: Option<String>,
  
     pub flow_id: String,
     
     pub flow_cta: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flow_action: Option<FlowAction>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flow_action_payload: Option<InteractiveFlowData>,
+
+    /// Set to `draft` to send a Flow that has not been published yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<FlowMode>,
+}
+
+
+/// How a Flow message opens its first screen.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowAction {
+
+    /// Opens the screen named in `flow_action_payload`.
+    Navigate,
+
+    /// Asks the Flow's data endpoint for the first screen.
+    DataExchange,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowMode {
+
+    Draft,
+
+    Published,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InteractiveLocationRequestAction {
 
+    /// Always `send_location`.
     pub name: String,
- 
-    pub button: String,
 }
 
 
@@ -387,7 +585,7 @@ pub enum InteractiveHeaderType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type")]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum InteractiveHeader {
   
     Text {
@@ -453,12 +651,15 @@ pub struct Interactive {
 }
 
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendInteractiveMessage {
 
     pub to: String,
    
     pub interactive: Interactive,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<MessageContext>,
 }
 
 
@@ -577,30 +778,134 @@ pub struct Contact {
 }
 
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendContactMessage {
     
     pub to: String,
   
     pub contacts: Vec<Contact>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<MessageContext>,
 }
 
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendReactionMessage {
   
     pub to: String,
   
+    /// The message reacted to.
     pub message_id: String,
 
+    /// A single emoji, or empty to remove the reaction.
     pub emoji: String,
 }
 
+impl SendReactionMessage {
+    pub fn new(to: impl Into<PhoneNumber>, message_id: &str, emoji: &str) -> Self {
+        Self {
+            to: String::from(to.into()),
+            message_id: message_id.to_string(),
+            emoji: emoji.to_string(),
+        }
+    }
+
+    /// Removes the reaction previously sent to `message_id`.
+    pub fn remove(to: impl Into<PhoneNumber>, message_id: &str) -> Self {
+        Self::new(to, message_id, "")
+    }
+
+    pub fn is_removal(&self) -> bool {
+        self.emoji.is_empty()
+    }
+}
+
+
+/// Any message the client sends to a single recipient, e.g. for storing it before it is sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutboundMessage {
+
+    Text(SendTextMessage),
+
+    Media(SendMediaMessage),
+
+    Location(SendLocationMessage),
+
+    Template(SendTemplateMessage),
+
+    Interactive(Box<SendInteractiveMessage>),
+
+    Contact(SendContactMessage),
+
+    Reaction(SendReactionMessage),
+}
+
+impl OutboundMessage {
+    pub fn to(&self) -> &str {
+        match self {
+            Self::Text(message) => &message.to,
+            Self::Media(message) => &message.to,
+            Self::Location(message) => &message.to,
+            Self::Template(message) => &message.to,
+            Self::Interactive(message) => &message.to,
+            Self::Contact(message) => &message.to,
+            Self::Reaction(message) => &message.to,
+        }
+    }
+}
+
+macro_rules! impl_from_outbound {
+    ($($message:ident => $variant:ident),+ $(,)?) => {
+        $(impl From<$message> for OutboundMessage {
+            fn from(message: $message) -> Self {
+                Self::$variant(message.into())
+            }
+        })+
+    };
+}
+
+impl_from_outbound!(
+    SendTextMessage => Text,
+    SendMediaMessage => Media,
+    SendLocationMessage => Location,
+    SendTemplateMessage => Template,
+    SendInteractiveMessage => Interactive,
+    SendContactMessage => Contact,
+    SendReactionMessage => Reaction,
+);
+
 
 #[derive(Debug, Clone, Serialize)]
 pub struct MarkMessageAsRead {
     
     pub message_id: String,
+
+    /// Also shows a typing indicator in the chat, for up to 25 seconds or until a reply is sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typing_indicator: Option<TypingIndicator>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TypingIndicator {
+
+    pub r#type: TypingIndicatorType,
+}
+
+impl TypingIndicator {
+    pub fn text() -> Self {
+        Self { r#type: TypingIndicatorType::Text }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TypingIndicatorType {
+
+    Text,
 }
 
 
@@ -612,6 +917,11 @@ pub struct SendMessageResponse {
     pub contacts: Vec<MessageResponseContact>,
    
     pub messages: Vec<MessageResponseMessage>,
+
+    /// Name of the template sent instead of the requested message, because
+    /// the customer service window was closed. The requested message was not sent.
+    #[serde(skip)]
+    pub fallback_template: Option<String>,
 }
 
 
@@ -631,71 +941,154 @@ pub struct MessageResponseMessage {
 }
 
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone)]
 pub struct SendAddressMessage {
  
     pub to: String,
-    
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub request_type: Option<String>,
-  
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub button_text: Option<String>,
+
+    pub body: String,
+
+    pub parameters: InteractiveAddressParameters,
+
+    pub context: Option<MessageContext>,
 }
 
+impl From<SendAddressMessage> for SendInteractiveMessage {
+    fn from(message: SendAddressMessage) -> Self {
+        SendInteractiveMessage {
+            to: message.to,
+            context: message.context,
+            interactive: Interactive {
+                r#type: InteractiveType::AddressMessage,
+                body: InteractiveBody { text: message.body },
+                action: InteractiveAction::Address(InteractiveAddressAction {
+                    name: "address_message".to_string(),
+                    parameters: message.parameters,
+                }),
+                header: None,
+                footer: None,
+                flow: None,
+            },
+        }
+    }
+}
 
-#[derive(Debug, Clone, Serialize)]
+
+/// The single button of a call-to-action URL message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InteractiveCtaUrlButton {
   
-    pub r#type: String,
-
-    pub title: String,
+    pub display_text: String,
    
     pub url: String,
 }
 
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone)]
 pub struct SendInteractiveCtaUrlButtonMessage {
     
     pub to: String,
   
     pub body: String,
   
-    pub buttons: Vec<InteractiveCtaUrlButton>,
+    pub button: InteractiveCtaUrlButton,
     
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub header_text: Option<String>,
    
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub footer_text: Option<String>,
+
+    pub context: Option<MessageContext>,
 }
 
+impl From<SendInteractiveCtaUrlButtonMessage> for SendInteractiveMessage {
+    fn from(message: SendInteractiveCtaUrlButtonMessage) -> Self {
+        SendInteractiveMessage {
+            to: message.to,
+            context: message.context,
+            interactive: Interactive {
+                r#type: InteractiveType::CtaUrl,
+                body: InteractiveBody { text: message.body },
+                action: InteractiveAction::CtaUrl(InteractiveCtaUrlAction {
+                    name: "cta_url".to_string(),
+                    parameters: message.button,
+                }),
+                header: message.header_text.map(|text| InteractiveHeader::Text { text }),
+                footer: message.footer_text.map(|text| InteractiveFooter { text }),
+                flow: None,
+            },
+        }
+    }
+}
 
-#[derive(Debug, Clone, Serialize)]
+
+/// Parameters of a Flow message, sent with `WhatsAppClient::send_flow_message`.
+#[derive(Debug, Clone)]
 pub struct InteractiveFlowParams {
    
     pub to: String,
   
     pub flow_id: String,
+
+    /// Label of the button that opens the Flow.
+    pub flow_cta: String,
+
+    pub body: String,
+
+    pub header: Option<String>,
+
+    pub footer: Option<String>,
  
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub flow_token: Option<String>,
- 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub flow_cta: Option<String>,
-    
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub flow_title: Option<String>,
-   
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub flow_description: Option<String>,
+
+    /// Defaults to `navigate` when a `screen` is given.
+    pub flow_action: Option<FlowAction>,
     
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub screen: Option<String>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<serde_json::Value>,
+
+    pub mode: Option<FlowMode>,
+
+    pub context: Option<MessageContext>,
+}
+
+impl From<InteractiveFlowParams> for SendInteractiveMessage {
+    fn from(params: InteractiveFlowParams) -> Self {
+        let flow_action = params
+            .flow_action
+            .or_else(|| params.screen.as_ref().map(|_| FlowAction::Navigate));
+        let flow_action_payload = match flow_action {
+            Some(FlowAction::Navigate) => Some(InteractiveFlowData {
+                screen: params.screen,
+                data: params.data,
+            }),
+            _ => None,
+        };
+
+        SendInteractiveMessage {
+            to: params.to,
+            context: params.context,
+            interactive: Interactive {
+                r#type: InteractiveType::Flow,
+                body: InteractiveBody { text: params.body },
+                action: InteractiveAction::Flow(InteractiveFlowAction {
+                    name: "flow".to_string(),
+                    parameters: InteractiveFlowParameters {
+                        flow_message_version: FLOW_MESSAGE_VERSION.to_string(),
+                        flow_token: params.flow_token,
+                        flow_id: params.flow_id,
+                        flow_cta: params.flow_cta,
+                        flow_action,
+                        flow_action_payload,
+                        mode: params.mode,
+                    },
+                }),
+                header: params.header.map(|text| InteractiveHeader::Text { text }),
+                footer: params.footer.map(|text| InteractiveFooter { text }),
+                flow: None,
+            },
+        }
+    }
 }
 
 
@@ -706,16 +1099,90 @@ pub struct SendInteractiveLocationRequestParams {
    
     pub body: String,
  
+    /// Not sent; WhatsApp labels the button itself.
+    #[deprecated(note = "WhatsApp labels the location request button itself; this is ignored")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub button_text: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub footer_text: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<MessageContext>,
 }
 
+impl From<SendInteractiveLocationRequestParams> for SendInteractiveMessage {
+    fn from(params: SendInteractiveLocationRequestParams) -> Self {
+        SendInteractiveMessage {
+            to: params.to,
+            context: params.context,
+            interactive: Interactive {
+                r#type: InteractiveType::LocationRequestMessage,
+                body: InteractiveBody { text: params.body },
+                action: InteractiveAction::LocationRequest(InteractiveLocationRequestAction {
+                    name: "send_location".to_string(),
+                }),
+                header: None,
+                footer: params.footer_text.map(|text| InteractiveFooter { text }),
+                flow: None,
+            },
+        }
+    }
+}
 
-#[derive(Debug, Clone, Serialize)]
+
+/// The message a reply quotes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct MessageContext {
 
     pub message_id: String,
 }
+
+impl MessageContext {
+    pub fn new(message_id: impl Into<String>) -> Self {
+        Self { message_id: message_id.into() }
+    }
+}
+
+impl From<&WebhookMessage> for MessageContext {
+    fn from(message: &WebhookMessage) -> Self {
+        Self::new(message.id.clone())
+    }
+}
+
+
+/// Outbound messages that can quote-reply to an inbound message.
+///
+/// Reactions are not included; they already name the message they react to.
+pub trait ReplyTo: Sized {
+
+    /// Addresses the message to the sender of `message` and quotes it.
+    fn reply_to(self, message: &WebhookMessage) -> Self;
+}
+
+macro_rules! impl_reply_to {
+    ($($message:ty),* $(,)?) => {
+        $(
+            impl ReplyTo for $message {
+                fn reply_to(mut self, message: &WebhookMessage) -> Self {
+                    self.to = message.from.clone();
+                    self.context = Some(MessageContext::from(message));
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_reply_to!(
+    SendTextMessage,
+    SendMediaMessage,
+    SendLocationMessage,
+    SendTemplateMessage,
+    SendInteractiveMessage,
+    SendContactMessage,
+    SendAddressMessage,
+    SendInteractiveCtaUrlButtonMessage,
+    InteractiveFlowParams,
+    SendInteractiveLocationRequestParams,
+);
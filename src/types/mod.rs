@@ -4,12 +4,25 @@
 //! in the WhatsApp Cloud API.
 
 pub mod messages;
+pub mod interactive;
 pub mod media;
 pub mod templates;
 pub mod profile;
 pub mod webhook;
 pub mod business;
+pub mod common;
+pub mod flows;
 
+pub use common::{
+    Paging,
+    PagingCursors,
+    SuccessResponse,
+};
+
+pub use interactive::{
+    InteractiveBuilder,
+    InteractiveKind,
+};
 
 pub use messages::{
     SendTextMessage,
@@ -19,7 +32,9 @@ pub use messages::{
     SendInteractiveMessage,
     SendContactMessage,
     SendReactionMessage,
+    OutboundMessage,
     MarkMessageAsRead,
+    TypingIndicator,
     SendMessageResponse,
     MediaType,
     Component,
@@ -32,7 +47,12 @@ pub use messages::{
     InteractiveButtonType,
     InteractiveButtonReply,
     InteractiveBody,
+    InteractiveFlowParams,
+    FlowAction,
+    FlowMode,
     Contact,
+    MessageContext,
+    ReplyTo,
 };
 
 pub use media::{
@@ -53,6 +73,10 @@ pub use templates::{
     TemplateComponentType,
     TemplateButton,
     TemplateButtonType,
+    Template,
+    TemplateStatus,
+    TemplateCategory,
+    TemplateQualityScore,
 };
 
 pub use profile::{
@@ -63,6 +87,16 @@ pub use profile::{
     DeregisterPhoneNumber,
     UpdatePhoneNumberSettings,
     PhoneNumberResponse,
+    BusinessVertical,
+    QualityRating,
+    Throughput,
+    ThroughputLevel,
+    CodeMethod,
+    RequestVerificationCode,
+    VerifyCode,
+    NameStatus,
+    CodeVerificationStatus,
+    PhoneNumberStatusResponse,
 };
 
 pub use webhook::{
@@ -71,6 +105,32 @@ pub use webhook::{
     WebhookMessageType,
     WebhookStatus,
     WebhookStatusType,
+    WebhookChange,
+    WebhookChangeValue,
+    TemplateStatusUpdate,
+    TemplateQualityUpdate,
+    TemplateCategoryUpdate,
+    WebhookFlowReply,
+    FlowResponse,
+    WebhookReaction,
+    ReactionEvent,
+    WebhookMedia,
+    StoredMedia,
+    MediaChecksum,
+};
+
+pub use flows::{
+    Flow,
+    FlowStatus,
+    FlowCategory,
+    FlowPreview,
+    FlowValidationError,
+    GetFlowsResponse,
+    CreateFlow,
+    CreateFlowResponse,
+    UpdateFlowMetadata,
+    UpdateFlowJsonResponse,
+    FlowPreviewResponse,
 };
 
 pub use business::{
@@ -84,4 +144,25 @@ pub use business::{
     BusinessPhoneNumbersResponse,
     SystemUsersResponse,
     CreateSystemUser,
+    CreateBusinessResponse,
+    WhatsAppBusinessTask,
+    SystemUser,
+    SystemUserRole,
+    CreateSystemUserResponse,
+    GenerateSystemUserToken,
+    AccessTokenResponse,
+    ExtendedCredit,
+    ExtendedCreditsResponse,
+    CreditLineAllocationResponse,
+    CreditAllocationConfig,
+    CreditAllocationCredential,
+    CreditAllocationConfigsResponse,
+    EmbeddedSignupProgress,
+    SubscribeApp,
+    SubscribedApp,
+    SubscribedAppData,
+    SubscribedAppsResponse,
+    WebhookOverride,
+    WebhookConfiguration,
+    PhoneNumberWebhookConfigurationResponse,
 };
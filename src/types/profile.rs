@@ -0,0 +1,285 @@
+//! Types for the business profile and phone number management endpoints
+
+use serde::{Serialize, Deserialize};
+
+use crate::types::common::Paging;
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BusinessVertical {
+
+    Undefined,
+
+    Other,
+
+    Auto,
+
+    Beauty,
+
+    Apparel,
+
+    Edu,
+
+    Entertain,
+
+    EventPlan,
+
+    Finance,
+
+    Grocery,
+
+    Govt,
+
+    Hotel,
+
+    Health,
+
+    Nonprofit,
+
+    ProfServices,
+
+    Retail,
+
+    Travel,
+
+    Restaurant,
+
+    NotABiz,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+/// Business profile of a phone number.
+///
+/// Used both as the result of `get_business_profile` and as the payload of
+/// `update_business_profile`, where only the fields that are set are changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BusinessProfile {
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub about: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub websites: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vertical: Option<BusinessVertical>,
+
+    /// Handle of an image uploaded with the resumable upload API. Write only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile_picture_handle: Option<String>,
+
+    /// Current profile picture. Read only.
+    #[serde(skip_serializing)]
+    pub profile_picture_url: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BusinessProfileResponse {
+
+    pub data: Vec<BusinessProfile>,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum QualityRating {
+
+    Green,
+
+    Yellow,
+
+    Red,
+
+    Na,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ThroughputLevel {
+
+    Standard,
+
+    High,
+
+    NotApplicable,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Throughput {
+
+    pub level: ThroughputLevel,
+}
+
+
+/// Review status of a display name.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NameStatus {
+
+    Approved,
+
+    AvailableWithoutReview,
+
+    Declined,
+
+    Expired,
+
+    PendingReview,
+
+    None,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CodeVerificationStatus {
+
+    Verified,
+
+    NotVerified,
+
+    Expired,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PhoneNumberResponse {
+
+    pub id: String,
+
+    #[serde(default)]
+    pub display_phone_number: Option<String>,
+
+    #[serde(default)]
+    pub verified_name: Option<String>,
+
+    #[serde(default)]
+    pub quality_rating: Option<QualityRating>,
+
+    #[serde(default)]
+    pub throughput: Option<Throughput>,
+
+    #[serde(default)]
+    pub messaging_limit_tier: Option<String>,
+
+    #[serde(default)]
+    pub code_verification_status: Option<CodeVerificationStatus>,
+
+    #[serde(default)]
+    pub name_status: Option<NameStatus>,
+
+    #[serde(default)]
+    pub new_name_status: Option<NameStatus>,
+
+    #[serde(default)]
+    pub platform_type: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetrievePhoneNumbersResponse {
+
+    pub data: Vec<PhoneNumberResponse>,
+
+    #[serde(default)]
+    pub paging: Option<Paging>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterPhoneNumber {
+
+    /// Six digit two-step verification PIN.
+    pub pin: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_localization_region: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeregisterPhoneNumber {}
+
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdatePhoneNumberSettings {
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pin: Option<String>,
+}
+
+
+/// Verification and display name review state of a phone number.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PhoneNumberStatusResponse {
+
+    pub id: String,
+
+    #[serde(default)]
+    pub verified_name: Option<String>,
+
+    #[serde(default)]
+    pub name_status: Option<NameStatus>,
+
+    /// Status of a pending display name change, if one was submitted.
+    #[serde(default)]
+    pub new_name_status: Option<NameStatus>,
+
+    #[serde(default)]
+    pub code_verification_status: Option<CodeVerificationStatus>,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CodeMethod {
+
+    Sms,
+
+    Voice,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestVerificationCode {
+
+    pub code_method: CodeMethod,
+
+    /// Language of the message carrying the code, for example `en_US`.
+    pub language: String,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyCode {
+
+    pub code: String,
+}
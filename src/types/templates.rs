@@ -0,0 +1,284 @@
+//! Types for managing message templates
+
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+use crate::types::common::Paging;
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TemplateStatus {
+
+    Approved,
+
+    Pending,
+
+    Rejected,
+
+    Paused,
+
+    Disabled,
+
+    Flagged,
+
+    InAppeal,
+
+    Reinstated,
+
+    PendingDeletion,
+
+    Deleted,
+
+    Archived,
+
+    Locked,
+
+    Unlocked,
+
+    LimitExceeded,
+
+    #[serde(other)]
+    Unknown,
+}
+
+impl TemplateStatus {
+    /// Whether messages can currently be sent with a template in this status.
+    ///
+    /// Unknown statuses are treated as usable so the API stays the authority
+    /// for states this SDK does not know about yet.
+    pub fn is_usable(&self) -> bool {
+        !matches!(
+            self,
+            TemplateStatus::Pending
+                | TemplateStatus::Rejected
+                | TemplateStatus::Paused
+                | TemplateStatus::Disabled
+                | TemplateStatus::InAppeal
+                | TemplateStatus::PendingDeletion
+                | TemplateStatus::Deleted
+                | TemplateStatus::Archived
+                | TemplateStatus::LimitExceeded
+        )
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TemplateStatus::Approved => "APPROVED",
+            TemplateStatus::Pending => "PENDING",
+            TemplateStatus::Rejected => "REJECTED",
+            TemplateStatus::Paused => "PAUSED",
+            TemplateStatus::Disabled => "DISABLED",
+            TemplateStatus::Flagged => "FLAGGED",
+            TemplateStatus::InAppeal => "IN_APPEAL",
+            TemplateStatus::Reinstated => "REINSTATED",
+            TemplateStatus::PendingDeletion => "PENDING_DELETION",
+            TemplateStatus::Deleted => "DELETED",
+            TemplateStatus::Archived => "ARCHIVED",
+            TemplateStatus::Locked => "LOCKED",
+            TemplateStatus::Unlocked => "UNLOCKED",
+            TemplateStatus::LimitExceeded => "LIMIT_EXCEEDED",
+            TemplateStatus::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+impl fmt::Display for TemplateStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TemplateCategory {
+
+    Marketing,
+
+    Utility,
+
+    Authentication,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TemplateQualityScore {
+
+    Green,
+
+    Yellow,
+
+    Red,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TemplateComponentType {
+
+    Header,
+
+    Body,
+
+    Footer,
+
+    Buttons,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TemplateButtonType {
+
+    QuickReply,
+
+    Url,
+
+    PhoneNumber,
+
+    Otp,
+
+    CopyCode,
+
+    Flow,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateButton {
+
+    pub r#type: TemplateButtonType,
+
+    pub text: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone_number: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<Vec<String>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateComponent {
+
+    pub r#type: TemplateComponentType,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buttons: Option<Vec<TemplateButton>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<serde_json::Value>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Template {
+
+    pub id: String,
+
+    pub name: String,
+
+    pub language: String,
+
+    pub status: TemplateStatus,
+
+    pub category: TemplateCategory,
+
+    #[serde(default)]
+    pub components: Vec<TemplateComponent>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality_score: Option<TemplateQuality>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateQuality {
+
+    pub score: TemplateQualityScore,
+}
+
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GetTemplates {
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<TemplateStatus>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<TemplateCategory>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetTemplatesResponse {
+
+    pub data: Vec<Template>,
+
+    #[serde(default)]
+    pub paging: Option<Paging>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateTemplate {
+
+    pub name: String,
+
+    pub category: TemplateCategory,
+
+    pub language: String,
+
+    pub components: Vec<TemplateComponent>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_category_change: Option<bool>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateTemplateResponse {
+
+    pub id: String,
+
+    pub status: TemplateStatus,
+
+    pub category: TemplateCategory,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeleteTemplate {
+
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hsm_id: Option<String>,
+}
@@ -0,0 +1,642 @@
+//! Types for events delivered to webhook endpoints
+//!
+//! The Cloud API wraps every notification in the same `object` / `entry` /
+//! `changes` envelope. The `field` of each change decides the shape of its
+//! `value`, which is exposed here as [`WebhookChangeValue`].
+
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use std::convert::TryFrom;
+
+use crate::error::WhatsAppResult;
+use crate::types::messages::Contact;
+use crate::types::templates::{TemplateCategory, TemplateQualityScore, TemplateStatus};
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookEvent {
+
+    pub object: String,
+
+    pub entry: Vec<WebhookEntry>,
+}
+
+impl WebhookEvent {
+    /// Iterates over every change in every entry of the event.
+    pub fn changes(&self) -> impl Iterator<Item = &WebhookChange> {
+        self.entry.iter().flat_map(|entry| entry.changes.iter())
+    }
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookEntry {
+
+    pub id: String,
+
+    #[serde(default)]
+    pub changes: Vec<WebhookChange>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawWebhookChange")]
+pub struct WebhookChange {
+
+    pub field: String,
+
+    pub value: WebhookChangeValue,
+}
+
+
+#[derive(Debug, Clone)]
+pub enum WebhookChangeValue {
+
+    Messages(WebhookValue),
+
+    MessageTemplateStatusUpdate(TemplateStatusUpdate),
+
+    MessageTemplateQualityUpdate(TemplateQualityUpdate),
+
+    TemplateCategoryUpdate(TemplateCategoryUpdate),
+
+    /// A field this SDK has no typed representation for.
+    Other(serde_json::Value),
+}
+
+
+#[derive(Deserialize)]
+struct RawWebhookChange {
+    field: String,
+    value: serde_json::Value,
+}
+
+impl TryFrom<RawWebhookChange> for WebhookChange {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawWebhookChange) -> Result<Self, Self::Error> {
+        let value = match raw.field.as_str() {
+            "messages" => WebhookChangeValue::Messages(serde_json::from_value(raw.value)?),
+            "message_template_status_update" => {
+                WebhookChangeValue::MessageTemplateStatusUpdate(serde_json::from_value(raw.value)?)
+            }
+            "message_template_quality_update" => {
+                WebhookChangeValue::MessageTemplateQualityUpdate(serde_json::from_value(raw.value)?)
+            }
+            "template_category_update" => {
+                WebhookChangeValue::TemplateCategoryUpdate(serde_json::from_value(raw.value)?)
+            }
+            _ => WebhookChangeValue::Other(raw.value),
+        };
+
+        Ok(Self {
+            field: raw.field,
+            value,
+        })
+    }
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookValue {
+
+    pub messaging_product: String,
+
+    pub metadata: WebhookMetadata,
+
+    #[serde(default)]
+    pub contacts: Vec<WebhookContact>,
+
+    #[serde(default)]
+    pub messages: Vec<WebhookMessage>,
+
+    #[serde(default)]
+    pub statuses: Vec<WebhookStatus>,
+
+    #[serde(default)]
+    pub errors: Vec<WebhookError>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookMetadata {
+
+    pub display_phone_number: String,
+
+    pub phone_number_id: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookContact {
+
+    pub wa_id: String,
+
+    pub profile: Option<WebhookProfile>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookProfile {
+
+    pub name: String,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookMessageType {
+
+    Text,
+
+    Image,
+
+    Audio,
+
+    Video,
+
+    Document,
+
+    Sticker,
+
+    Location,
+
+    Contacts,
+
+    Interactive,
+
+    Button,
+
+    Reaction,
+
+    Order,
+
+    System,
+
+    Request,
+
+    Unsupported,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookMessage {
+
+    pub from: String,
+
+    pub id: String,
+
+    pub timestamp: String,
+
+    pub r#type: WebhookMessageType,
+
+    pub context: Option<WebhookMessageContext>,
+
+    pub text: Option<WebhookText>,
+
+    pub image: Option<WebhookMedia>,
+
+    pub audio: Option<WebhookMedia>,
+
+    pub video: Option<WebhookMedia>,
+
+    pub document: Option<WebhookMedia>,
+
+    pub sticker: Option<WebhookMedia>,
+
+    pub location: Option<WebhookLocation>,
+
+    pub contacts: Option<Vec<Contact>>,
+
+    pub interactive: Option<WebhookInteractive>,
+
+    pub button: Option<WebhookButton>,
+
+    pub reaction: Option<WebhookReaction>,
+
+    #[serde(default)]
+    pub errors: Vec<WebhookError>,
+}
+
+impl WebhookMessage {
+    /// The image, audio, video, document or sticker carried by this message.
+    pub fn media(&self) -> Option<&WebhookMedia> {
+        self.image
+            .as_ref()
+            .or(self.audio.as_ref())
+            .or(self.video.as_ref())
+            .or(self.document.as_ref())
+            .or(self.sticker.as_ref())
+    }
+
+    pub fn media_mut(&mut self) -> Option<&mut WebhookMedia> {
+        self.image
+            .as_mut()
+            .or(self.audio.as_mut())
+            .or(self.video.as_mut())
+            .or(self.document.as_mut())
+            .or(self.sticker.as_mut())
+    }
+
+    /// The Flow completion carried by this message, if it is one.
+    pub fn flow_reply(&self) -> Option<&WebhookFlowReply> {
+        match &self.interactive {
+            Some(WebhookInteractive::NfmReply { nfm_reply }) => Some(nfm_reply),
+            _ => None,
+        }
+    }
+
+    /// The reaction carried by this message, if it is one.
+    pub fn reaction_event(&self) -> Option<ReactionEvent> {
+        let reaction = self.reaction.as_ref()?;
+        let from = self.from.clone();
+        let message_id = reaction.message_id.clone();
+
+        Some(match reaction.emoji.as_deref() {
+            Some(emoji) if !emoji.is_empty() => ReactionEvent::Reacted {
+                from,
+                message_id,
+                emoji: emoji.to_string(),
+            },
+            _ => ReactionEvent::Removed { from, message_id },
+        })
+    }
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookReaction {
+
+    /// The message reacted to.
+    pub message_id: String,
+
+    /// Absent when the user removed their reaction.
+    pub emoji: Option<String>,
+}
+
+
+/// A reaction a user set or removed on one of the business's messages.
+///
+/// A user has at most one reaction per message, so a changed reaction arrives
+/// as another `Reacted` event for the same `message_id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReactionEvent {
+
+    Reacted {
+        from: String,
+        message_id: String,
+        emoji: String,
+    },
+
+    Removed {
+        from: String,
+        message_id: String,
+    },
+}
+
+impl ReactionEvent {
+    pub fn from(&self) -> &str {
+        match self {
+            Self::Reacted { from, .. } | Self::Removed { from, .. } => from,
+        }
+    }
+
+    /// The id of the message reacted to.
+    pub fn message_id(&self) -> &str {
+        match self {
+            Self::Reacted { message_id, .. } | Self::Removed { message_id, .. } => message_id,
+        }
+    }
+
+    pub fn emoji(&self) -> Option<&str> {
+        match self {
+            Self::Reacted { emoji, .. } => Some(emoji),
+            Self::Removed { .. } => None,
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookMessageContext {
+
+    pub from: Option<String>,
+
+    pub id: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookText {
+
+    pub body: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookMedia {
+
+    pub id: String,
+
+    pub mime_type: Option<String>,
+
+    pub sha256: Option<String>,
+
+    pub caption: Option<String>,
+
+    pub filename: Option<String>,
+
+    /// Where the media was saved, once a `MediaDownloader` has fetched it.
+    #[serde(skip)]
+    pub stored: Option<StoredMedia>,
+}
+
+
+/// Inbound media saved by a `MediaDownloader`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredMedia {
+
+    /// Where the sink put the media, e.g. a file path.
+    pub location: String,
+
+    pub size: u64,
+
+    /// SHA-256 of the downloaded bytes, hex encoded.
+    pub sha256: String,
+
+    pub checksum: MediaChecksum,
+}
+
+
+/// Result of comparing downloaded media with the `sha256` of its webhook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MediaChecksum {
+
+    Verified,
+
+    /// The webhook's hash differs; the media may be corrupt or incomplete.
+    Mismatch {
+        expected: String,
+    },
+
+    /// The webhook carried no hash to compare with.
+    Unavailable,
+}
+
+impl MediaChecksum {
+    pub fn is_verified(&self) -> bool {
+        matches!(self, Self::Verified)
+    }
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookLocation {
+
+    pub latitude: f64,
+
+    pub longitude: f64,
+
+    pub name: Option<String>,
+
+    pub address: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebhookInteractive {
+
+    ButtonReply {
+
+        button_reply: WebhookReply,
+    },
+
+    ListReply {
+
+        list_reply: WebhookReply,
+    },
+
+    /// Sent when a user completes a Flow.
+    NfmReply {
+
+        nfm_reply: WebhookFlowReply,
+    },
+
+    /// A reply type this SDK does not model yet.
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookFlowReply {
+
+    /// Always `flow`.
+    pub name: Option<String>,
+
+    pub body: Option<String>,
+
+    /// The Flow's completion payload, as a JSON string.
+    pub response_json: String,
+}
+
+impl WebhookFlowReply {
+    /// Parses `response_json` into the type the Flow's completion payload was designed as.
+    pub fn response<T: DeserializeOwned>(&self) -> WhatsAppResult<T> {
+        Ok(serde_json::from_str(&self.response_json)?)
+    }
+
+    pub fn flow_response(&self) -> WhatsAppResult<FlowResponse> {
+        self.response()
+    }
+}
+
+
+/// Completion payload of a Flow: the token the Flow was sent with, and the
+/// fields its terminal screen submitted.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlowResponse {
+
+    pub flow_token: Option<String>,
+
+    #[serde(flatten)]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookReply {
+
+    pub id: String,
+
+    pub title: String,
+
+    pub description: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookButton {
+
+    pub text: String,
+
+    pub payload: String,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookStatusType {
+
+    Sent,
+
+    Delivered,
+
+    Read,
+
+    Failed,
+
+    Deleted,
+
+    Warning,
+
+    #[serde(other)]
+    Unknown,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookStatus {
+
+    pub id: String,
+
+    pub status: WebhookStatusType,
+
+    pub timestamp: String,
+
+    pub recipient_id: String,
+
+    pub conversation: Option<WebhookConversation>,
+
+    pub pricing: Option<WebhookPricing>,
+
+    /// Data the message was sent with, see `WhatsAppClient::with_callback_data`.
+    pub biz_opaque_callback_data: Option<String>,
+
+    #[serde(default)]
+    pub errors: Vec<WebhookError>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConversation {
+
+    pub id: String,
+
+    pub expiration_timestamp: Option<String>,
+
+    pub origin: Option<WebhookConversationOrigin>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConversationOrigin {
+
+    pub r#type: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookPricing {
+
+    pub billable: bool,
+
+    pub pricing_model: String,
+
+    pub category: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookError {
+
+    pub code: i32,
+
+    pub title: String,
+
+    pub message: Option<String>,
+
+    pub error_data: Option<WebhookErrorData>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookErrorData {
+
+    pub details: String,
+}
+
+
+/// Value of a `message_template_status_update` change.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateStatusUpdate {
+
+    pub event: TemplateStatus,
+
+    pub message_template_id: u64,
+
+    pub message_template_name: String,
+
+    pub message_template_language: String,
+
+    pub reason: Option<String>,
+
+    pub other_info: Option<TemplateStatusOtherInfo>,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateStatusOtherInfo {
+
+    pub title: String,
+
+    pub description: Option<String>,
+}
+
+
+/// Value of a `message_template_quality_update` change.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateQualityUpdate {
+
+    pub previous_quality_score: TemplateQualityScore,
+
+    pub new_quality_score: TemplateQualityScore,
+
+    pub message_template_id: u64,
+
+    pub message_template_name: String,
+
+    pub message_template_language: String,
+}
+
+
+/// Value of a `template_category_update` change.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateCategoryUpdate {
+
+    pub message_template_id: u64,
+
+    pub message_template_name: String,
+
+    pub message_template_language: String,
+
+    pub previous_category: TemplateCategory,
+
+    pub new_category: TemplateCategory,
+
+    pub correct_category: Option<TemplateCategory>,
+}
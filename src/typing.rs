@@ -0,0 +1,87 @@
+//! Typing indicators that outlast the API's 25 second limit
+//!
+//! A typing indicator disappears after 25 seconds or when a reply is sent. A
+//! [`TypingGuard`] keeps it visible for as long as the guard lives by sending
+//! it again periodically, and stops as soon as the client sends a message to
+//! the same user, so the indicator does not reappear after the reply.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::task::AbortHandle;
+
+use crate::util::phone::wa_id_key;
+
+/// How often a guard resends the typing indicator; below the 25 second limit.
+pub const TYPING_REFRESH_INTERVAL: Duration = Duration::from_secs(20);
+
+
+/// Refresh tasks of the active guards of a client, keyed by `wa_id`.
+#[derive(Debug, Default)]
+pub(crate) struct TypingRegistry {
+    next_id: AtomicU64,
+    active: Mutex<HashMap<String, (u64, AbortHandle)>>,
+}
+
+impl TypingRegistry {
+    /// Registers a refresh task, replacing and stopping any earlier one for the same user.
+    pub(crate) fn register(self: &Arc<Self>, to: &str, task: AbortHandle) -> TypingGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let to = wa_id_key(to);
+
+        if let Some((_, previous)) = self.active.lock().unwrap().insert(to.clone(), (id, task)) {
+            previous.abort();
+        }
+
+        TypingGuard {
+            registry: Arc::clone(self),
+            to,
+            id,
+        }
+    }
+
+    /// Stops the guard for `to`, if any; called before a message is sent to them.
+    pub(crate) fn stop(&self, to: &str) {
+        if let Some((_, task)) = self.active.lock().unwrap().remove(&wa_id_key(to)) {
+            task.abort();
+        }
+    }
+
+    fn release(&self, to: &str, id: u64) {
+        let mut active = self.active.lock().unwrap();
+        if active.get(to).is_some_and(|(active_id, _)| *active_id == id) {
+            if let Some((_, task)) = active.remove(to) {
+                task.abort();
+            }
+        }
+    }
+}
+
+
+/// Keeps a typing indicator visible until dropped or until a message is sent
+/// to the user. Created by `WhatsAppClient::start_typing`.
+#[derive(Debug)]
+#[must_use = "the typing indicator stops when the guard is dropped"]
+pub struct TypingGuard {
+    registry: Arc<TypingRegistry>,
+    to: String,
+    id: u64,
+}
+
+impl TypingGuard {
+    /// The user the indicator is shown to, as a `wa_id`.
+    pub fn to(&self) -> &str {
+        &self.to
+    }
+
+    /// Stops refreshing the indicator; the same as dropping the guard.
+    pub fn stop(self) {}
+}
+
+impl Drop for TypingGuard {
+    fn drop(&mut self) {
+        self.registry.release(&self.to, self.id);
+    }
+}
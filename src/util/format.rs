@@ -0,0 +1,564 @@
+//! Helpers for WhatsApp text formatting
+//!
+//! WhatsApp renders its own markup in text bodies and captions: `*bold*`,
+//! `_italic_`, `~strikethrough~`, `` `inline code` ``, ```` ```monospace``` ````,
+//! `> ` quotes and `- ` / `1. ` lists. This module composes that markup with
+//! [`FormattedText`], neutralizes it in untrusted content with [`escape`],
+//! converts Markdown and HTML subsets to it, and strips it again with
+//! [`to_plain_text`].
+
+use std::fmt;
+
+/// Inserted after a markup character to keep WhatsApp from treating it as formatting.
+pub const ESCAPE_CHAR: char = '\u{200B}';
+
+const INLINE_MARKERS: &[char] = &['*', '_', '~', '`'];
+
+
+/// Escapes untrusted content so WhatsApp shows it as typed.
+///
+/// Every formatting character is followed by a zero width space, and lines that
+/// would start a quote get one in front, which keeps the text visually unchanged.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            escaped.push('\n');
+        }
+        if line.starts_with('>') {
+            escaped.push(ESCAPE_CHAR);
+        }
+        for c in line.chars() {
+            escaped.push(c);
+            if INLINE_MARKERS.contains(&c) {
+                escaped.push(ESCAPE_CHAR);
+            }
+        }
+    }
+
+    escaped
+}
+
+/// Wraps `text` in `marker`, keeping surrounding whitespace outside of the
+/// markers since WhatsApp ignores `* bold *`.
+fn wrap(text: &str, marker: &str) -> String {
+    let inner = text.trim();
+    if inner.is_empty() {
+        return text.to_string();
+    }
+
+    let start = text.len() - text.trim_start().len();
+    let end = text.trim_end().len();
+    format!("{}{}{}{}{}", &text[..start], marker, inner, marker, &text[end..])
+}
+
+
+/// Builder for formatted message text.
+///
+/// Content passed to the builder is escaped, so only the formatting requested
+/// through the builder is rendered.
+///
+/// ```
+/// use whatsapp_cloud_sdk::util::FormattedText;
+///
+/// let order_id = "A-1042";
+/// let body = FormattedText::new()
+///     .text("Order ")
+///     .bold(order_id)
+///     .text(" has shipped")
+///     .bulleted_list(&["Tracking: 1Z999", "Carrier: UPS"])
+///     .build();
+///
+/// assert_eq!(body, "Order *A-1042* has shipped\n- Tracking: 1Z999\n- Carrier: UPS");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormattedText {
+    text: String,
+}
+
+impl FormattedText {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(mut self, text: &str) -> Self {
+        self.text.push_str(&escape(text));
+        self
+    }
+
+    /// Appends text without escaping, so any markup in it is rendered.
+    pub fn raw(mut self, text: &str) -> Self {
+        self.text.push_str(text);
+        self
+    }
+
+    pub fn bold(mut self, text: &str) -> Self {
+        self.text.push_str(&wrap(&escape(text), "*"));
+        self
+    }
+
+    pub fn italic(mut self, text: &str) -> Self {
+        self.text.push_str(&wrap(&escape(text), "_"));
+        self
+    }
+
+    pub fn strikethrough(mut self, text: &str) -> Self {
+        self.text.push_str(&wrap(&escape(text), "~"));
+        self
+    }
+
+    pub fn inline_code(mut self, text: &str) -> Self {
+        self.text.push_str(&wrap(&escape(text), "`"));
+        self
+    }
+
+    /// Appends a monospace block. Nothing inside it is formatted, so only
+    /// backticks are escaped.
+    pub fn monospace(mut self, text: &str) -> Self {
+        let escaped: String = text
+            .chars()
+            .flat_map(|c| match c {
+                '`' => vec![c, ESCAPE_CHAR],
+                _ => vec![c],
+            })
+            .collect();
+        self.text.push_str(&format!("```{}```", escaped));
+        self
+    }
+
+    pub fn line_break(mut self) -> Self {
+        self.text.push('\n');
+        self
+    }
+
+    pub fn quote(mut self, text: &str) -> Self {
+        self.start_line();
+        let lines: Vec<String> = text.lines().map(|line| format!("> {}", escape(line))).collect();
+        self.text.push_str(&lines.join("\n"));
+        self
+    }
+
+    pub fn bulleted_list<I, S>(mut self, items: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.start_line();
+        let lines: Vec<String> = items
+            .into_iter()
+            .map(|item| format!("- {}", escape(item.as_ref())))
+            .collect();
+        self.text.push_str(&lines.join("\n"));
+        self
+    }
+
+    pub fn numbered_list<I, S>(mut self, items: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.start_line();
+        let lines: Vec<String> = items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| format!("{}. {}", index + 1, escape(item.as_ref())))
+            .collect();
+        self.text.push_str(&lines.join("\n"));
+        self
+    }
+
+    pub fn build(self) -> String {
+        self.text
+    }
+
+    /// Block elements only render at the start of a line.
+    fn start_line(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+    }
+}
+
+impl fmt::Display for FormattedText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl From<FormattedText> for String {
+    fn from(text: FormattedText) -> Self {
+        text.text
+    }
+}
+
+
+/// Converts a Markdown subset to WhatsApp markup.
+///
+/// Supports emphasis (`**`, `__`, `*`, `_`), `~~strikethrough~~`, inline code,
+/// fenced code blocks, headings (rendered bold), links (rendered as
+/// `text (url)`), lists and block quotes.
+pub fn markdown_to_whatsapp(markdown: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            lines.push("```".to_string());
+            continue;
+        }
+        if in_fence {
+            lines.push(line.to_string());
+            continue;
+        }
+        lines.push(convert_markdown_line(line));
+    }
+
+    lines.join("\n")
+}
+
+fn convert_markdown_line(line: &str) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let trimmed = line.trim_start();
+
+    let heading_level = trimmed.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&heading_level) && trimmed[heading_level..].starts_with(' ') {
+        return wrap(&convert_markdown_inline(trimmed[heading_level..].trim()), "*");
+    }
+
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = trimmed.strip_prefix(bullet) {
+            return format!("{}- {}", indent, convert_markdown_inline(item));
+        }
+    }
+
+    if let Some(quote) = trimmed.strip_prefix('>') {
+        return format!("> {}", convert_markdown_inline(quote.trim_start()));
+    }
+
+    format!("{}{}", indent, convert_markdown_inline(trimmed))
+}
+
+fn find_delimiter(chars: &[char], from: usize, delimiter: &[char]) -> Option<usize> {
+    (from..chars.len().saturating_sub(delimiter.len() - 1))
+        .find(|&i| chars[i..i + delimiter.len()] == *delimiter)
+}
+
+fn convert_markdown_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let prev_is_word = i > 0 && chars[i - 1].is_alphanumeric();
+
+        if c == '`' {
+            if let Some(end) = find_delimiter(&chars, i + 1, &['`']) {
+                out.extend(&chars[i..=end]);
+                i = end + 1;
+                continue;
+            }
+        }
+
+        if (c == '*' || c == '_' || c == '~') && next == Some(c) {
+            if let Some(end) = find_delimiter(&chars, i + 2, &[c, c]).filter(|&end| end > i + 2) {
+                let inner: String = chars[i + 2..end].iter().collect();
+                let marker = if c == '~' { "~" } else { "*" };
+                out.push_str(&wrap(&convert_markdown_inline(&inner), marker));
+                i = end + 2;
+                continue;
+            }
+        }
+
+        if (c == '*' || c == '_') && !prev_is_word && next.is_some_and(|n| !n.is_whitespace()) {
+            if let Some(end) = find_delimiter(&chars, i + 1, &[c]).filter(|&end| end > i + 1) {
+                let inner: String = chars[i + 1..end].iter().collect();
+                out.push_str(&wrap(&convert_markdown_inline(&inner), "_"));
+                i = end + 1;
+                continue;
+            }
+        }
+
+        if c == '[' {
+            if let Some(close) = find_delimiter(&chars, i + 1, &[']']) {
+                if chars.get(close + 1) == Some(&'(') {
+                    if let Some(end) = find_delimiter(&chars, close + 2, &[')']) {
+                        let label: String = chars[i + 1..close].iter().collect();
+                        let url: String = chars[close + 2..end].iter().collect();
+                        if label == url || label.is_empty() {
+                            out.push_str(&url);
+                        } else {
+                            out.push_str(&format!("{} ({})", convert_markdown_inline(&label), url));
+                        }
+                        i = end + 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        out.push(c);
+        i += 1;
+    }
+
+    out
+}
+
+
+/// Converts an HTML subset to WhatsApp markup.
+///
+/// Handles `b`/`strong`, `i`/`em`, `s`/`del`/`strike`, `code`, `pre`, `a`,
+/// `br`, `p`, `div`, headings, `ul`/`ol`/`li` and `blockquote`. Other tags are
+/// dropped and their text kept. Text content is escaped.
+pub fn html_to_whatsapp(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut lists: Vec<Option<usize>> = Vec::new();
+    let mut pending_href: Vec<Option<String>> = Vec::new();
+    let mut quote_depth = 0usize;
+    let mut pre_depth = 0usize;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let (text, after) = match rest.find('<') {
+            Some(start) => (&rest[..start], &rest[start..]),
+            None => (rest, ""),
+        };
+
+        if !text.is_empty() {
+            let decoded = decode_entities(text);
+            if pre_depth > 0 {
+                out.push_str(&decoded);
+            } else {
+                let collapsed = decoded.split_whitespace().collect::<Vec<_>>().join(" ");
+                if decoded.starts_with(char::is_whitespace) && !out.ends_with(char::is_whitespace) && !out.is_empty() {
+                    out.push(' ');
+                }
+                out.push_str(&escape(&collapsed));
+                if decoded.ends_with(char::is_whitespace) && !collapsed.is_empty() {
+                    out.push(' ');
+                }
+            }
+        }
+
+        if after.is_empty() {
+            break;
+        }
+
+        let end = match after.find('>') {
+            Some(end) => end,
+            None => {
+                out.push_str(&escape(&decode_entities(after)));
+                break;
+            }
+        };
+        let tag = &after[1..end];
+        rest = &after[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let tag_body = tag.trim_start_matches('/').trim_end_matches('/');
+        let name = tag_body
+            .split(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        match (name.as_str(), closing) {
+            ("b" | "strong", _) => push_marker(&mut out, '*', closing),
+            ("i" | "em", _) => push_marker(&mut out, '_', closing),
+            ("s" | "del" | "strike", _) => push_marker(&mut out, '~', closing),
+            ("code", _) if pre_depth == 0 => push_marker(&mut out, '`', closing),
+            ("pre", false) => {
+                start_block(&mut out);
+                out.push_str("```");
+                pre_depth += 1;
+            }
+            ("pre", true) => {
+                out.push_str("```\n");
+                pre_depth = pre_depth.saturating_sub(1);
+            }
+            ("br", _) => out.push('\n'),
+            ("p" | "div", _) => start_block(&mut out),
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                start_block(&mut out);
+                out.push('*');
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => {
+                push_marker(&mut out, '*', true);
+                out.push('\n');
+            }
+            ("ul", false) => {
+                start_block(&mut out);
+                lists.push(None);
+            }
+            ("ol", false) => {
+                start_block(&mut out);
+                lists.push(Some(0));
+            }
+            ("ul" | "ol", true) => {
+                lists.pop();
+                start_block(&mut out);
+            }
+            ("li", false) => {
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                match lists.last_mut() {
+                    Some(Some(counter)) => {
+                        *counter += 1;
+                        out.push_str(&format!("{}. ", counter));
+                    }
+                    _ => out.push_str("- "),
+                }
+            }
+            ("blockquote", false) => {
+                start_block(&mut out);
+                quote_depth += 1;
+                out.push_str("> ");
+            }
+            ("blockquote", true) => {
+                quote_depth = quote_depth.saturating_sub(1);
+                start_block(&mut out);
+            }
+            ("a", false) => pending_href.push(attribute(tag_body, "href")),
+            ("a", true) => {
+                if let Some(Some(href)) = pending_href.pop() {
+                    if !out.trim_end().ends_with(href.as_str()) {
+                        out.push_str(&format!(" ({})", href));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if quote_depth > 0 && out.ends_with('\n') {
+            out.push_str("> ");
+        }
+    }
+
+    tidy_blank_lines(&out)
+}
+
+/// Closing markers must directly follow the text they format.
+fn push_marker(out: &mut String, marker: char, closing: bool) {
+    if closing {
+        let trailing = out.len() - out.trim_end_matches(' ').len();
+        out.truncate(out.len() - trailing);
+        out.push(marker);
+        out.push_str(&" ".repeat(trailing));
+    } else {
+        out.push(marker);
+    }
+}
+
+fn start_block(out: &mut String) {
+    let trimmed = out.trim_end_matches(' ').len();
+    out.truncate(trimmed);
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+}
+
+fn attribute(tag_body: &str, name: &str) -> Option<String> {
+    let lower = tag_body.to_ascii_lowercase();
+    let start = lower.find(&format!("{}=", name))? + name.len() + 1;
+    let value = &tag_body[start..];
+    let value = match value.chars().next()? {
+        quote @ ('"' | '\'') => value[1..].split(quote).next()?,
+        _ => value.split_whitespace().next()?,
+    };
+    Some(decode_entities(value))
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn tidy_blank_lines(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+
+    for line in text.trim().lines() {
+        let line = line.trim_end();
+        if line.is_empty() || line == ">" {
+            blank_lines += 1;
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out.trim_end().to_string()
+}
+
+
+/// Removes WhatsApp markup and escape characters, e.g. for logging.
+pub fn to_plain_text(text: &str) -> String {
+    // Escaped markers are text, so escape characters go only after the markup.
+    let without_fences = text.replace("```", "");
+
+    without_fences
+        .split('\n')
+        .map(|line| {
+            let line = line
+                .strip_prefix("> ")
+                .or_else(|| line.strip_prefix('>'))
+                .unwrap_or(line);
+            strip_inline_markers(line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .chars()
+        .filter(|&c| c != ESCAPE_CHAR)
+        .collect()
+}
+
+fn strip_inline_markers(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut skip = vec![false; chars.len()];
+    let escaped = |index: usize| chars.get(index + 1) == Some(&ESCAPE_CHAR);
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let opens = INLINE_MARKERS.contains(&c)
+            && !skip[i]
+            && !escaped(i)
+            && (i == 0 || !chars[i - 1].is_alphanumeric())
+            && chars.get(i + 1).is_some_and(|n| !n.is_whitespace() && *n != c);
+
+        if opens {
+            let close = (i + 2..chars.len()).find(|&j| {
+                chars[j] == c
+                    && !escaped(j)
+                    && !chars[j - 1].is_whitespace()
+                    && chars.get(j + 1).map_or(true, |n| !n.is_alphanumeric())
+            });
+            if let Some(close) = close {
+                skip[i] = true;
+                skip[close] = true;
+            }
+        }
+        i += 1;
+    }
+
+    chars
+        .iter()
+        .zip(skip)
+        .filter(|(_, skipped)| !skipped)
+        .map(|(c, _)| c)
+        .collect()
+}
@@ -0,0 +1,13 @@
+//! Utilities for preparing data sent through the WhatsApp Cloud API
+
+pub mod emoji;
+pub mod format;
+pub mod phone;
+pub mod split;
+pub mod vcard;
+
+pub use emoji::is_single_emoji;
+pub use format::FormattedText;
+pub use phone::PhoneNumber;
+pub use split::split_text;
+pub use vcard::{parse_vcards, to_vcards, VCardVersion};
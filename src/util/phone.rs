@@ -0,0 +1,467 @@
+//! Phone number normalization and validation
+//!
+//! The Cloud API addresses users by their `wa_id`: the E.164 number without the
+//! leading `+`. [`PhoneNumber`] holds a number in that form and can be built from
+//! the formats people usually type, such as `+1 (555) 123-4567`, `00 44 20 7946 0958`
+//! or, given a default region, national formats like `020 7946 0958`.
+
+use serde::{Serialize, Deserialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{WhatsAppError, WhatsAppResult};
+
+/// Longest number allowed by E.164, country calling code included.
+const MAX_E164_DIGITS: usize = 15;
+
+
+struct CallingCode {
+    code: &'static str,
+    regions: &'static [&'static str],
+    min_national_digits: usize,
+    max_national_digits: usize,
+}
+
+macro_rules! calling_codes {
+    ($(($code:literal, [$($region:literal),+], $min:literal, $max:literal)),+ $(,)?) => {
+        &[$(CallingCode {
+            code: $code,
+            regions: &[$($region),+],
+            min_national_digits: $min,
+            max_national_digits: $max,
+        }),+]
+    };
+}
+
+/// Country calling codes with the length range of their national significant numbers.
+///
+/// The first region listed is the one reported for numbers with that code.
+static CALLING_CODES: &[CallingCode] = calling_codes![
+    ("1", ["US", "CA", "PR", "DO", "JM", "TT", "BS", "BB", "AG", "AI", "BM", "DM", "GD", "GU", "KN", "KY", "LC", "MP", "MS", "SX", "TC", "VC", "VG", "VI", "AS"], 10, 10),
+    ("7", ["RU", "KZ"], 10, 10),
+    ("20", ["EG"], 8, 10),
+    ("27", ["ZA"], 9, 9),
+    ("30", ["GR"], 10, 10),
+    ("31", ["NL"], 9, 9),
+    ("32", ["BE"], 8, 9),
+    ("33", ["FR"], 9, 9),
+    ("34", ["ES"], 9, 9),
+    ("36", ["HU"], 8, 9),
+    ("39", ["IT", "VA"], 6, 11),
+    ("40", ["RO"], 9, 9),
+    ("41", ["CH"], 9, 9),
+    ("43", ["AT"], 4, 13),
+    ("44", ["GB", "GG", "JE", "IM"], 7, 10),
+    ("45", ["DK"], 8, 8),
+    ("46", ["SE"], 7, 13),
+    ("47", ["NO", "SJ"], 5, 8),
+    ("48", ["PL"], 9, 9),
+    ("49", ["DE"], 6, 13),
+    ("51", ["PE"], 8, 9),
+    ("52", ["MX"], 10, 11),
+    ("53", ["CU"], 6, 8),
+    ("54", ["AR"], 10, 11),
+    ("55", ["BR"], 10, 11),
+    ("56", ["CL"], 9, 9),
+    ("57", ["CO"], 10, 10),
+    ("58", ["VE"], 10, 10),
+    ("60", ["MY"], 8, 10),
+    ("61", ["AU", "CX", "CC"], 9, 9),
+    ("62", ["ID"], 8, 12),
+    ("63", ["PH"], 8, 10),
+    ("64", ["NZ"], 8, 10),
+    ("65", ["SG"], 8, 8),
+    ("66", ["TH"], 8, 9),
+    ("81", ["JP"], 9, 10),
+    ("82", ["KR"], 8, 10),
+    ("84", ["VN"], 9, 10),
+    ("86", ["CN"], 10, 11),
+    ("90", ["TR"], 10, 10),
+    ("91", ["IN"], 10, 10),
+    ("92", ["PK"], 9, 10),
+    ("93", ["AF"], 9, 9),
+    ("94", ["LK"], 9, 9),
+    ("95", ["MM"], 7, 10),
+    ("98", ["IR"], 10, 10),
+    ("211", ["SS"], 9, 9),
+    ("212", ["MA", "EH"], 9, 9),
+    ("213", ["DZ"], 8, 9),
+    ("216", ["TN"], 8, 8),
+    ("218", ["LY"], 9, 9),
+    ("220", ["GM"], 7, 7),
+    ("221", ["SN"], 9, 9),
+    ("222", ["MR"], 8, 8),
+    ("223", ["ML"], 8, 8),
+    ("224", ["GN"], 9, 9),
+    ("225", ["CI"], 10, 10),
+    ("226", ["BF"], 8, 8),
+    ("227", ["NE"], 8, 8),
+    ("228", ["TG"], 8, 8),
+    ("229", ["BJ"], 8, 10),
+    ("230", ["MU"], 7, 8),
+    ("231", ["LR"], 7, 9),
+    ("232", ["SL"], 8, 8),
+    ("233", ["GH"], 9, 9),
+    ("234", ["NG"], 8, 10),
+    ("235", ["TD"], 8, 8),
+    ("236", ["CF"], 8, 8),
+    ("237", ["CM"], 9, 9),
+    ("238", ["CV"], 7, 7),
+    ("239", ["ST"], 7, 7),
+    ("240", ["GQ"], 9, 9),
+    ("241", ["GA"], 7, 8),
+    ("242", ["CG"], 9, 9),
+    ("243", ["CD"], 9, 9),
+    ("244", ["AO"], 9, 9),
+    ("245", ["GW"], 9, 9),
+    ("246", ["IO"], 7, 7),
+    ("248", ["SC"], 7, 7),
+    ("249", ["SD"], 9, 9),
+    ("250", ["RW"], 9, 9),
+    ("251", ["ET"], 9, 9),
+    ("252", ["SO"], 7, 9),
+    ("253", ["DJ"], 8, 8),
+    ("254", ["KE"], 9, 10),
+    ("255", ["TZ"], 9, 9),
+    ("256", ["UG"], 9, 9),
+    ("257", ["BI"], 8, 8),
+    ("258", ["MZ"], 8, 9),
+    ("260", ["ZM"], 9, 9),
+    ("261", ["MG"], 9, 9),
+    ("262", ["RE", "YT"], 9, 9),
+    ("263", ["ZW"], 9, 9),
+    ("264", ["NA"], 8, 9),
+    ("265", ["MW"], 7, 9),
+    ("266", ["LS"], 8, 8),
+    ("267", ["BW"], 7, 8),
+    ("268", ["SZ"], 8, 8),
+    ("269", ["KM"], 7, 7),
+    ("290", ["SH", "TA"], 4, 5),
+    ("291", ["ER"], 7, 7),
+    ("297", ["AW"], 7, 7),
+    ("298", ["FO"], 6, 6),
+    ("299", ["GL"], 6, 6),
+    ("350", ["GI"], 8, 8),
+    ("351", ["PT"], 9, 9),
+    ("352", ["LU"], 4, 11),
+    ("353", ["IE"], 7, 9),
+    ("354", ["IS"], 7, 7),
+    ("355", ["AL"], 8, 9),
+    ("356", ["MT"], 8, 8),
+    ("357", ["CY"], 8, 8),
+    ("358", ["FI", "AX"], 5, 12),
+    ("359", ["BG"], 7, 9),
+    ("370", ["LT"], 8, 8),
+    ("371", ["LV"], 8, 8),
+    ("372", ["EE"], 7, 8),
+    ("373", ["MD"], 8, 8),
+    ("374", ["AM"], 8, 8),
+    ("375", ["BY"], 9, 9),
+    ("376", ["AD"], 6, 6),
+    ("377", ["MC"], 8, 9),
+    ("378", ["SM"], 6, 10),
+    ("380", ["UA"], 9, 9),
+    ("381", ["RS"], 8, 9),
+    ("382", ["ME"], 8, 8),
+    ("383", ["XK"], 8, 8),
+    ("385", ["HR"], 8, 9),
+    ("386", ["SI"], 8, 8),
+    ("387", ["BA"], 8, 8),
+    ("389", ["MK"], 8, 8),
+    ("420", ["CZ"], 9, 9),
+    ("421", ["SK"], 9, 9),
+    ("423", ["LI"], 7, 7),
+    ("500", ["FK"], 5, 5),
+    ("501", ["BZ"], 7, 7),
+    ("502", ["GT"], 8, 8),
+    ("503", ["SV"], 8, 8),
+    ("504", ["HN"], 8, 8),
+    ("505", ["NI"], 8, 8),
+    ("506", ["CR"], 8, 8),
+    ("507", ["PA"], 7, 8),
+    ("508", ["PM"], 6, 6),
+    ("509", ["HT"], 8, 8),
+    ("590", ["GP", "BL", "MF"], 9, 9),
+    ("591", ["BO"], 8, 8),
+    ("592", ["GY"], 7, 7),
+    ("593", ["EC"], 8, 9),
+    ("594", ["GF"], 9, 9),
+    ("595", ["PY"], 9, 9),
+    ("596", ["MQ"], 9, 9),
+    ("597", ["SR"], 6, 7),
+    ("598", ["UY"], 8, 8),
+    ("599", ["CW", "BQ"], 7, 8),
+    ("670", ["TL"], 7, 8),
+    ("672", ["NF"], 6, 6),
+    ("673", ["BN"], 7, 7),
+    ("674", ["NR"], 7, 7),
+    ("675", ["PG"], 7, 8),
+    ("676", ["TO"], 5, 7),
+    ("677", ["SB"], 5, 7),
+    ("678", ["VU"], 5, 7),
+    ("679", ["FJ"], 7, 7),
+    ("680", ["PW"], 7, 7),
+    ("681", ["WF"], 6, 6),
+    ("682", ["CK"], 5, 5),
+    ("683", ["NU"], 4, 4),
+    ("685", ["WS"], 5, 7),
+    ("686", ["KI"], 5, 8),
+    ("687", ["NC"], 6, 6),
+    ("688", ["TV"], 5, 6),
+    ("689", ["PF"], 8, 8),
+    ("690", ["TK"], 4, 4),
+    ("691", ["FM"], 7, 7),
+    ("692", ["MH"], 7, 7),
+    ("850", ["KP"], 8, 10),
+    ("852", ["HK"], 8, 8),
+    ("853", ["MO"], 8, 8),
+    ("855", ["KH"], 8, 9),
+    ("856", ["LA"], 8, 10),
+    ("880", ["BD"], 10, 10),
+    ("886", ["TW"], 8, 9),
+    ("960", ["MV"], 7, 7),
+    ("961", ["LB"], 7, 8),
+    ("962", ["JO"], 8, 9),
+    ("963", ["SY"], 9, 9),
+    ("964", ["IQ"], 10, 10),
+    ("965", ["KW"], 8, 8),
+    ("966", ["SA"], 9, 9),
+    ("967", ["YE"], 9, 9),
+    ("968", ["OM"], 8, 8),
+    ("970", ["PS"], 9, 9),
+    ("971", ["AE"], 8, 9),
+    ("972", ["IL"], 8, 9),
+    ("973", ["BH"], 8, 8),
+    ("974", ["QA"], 8, 8),
+    ("975", ["BT"], 8, 8),
+    ("976", ["MN"], 8, 8),
+    ("977", ["NP"], 10, 10),
+    ("992", ["TJ"], 9, 9),
+    ("993", ["TM"], 8, 8),
+    ("994", ["AZ"], 9, 9),
+    ("995", ["GE"], 9, 9),
+    ("996", ["KG"], 9, 9),
+    ("998", ["UZ"], 9, 9),
+];
+
+/// Regions where a leading zero is part of the national number rather than a trunk prefix.
+const REGIONS_KEEPING_LEADING_ZERO: &[&str] = &["IT", "VA", "SM"];
+
+
+fn find_by_number(digits: &str) -> Option<&'static CallingCode> {
+    (1..=3)
+        .filter_map(|len| digits.get(..len))
+        .find_map(|prefix| CALLING_CODES.iter().find(|entry| entry.code == prefix))
+}
+
+fn find_by_region(region: &str) -> Option<&'static CallingCode> {
+    CALLING_CODES
+        .iter()
+        .find(|entry| entry.regions.iter().any(|r| r.eq_ignore_ascii_case(region)))
+}
+
+
+/// A validated phone number in `wa_id` form: country calling code and national
+/// number as digits only.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct PhoneNumber {
+    digits: String,
+    country_code_len: usize,
+}
+
+impl PhoneNumber {
+
+    /// Parses a number written in international format.
+    ///
+    /// Accepts a leading `+` or `00`, or the bare `wa_id` digits, with any of
+    /// spaces, dashes, dots, slashes and parentheses as separators.
+    pub fn parse(input: &str) -> WhatsAppResult<Self> {
+        Self::parse_with_region(input, None)
+    }
+
+    /// Parses a number, reading it in the national format of `default_region`
+    /// (an ISO 3166 code such as `GB`) when it has no international prefix.
+    pub fn parse_with_region(input: &str, default_region: Option<&str>) -> WhatsAppResult<Self> {
+        let trimmed = input.trim();
+        let (has_plus, rest) = match trimmed.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+
+        let mut digits = String::with_capacity(rest.len());
+        for c in rest.chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                ' ' | '-' | '.' | '(' | ')' | '/' | '\u{a0}' => {}
+                _ => {
+                    return Err(invalid(input, &format!("unexpected character '{}'", c)));
+                }
+            }
+        }
+
+        if digits.is_empty() {
+            return Err(invalid(input, "no digits"));
+        }
+
+        if has_plus {
+            return Self::from_international(input, &digits);
+        }
+
+        if let Some(international) = digits.strip_prefix("00") {
+            return Self::from_international(input, international);
+        }
+
+        match default_region {
+            Some(region) => Self::from_national(input, &digits, region),
+            None => Self::from_international(input, &digits),
+        }
+    }
+
+    fn from_international(input: &str, digits: &str) -> WhatsAppResult<Self> {
+        let entry = find_by_number(digits)
+            .ok_or_else(|| invalid(input, "unknown country calling code"))?;
+
+        Self::build(input, entry, &digits[entry.code.len()..])
+    }
+
+    fn from_national(input: &str, digits: &str, region: &str) -> WhatsAppResult<Self> {
+        let entry = find_by_region(region).ok_or_else(|| {
+            WhatsAppError::ValidationError(format!("Unknown region '{}'", region))
+        })?;
+
+        let national = match entry.code {
+            // NANP numbers are dialled nationally with a leading 1
+            "1" if digits.len() == 11 => digits.strip_prefix('1').unwrap_or(digits),
+            // Russia and Kazakhstan use 8 as the trunk prefix
+            "7" if digits.len() == 11 => digits.strip_prefix('8').unwrap_or(digits),
+            _ if REGIONS_KEEPING_LEADING_ZERO.contains(&entry.regions[0]) => digits,
+            _ => digits.strip_prefix('0').unwrap_or(digits),
+        };
+
+        Self::build(input, entry, national)
+    }
+
+    fn build(input: &str, entry: &CallingCode, national: &str) -> WhatsAppResult<Self> {
+        if national.len() < entry.min_national_digits || national.len() > entry.max_national_digits {
+            return Err(invalid(
+                input,
+                &format!(
+                    "national number for +{} must have {} to {} digits, got {}",
+                    entry.code,
+                    entry.min_national_digits,
+                    entry.max_national_digits,
+                    national.len()
+                ),
+            ));
+        }
+
+        // Mexican wa_ids keep the mobile "1" that was dropped from dialling in 2019
+        if entry.code == "52" && national.len() == 11 && !national.starts_with('1') {
+            return Err(invalid(input, "11 digit national number for +52 must start with 1"));
+        }
+
+        if entry.code.len() + national.len() > MAX_E164_DIGITS {
+            return Err(invalid(input, "longer than 15 digits"));
+        }
+
+        Ok(Self {
+            digits: format!("{}{}", entry.code, national),
+            country_code_len: entry.code.len(),
+        })
+    }
+
+    /// The number in `wa_id` form, e.g. `15551234567`.
+    pub fn as_str(&self) -> &str {
+        &self.digits
+    }
+
+    /// The number in E.164 form, e.g. `+15551234567`.
+    pub fn to_e164(&self) -> String {
+        format!("+{}", self.digits)
+    }
+
+    pub fn country_code(&self) -> &str {
+        &self.digits[..self.country_code_len]
+    }
+
+    pub fn national_number(&self) -> &str {
+        &self.digits[self.country_code_len..]
+    }
+
+    /// Main region for the number's calling code. Codes shared by several
+    /// regions, such as `1`, report the most populous one.
+    pub fn region(&self) -> Option<&'static str> {
+        find_by_number(&self.digits).map(|entry| entry.regions[0])
+    }
+}
+
+fn invalid(input: &str, reason: &str) -> WhatsAppError {
+    WhatsAppError::ValidationError(format!("Invalid phone number '{}': {}", input, reason))
+}
+
+impl fmt::Display for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{}", self.digits)
+    }
+}
+
+impl FromStr for PhoneNumber {
+    type Err = WhatsAppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<&str> for PhoneNumber {
+    type Error = WhatsAppError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(value)
+    }
+}
+
+impl TryFrom<String> for PhoneNumber {
+    type Error = WhatsAppError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<PhoneNumber> for String {
+    fn from(number: PhoneNumber) -> Self {
+        number.digits
+    }
+}
+
+impl From<&PhoneNumber> for String {
+    fn from(number: &PhoneNumber) -> Self {
+        number.digits.clone()
+    }
+}
+
+impl AsRef<str> for PhoneNumber {
+    fn as_ref(&self) -> &str {
+        &self.digits
+    }
+}
+
+/// Normalizes user input to `wa_id` form. See [`PhoneNumber::parse_with_region`].
+pub fn normalize(input: &str, default_region: Option<&str>) -> WhatsAppResult<String> {
+    PhoneNumber::parse_with_region(input, default_region).map(String::from)
+}
+
+/// Key for per-user state: the `wa_id` form when `input` parses, else `input` as is.
+pub(crate) fn wa_id_key(input: &str) -> String {
+    PhoneNumber::parse(input)
+        .map(String::from)
+        .unwrap_or_else(|_| input.to_string())
+}
+
+/// Whether the input is a valid international phone number.
+pub fn is_valid(input: &str) -> bool {
+    PhoneNumber::parse(input).is_ok()
+}
@@ -0,0 +1,252 @@
+//! Splitting of text that exceeds the message length limit
+//!
+//! Parts are cut at the strongest boundary available near the limit, in order:
+//! paragraph, line, sentence, word and finally grapheme cluster. URLs are never
+//! cut while there is another option, and a cut inside a formatted span closes
+//! the span at the end of one part and reopens it at the start of the next.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+const FENCE: &str = "```";
+const INLINE_MARKERS: &[char] = &['*', '_', '~', '`'];
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Boundary {
+    Grapheme,
+    Word,
+    Sentence,
+    Line,
+    Paragraph,
+}
+
+/// A formatted region, from the byte offset of its opening marker to the byte
+/// offset just past its closing marker.
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: usize,
+    end: usize,
+    marker: &'static str,
+}
+
+impl Span {
+    /// Code blocks are closed and reopened on their own line.
+    fn closing(&self) -> &'static str {
+        if self.marker == FENCE { "\n```" } else { self.marker }
+    }
+
+    fn opening(&self) -> &'static str {
+        if self.marker == FENCE { "```\n" } else { self.marker }
+    }
+}
+
+
+/// Splits `text` into parts of at most `max_chars` characters each.
+pub fn split_text(text: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(8);
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return if text.is_empty() { Vec::new() } else { vec![text.to_string()] };
+    }
+
+    let spans = formatting_spans(text);
+    let urls = url_ranges(text);
+    let boundaries: Vec<usize> = text
+        .grapheme_indices(true)
+        .map(|(offset, _)| offset)
+        .skip(1)
+        .chain(std::iter::once(text.len()))
+        .collect();
+
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut first_boundary = 0;
+    let mut reopen = String::new();
+
+    while start < text.len() {
+        let budget = max_chars - reopen.chars().count();
+
+        // Boundaries reachable within the budget, counting characters as we go
+        while boundaries[first_boundary] <= start {
+            first_boundary += 1;
+        }
+        let mut window = Vec::new();
+        let (mut previous, mut count) = (start, 0);
+        for &b in &boundaries[first_boundary..] {
+            count += text[previous..b].chars().count();
+            if count > budget {
+                break;
+            }
+            window.push(b);
+            previous = b;
+        }
+
+        if window.last() == Some(&text.len()) {
+            push_part(&mut parts, format!("{}{}", reopen, &text[start..]));
+            break;
+        }
+
+        let (end, open) = choose_split(text, start, &window, &spans, &urls, budget);
+        let closing: String = open.iter().rev().map(Span::closing).collect();
+        let part = format!("{}{}{}", reopen, text[start..end].trim_end(), closing);
+        push_part(&mut parts, part);
+
+        reopen = open.iter().map(Span::opening).collect();
+        start = end + (text[end..].len() - text[end..].trim_start().len());
+    }
+
+    parts
+}
+
+fn push_part(parts: &mut Vec<String>, part: String) {
+    if !part.trim().is_empty() {
+        parts.push(part);
+    }
+}
+
+/// Picks the end of the next part and the spans left open by cutting there.
+fn choose_split(
+    text: &str,
+    start: usize,
+    window: &[usize],
+    spans: &[Span],
+    urls: &[(usize, usize)],
+    budget: usize,
+) -> (usize, Vec<Span>) {
+    let in_url = |p: usize| urls.iter().any(|&(s, e)| s < p && p < e);
+    let open_at = |p: usize| -> Vec<Span> {
+        spans
+            .iter()
+            .copied()
+            .filter(|span| span.start < p && p < span.end)
+            .collect()
+    };
+    let half = window.len() / 2;
+
+    // Best boundary outside any URL or formatted span, preferring the later half of the window
+    for candidates in [&window[half..], window] {
+        let best = candidates
+            .iter()
+            .copied()
+            .filter(|&p| !in_url(p) && open_at(p).is_empty())
+            .max_by_key(|&p| (boundary_kind(text, p), p));
+        if let Some(p) = best {
+            if boundary_kind(text, p) > Boundary::Grapheme || candidates.len() == window.len() {
+                return (p, Vec::new());
+            }
+        }
+    }
+
+    // Otherwise cut inside a span, leaving room to close its markers
+    let best = window
+        .iter()
+        .copied()
+        .filter(|&p| !in_url(p))
+        .filter(|&p| {
+            let closing: usize = open_at(p).iter().map(|span| span.closing().len()).sum();
+            text[start..p].chars().count() + closing <= budget
+        })
+        .max_by_key(|&p| (boundary_kind(text, p), p));
+
+    match best {
+        Some(p) => (p, open_at(p)),
+        // A URL longer than the limit has to be cut
+        None => {
+            let p = window.last().copied().unwrap_or(text.len());
+            (p, Vec::new())
+        }
+    }
+}
+
+fn boundary_kind(text: &str, p: usize) -> Boundary {
+    let before = &text[..p];
+    if before.ends_with("\n\n") {
+        return Boundary::Paragraph;
+    }
+    if before.ends_with('\n') {
+        return Boundary::Line;
+    }
+    if before.ends_with(char::is_whitespace) {
+        let trimmed = before.trim_end();
+        if trimmed.ends_with(['.', '!', '?', '…']) {
+            return Boundary::Sentence;
+        }
+        return Boundary::Word;
+    }
+    Boundary::Grapheme
+}
+
+fn url_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+
+    for word in text.split_inclusive(char::is_whitespace) {
+        let token = word.trim_end();
+        if token.starts_with("http://") || token.starts_with("https://") || token.starts_with("www.") {
+            ranges.push((offset, offset + token.len()));
+        }
+        offset += word.len();
+    }
+
+    ranges
+}
+
+/// Finds code fences and inline formatting spans, using the same opening and
+/// closing rules WhatsApp applies.
+fn formatting_spans(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+
+    let fences: Vec<usize> = text.match_indices(FENCE).map(|(offset, _)| offset).collect();
+    for pair in fences.chunks(2) {
+        if let [open, close] = pair {
+            spans.push(Span {
+                start: *open,
+                end: close + FENCE.len(),
+                marker: FENCE,
+            });
+        }
+    }
+    let fenced = spans.clone();
+    let in_fence = |offset: usize| fenced.iter().any(|span| span.start <= offset && offset < span.end);
+
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut used = vec![false; chars.len()];
+
+    for i in 0..chars.len() {
+        let (offset, c) = chars[i];
+        if used[i] || !INLINE_MARKERS.contains(&c) || in_fence(offset) {
+            continue;
+        }
+
+        let opens = (i == 0 || !chars[i - 1].1.is_alphanumeric())
+            && chars.get(i + 1).is_some_and(|(_, n)| !n.is_whitespace() && *n != c);
+        if !opens {
+            continue;
+        }
+
+        // Inline formatting does not continue past the end of a line
+        let close = (i + 2..chars.len()).take_while(|&j| chars[j].1 != '\n').find(|&j| {
+            !used[j]
+                && chars[j].1 == c
+                && !chars[j - 1].1.is_whitespace()
+                && chars.get(j + 1).map_or(true, |(_, n)| !n.is_alphanumeric())
+        });
+
+        if let Some(j) = close {
+            used[i] = true;
+            used[j] = true;
+            spans.push(Span {
+                start: offset,
+                end: chars[j].0 + c.len_utf8(),
+                marker: match c {
+                    '*' => "*",
+                    '_' => "_",
+                    '~' => "~",
+                    _ => "`",
+                },
+            });
+        }
+    }
+
+    spans
+}
@@ -0,0 +1,524 @@
+//! Conversion between contact cards and vCard text
+//!
+//! [`Contact`] mirrors the Cloud API's contact object. This module writes it as
+//! a vCard 3.0 (RFC 2426) or 4.0 (RFC 6350) and reads vCards of either version
+//! back, with their multi-valued `TEL`, `EMAIL`, `ADR` and `URL` properties and
+//! `TYPE` parameters. The `waid` parameter WhatsApp puts on `TEL` lines is kept
+//! as the phone's `wa_id`.
+//!
+//! Properties without a counterpart in [`Contact`] are skipped on import, and
+//! an address's `country_code` has no vCard counterpart on export.
+
+use std::fmt;
+
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::types::messages::{
+    Contact, ContactAddress, ContactEmail, ContactName, ContactOrg, ContactPhone, ContactUrl,
+};
+
+/// Longest line allowed before folding, in octets, line break excluded.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// `TYPE` values that say nothing about which phone, email, address or URL it is.
+const GENERIC_TYPES: &[&str] = &["pref", "voice", "internet", "x400", "intl", "postal", "parcel", "dom"];
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VCardVersion {
+
+    V3,
+
+    V4,
+}
+
+impl VCardVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V3 => "3.0",
+            Self::V4 => "4.0",
+        }
+    }
+}
+
+impl fmt::Display for VCardVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+impl Contact {
+    /// Writes the contact as a single vCard with CRLF line endings.
+    pub fn to_vcard(&self, version: VCardVersion) -> String {
+        let mut card = VCardWriter::new(version);
+
+        card.line("BEGIN", &[], "VCARD");
+        card.line("VERSION", &[], version.as_str());
+        card.line("FN", &[], &escape(&self.name.formatted_name));
+        card.line("N", &[], &structured(&[
+            self.name.last_name.as_deref(),
+            Some(&self.name.first_name),
+            self.name.middle_name.as_deref(),
+            self.name.prefix.as_deref(),
+            self.name.suffix.as_deref(),
+        ]));
+
+        if let Some(org) = &self.org {
+            if org.company.is_some() || org.department.is_some() {
+                card.line("ORG", &[], &structured(&[org.company.as_deref(), org.department.as_deref()]));
+            }
+            if let Some(title) = &org.title {
+                card.line("TITLE", &[], &escape(title));
+            }
+        }
+
+        for phone in self.phones.iter().flatten() {
+            let mut params = card.type_param(&phone.r#type);
+            // vCard 4.0 expects a `tel:` URI unless told otherwise, and typed numbers rarely are one.
+            if version == VCardVersion::V4 {
+                params.push("VALUE=text".to_string());
+            }
+            if let Some(wa_id) = &phone.wa_id {
+                params.push(format!("waid={}", wa_id));
+            }
+            card.line("TEL", &params, &escape(&phone.phone));
+        }
+
+        for email in self.emails.iter().flatten() {
+            card.line("EMAIL", &card.type_param(&email.r#type), &escape(&email.email));
+        }
+
+        for address in self.addresses.iter().flatten() {
+            let params = card.type_param(address.r#type.as_deref().unwrap_or(""));
+            card.line("ADR", &params, &structured(&[
+                None,
+                None,
+                address.street.as_deref(),
+                address.city.as_deref(),
+                address.state.as_deref(),
+                address.zip.as_deref(),
+                address.country.as_deref(),
+            ]));
+        }
+
+        for url in self.urls.iter().flatten() {
+            card.line("URL", &card.type_param(&url.r#type), &escape(&url.url));
+        }
+
+        if let Some(birthday) = &self.birthday {
+            // vCard 4.0 only allows the basic ISO 8601 format.
+            let birthday = match version {
+                VCardVersion::V3 => birthday.clone(),
+                VCardVersion::V4 => birthday.replace('-', ""),
+            };
+            card.line("BDAY", &[], &birthday);
+        }
+
+        card.line("END", &[], "VCARD");
+        card.finish()
+    }
+
+    /// Reads a contact from text holding exactly one vCard.
+    pub fn from_vcard(text: &str) -> WhatsAppResult<Self> {
+        let mut contacts = parse_vcards(text)?;
+        match contacts.len() {
+            1 => Ok(contacts.remove(0)),
+            0 => Err(WhatsAppError::ValidationError("no vCard found".to_string())),
+            n => Err(WhatsAppError::ValidationError(format!("expected one vCard, found {}", n))),
+        }
+    }
+}
+
+
+/// Writes several contacts as consecutive vCards, as in a `.vcf` file.
+pub fn to_vcards(contacts: &[Contact], version: VCardVersion) -> String {
+    contacts.iter().map(|contact| contact.to_vcard(version)).collect()
+}
+
+/// Reads every vCard in `text`, such as the content of a `.vcf` file.
+pub fn parse_vcards(text: &str) -> WhatsAppResult<Vec<Contact>> {
+    let mut contacts = Vec::new();
+    let mut card: Option<Vec<Property>> = None;
+
+    for line in unfold(text) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let property = Property::parse(&line)?;
+
+        match (property.name.as_str(), &mut card) {
+            ("BEGIN", None) if property.value.eq_ignore_ascii_case("VCARD") => card = Some(Vec::new()),
+            ("BEGIN", Some(_)) => {
+                return Err(WhatsAppError::ValidationError("nested vCards are not supported".to_string()))
+            }
+            ("END", Some(properties)) if property.value.eq_ignore_ascii_case("VCARD") => {
+                contacts.push(contact_from_properties(properties)?);
+                card = None;
+            }
+            (_, Some(properties)) => properties.push(property),
+            (_, None) => {
+                return Err(WhatsAppError::ValidationError(format!(
+                    "{} outside of BEGIN:VCARD and END:VCARD",
+                    property.name
+                )))
+            }
+        }
+    }
+
+    if card.is_some() {
+        return Err(WhatsAppError::ValidationError("vCard is missing END:VCARD".to_string()));
+    }
+    Ok(contacts)
+}
+
+
+struct VCardWriter {
+    version: VCardVersion,
+    output: String,
+}
+
+impl VCardWriter {
+    fn new(version: VCardVersion) -> Self {
+        Self {
+            version,
+            output: String::new(),
+        }
+    }
+
+    /// `TYPE` parameter for a Cloud API type such as `CELL` or `WORK`; vCard 4.0 uses lowercase.
+    fn type_param(&self, r#type: &str) -> Vec<String> {
+        if r#type.is_empty() {
+            return Vec::new();
+        }
+        match self.version {
+            VCardVersion::V3 => vec![format!("TYPE={}", r#type.to_uppercase())],
+            VCardVersion::V4 => vec![format!("TYPE={}", r#type.to_lowercase())],
+        }
+    }
+
+    fn line(&mut self, name: &str, params: &[String], value: &str) {
+        let mut line = name.to_string();
+        for param in params {
+            line.push(';');
+            line.push_str(param);
+        }
+        line.push(':');
+        line.push_str(value);
+
+        self.fold(&line);
+    }
+
+    /// Folds at 75 octets without splitting a UTF-8 sequence.
+    fn fold(&mut self, line: &str) {
+        let mut octets = 0;
+        for c in line.chars() {
+            if octets + c.len_utf8() > MAX_LINE_OCTETS {
+                self.output.push_str("\r\n ");
+                octets = 1;
+            }
+            self.output.push(c);
+            octets += c.len_utf8();
+        }
+        self.output.push_str("\r\n");
+    }
+
+    fn finish(self) -> String {
+        self.output
+    }
+}
+
+
+#[derive(Debug)]
+struct Property {
+    /// Uppercased, without its group.
+    name: String,
+    /// Parameter names uppercased; `TYPE` lists are split into one entry per value.
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> WhatsAppResult<Self> {
+        let colon = value_start(line).ok_or_else(|| {
+            WhatsAppError::ValidationError(format!("vCard line without a value: {}", line))
+        })?;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+        let mut parts = split_unquoted(head, ';').into_iter();
+        let name = parts.next().unwrap_or_default();
+        let name = name.rsplit('.').next().unwrap_or_default().to_uppercase();
+
+        let mut params = Vec::new();
+        for part in parts {
+            match part.split_once('=') {
+                Some((key, value)) => {
+                    let key = key.trim().to_uppercase();
+                    let value = value.trim_matches('"');
+                    if key == "TYPE" {
+                        params.extend(value.split(',').map(|value| (key.clone(), value.trim().to_string())));
+                    } else {
+                        params.push((key, value.to_string()));
+                    }
+                }
+                // vCard 2.1 writes types without `TYPE=`.
+                None => params.push(("TYPE".to_string(), part)),
+            }
+        }
+
+        Ok(Self {
+            name,
+            params,
+            value: value.to_string(),
+        })
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// The first `TYPE` that tells values apart, uppercased as the Cloud API expects.
+    fn r#type(&self) -> String {
+        self.params
+            .iter()
+            .filter(|(key, _)| key == "TYPE")
+            .map(|(_, value)| value.to_lowercase())
+            .find(|value| !GENERIC_TYPES.contains(&value.as_str()))
+            .map(|value| value.to_uppercase())
+            .unwrap_or_default()
+    }
+
+    fn text(&self) -> String {
+        unescape(&self.value)
+    }
+
+    fn components(&self) -> Vec<String> {
+        split_unescaped(&self.value, ';').iter().map(|component| unescape(component)).collect()
+    }
+}
+
+
+fn contact_from_properties(properties: &[Property]) -> WhatsAppResult<Contact> {
+    let mut formatted_name = None;
+    let mut name_components = None;
+    let mut org = ContactOrg {
+        company: None,
+        department: None,
+        title: None,
+    };
+    let mut phones = Vec::new();
+    let mut emails = Vec::new();
+    let mut addresses = Vec::new();
+    let mut urls = Vec::new();
+    let mut birthday = None;
+
+    for property in properties {
+        match property.name.as_str() {
+            "FN" => formatted_name = non_empty(property.text()),
+            "N" => name_components = Some(property.components()),
+            "ORG" => {
+                let mut components = property.components().into_iter();
+                org.company = components.next().and_then(non_empty);
+                let departments: Vec<String> = components.filter(|c| !c.is_empty()).collect();
+                org.department = non_empty(departments.join(", "));
+            }
+            "TITLE" => org.title = non_empty(property.text()),
+            "TEL" => {
+                let phone = property.text();
+                let phone = phone.strip_prefix("tel:").unwrap_or(&phone).to_string();
+                if !phone.is_empty() {
+                    phones.push(ContactPhone {
+                        phone,
+                        r#type: property.r#type(),
+                        wa_id: property.param("WAID").map(str::to_string),
+                    });
+                }
+            }
+            "EMAIL" => {
+                if let Some(email) = non_empty(property.text()) {
+                    emails.push(ContactEmail {
+                        email,
+                        r#type: property.r#type(),
+                    });
+                }
+            }
+            "ADR" => {
+                let mut components = property.components();
+                components.resize(7, String::new());
+                let street: Vec<&str> = components[..3]
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|c| !c.is_empty())
+                    .collect();
+
+                addresses.push(ContactAddress {
+                    street: non_empty(street.join(", ")),
+                    city: non_empty(components[3].clone()),
+                    state: non_empty(components[4].clone()),
+                    zip: non_empty(components[5].clone()),
+                    country: non_empty(components[6].clone()),
+                    country_code: None,
+                    r#type: non_empty(property.r#type()),
+                });
+            }
+            "URL" => {
+                if let Some(url) = non_empty(property.text()) {
+                    urls.push(ContactUrl {
+                        url,
+                        r#type: property.r#type(),
+                    });
+                }
+            }
+            "BDAY" => birthday = parse_birthday(&property.value),
+            _ => {}
+        }
+    }
+
+    let components = name_components.unwrap_or_default();
+    let component = |i: usize| components.get(i).cloned().and_then(non_empty);
+
+    let formatted_name = formatted_name
+        .or_else(|| {
+            let parts: Vec<String> = [3, 1, 2, 0, 4].iter().filter_map(|&i| component(i)).collect();
+            non_empty(parts.join(" "))
+        })
+        .ok_or_else(|| WhatsAppError::ValidationError("vCard has neither FN nor N".to_string()))?;
+
+    let name = ContactName {
+        first_name: component(1).unwrap_or_else(|| formatted_name.clone()),
+        last_name: component(0),
+        middle_name: component(2),
+        prefix: component(3),
+        suffix: component(4),
+        formatted_name,
+    };
+
+    let has_org = org.company.is_some() || org.department.is_some() || org.title.is_some();
+
+    Ok(Contact {
+        addresses: Some(addresses).filter(|v| !v.is_empty()),
+        birthday,
+        emails: Some(emails).filter(|v| !v.is_empty()),
+        name,
+        org: Some(org).filter(|_| has_org),
+        phones: Some(phones).filter(|v| !v.is_empty()),
+        urls: Some(urls).filter(|v| !v.is_empty()),
+    })
+}
+
+/// Normalizes a `BDAY` to the Cloud API's `YYYY-MM-DD`; dates without a year are dropped.
+fn parse_birthday(value: &str) -> Option<String> {
+    let date = value.split('T').next().unwrap_or_default().replace('-', "");
+    if date.len() == 8 && date.chars().all(|c| c.is_ascii_digit()) {
+        Some(format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..]))
+    } else {
+        None
+    }
+}
+
+
+/// Joins folded lines; continuation lines start with a space or tab.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Byte offset of the colon separating a line's name and parameters from its value.
+fn value_start(line: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_unquoted(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                parts.last_mut().unwrap().push(c);
+            }
+            c if c == separator && !quoted => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// Splits a structured value, keeping escapes for `unescape`.
+fn split_unescaped(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().unwrap();
+        match c {
+            '\\' => {
+                part.push(c);
+                if let Some(escaped) = chars.next() {
+                    part.push(escaped);
+                }
+            }
+            c if c == separator => parts.push(String::new()),
+            c => part.push(c),
+        }
+    }
+    parts
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            ',' => escaped.push_str("\\,"),
+            ';' => escaped.push_str("\\;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+fn structured(components: &[Option<&str>]) -> String {
+    components
+        .iter()
+        .map(|component| component.map(escape).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() { None } else { Some(value.to_string()) }
+}
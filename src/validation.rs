@@ -0,0 +1,378 @@
+//! Client-side checks for outgoing messages
+//!
+//! The Cloud API enforces length and structure limits on every message, but only
+//! reports the first problem it finds. [`Validate`] checks the same limits before
+//! a request is made and reports every violation with the path of its field.
+
+use crate::error::{Violation, WhatsAppError, WhatsAppResult};
+use crate::types::messages::*;
+use crate::util::{is_single_emoji, PhoneNumber};
+
+pub const MAX_TEXT_BODY: usize = 4096;
+pub const MAX_CAPTION: usize = 1024;
+pub const MAX_INTERACTIVE_BODY: usize = 1024;
+pub const MAX_HEADER_TEXT: usize = 60;
+pub const MAX_FOOTER_TEXT: usize = 60;
+pub const MAX_REPLY_BUTTONS: usize = 3;
+pub const MAX_BUTTON_TITLE: usize = 20;
+pub const MAX_BUTTON_ID: usize = 256;
+pub const MAX_LIST_SECTIONS: usize = 10;
+pub const MAX_LIST_ROWS: usize = 10;
+pub const MAX_SECTION_TITLE: usize = 24;
+pub const MAX_ROW_TITLE: usize = 24;
+pub const MAX_ROW_DESCRIPTION: usize = 72;
+pub const MAX_ROW_ID: usize = 200;
+
+
+pub trait Validate {
+
+    /// Appends every violation found, with field paths prefixed by `path`.
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>);
+
+    fn violations(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.collect_violations("", &mut violations);
+        violations
+    }
+
+    /// Fails with `WhatsAppError::InvalidMessage` listing all violations.
+    fn validate(&self) -> WhatsAppResult<()> {
+        let violations = self.violations();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(WhatsAppError::InvalidMessage(violations))
+        }
+    }
+}
+
+
+fn field(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn index(path: &str, i: usize) -> String {
+    format!("{}[{}]", path, i)
+}
+
+fn check_length(violations: &mut Vec<Violation>, path: String, value: &str, max: usize) {
+    let length = value.chars().count();
+    if length > max {
+        violations.push(Violation::new(
+            path,
+            format!("must be at most {} characters, got {}", max, length),
+        ));
+    }
+}
+
+fn check_required(violations: &mut Vec<Violation>, path: String, value: &str, max: usize) {
+    if value.trim().is_empty() {
+        violations.push(Violation::new(path, "must not be empty"));
+    } else {
+        check_length(violations, path, value, max);
+    }
+}
+
+fn check_recipient(violations: &mut Vec<Violation>, path: &str, to: &str) {
+    if let Err(WhatsAppError::ValidationError(message)) = PhoneNumber::parse(to) {
+        violations.push(Violation::new(field(path, "to"), message));
+    }
+}
+
+
+impl Validate for SendTextMessage {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        check_recipient(violations, path, &self.to);
+        check_required(violations, field(path, "text"), &self.text, MAX_TEXT_BODY);
+    }
+}
+
+impl Validate for SendMediaMessage {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        check_recipient(violations, path, &self.to);
+
+        match (&self.media_id, &self.media_url) {
+            (Some(_), Some(_)) => violations.push(Violation::new(
+                field(path, "media_id"),
+                "media_id and media_url are mutually exclusive",
+            )),
+            (None, None) => violations.push(Violation::new(
+                field(path, "media_id"),
+                "one of media_id or media_url is required",
+            )),
+            _ => {}
+        }
+
+        if let Some(caption) = &self.caption {
+            if matches!(self.media_type, MediaType::Audio | MediaType::Sticker) {
+                violations.push(Violation::new(
+                    field(path, "caption"),
+                    "is not supported for audio and sticker messages",
+                ));
+            } else {
+                check_length(violations, field(path, "caption"), caption, MAX_CAPTION);
+            }
+        }
+
+        if self.filename.is_some() && self.media_type != MediaType::Document {
+            violations.push(Violation::new(
+                field(path, "filename"),
+                "is only supported for document messages",
+            ));
+        }
+    }
+}
+
+impl Validate for SendLocationMessage {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        check_recipient(violations, path, &self.to);
+
+        if !(-90.0..=90.0).contains(&self.latitude) {
+            violations.push(Violation::new(field(path, "latitude"), "must be between -90 and 90"));
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            violations.push(Violation::new(field(path, "longitude"), "must be between -180 and 180"));
+        }
+    }
+}
+
+impl Validate for SendTemplateMessage {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        check_recipient(violations, path, &self.to);
+        check_required(violations, field(path, "template_name"), &self.template_name, 512);
+        check_required(violations, field(path, "language_code"), &self.language_code, 16);
+    }
+}
+
+impl Validate for InteractiveButtons {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        let buttons_path = field(path, "buttons");
+
+        if self.buttons.is_empty() || self.buttons.len() > MAX_REPLY_BUTTONS {
+            violations.push(Violation::new(
+                buttons_path.clone(),
+                format!("must contain 1 to {} buttons, got {}", MAX_REPLY_BUTTONS, self.buttons.len()),
+            ));
+        }
+
+        let mut ids = Vec::new();
+        for (i, button) in self.buttons.iter().enumerate() {
+            let button_path = index(&buttons_path, i);
+
+            if button.r#type != InteractiveButtonType::Reply {
+                violations.push(Violation::new(
+                    field(&button_path, "type"),
+                    "reply button messages only accept reply buttons",
+                ));
+            }
+
+            match &button.reply {
+                Some(reply) => {
+                    let reply_path = field(&button_path, "reply");
+                    check_required(violations, field(&reply_path, "id"), &reply.id, MAX_BUTTON_ID);
+                    check_required(violations, field(&reply_path, "title"), &reply.title, MAX_BUTTON_TITLE);
+
+                    if ids.contains(&reply.id) {
+                        violations.push(Violation::new(field(&reply_path, "id"), "must be unique"));
+                    }
+                    ids.push(reply.id.clone());
+                }
+                None => violations.push(Violation::new(field(&button_path, "reply"), "is required")),
+            }
+        }
+    }
+}
+
+impl Validate for InteractiveSections {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        check_required(violations, field(path, "button"), &self.button, MAX_BUTTON_TITLE);
+
+        let sections_path = field(path, "sections");
+
+        if self.sections.is_empty() || self.sections.len() > MAX_LIST_SECTIONS {
+            violations.push(Violation::new(
+                sections_path.clone(),
+                format!("must contain 1 to {} sections, got {}", MAX_LIST_SECTIONS, self.sections.len()),
+            ));
+        }
+
+        let total_rows: usize = self.sections.iter().map(|section| section.rows.len()).sum();
+        if total_rows > MAX_LIST_ROWS {
+            violations.push(Violation::new(
+                sections_path.clone(),
+                format!("must contain at most {} rows in total, got {}", MAX_LIST_ROWS, total_rows),
+            ));
+        }
+
+        let mut ids = Vec::new();
+        for (i, section) in self.sections.iter().enumerate() {
+            let section_path = index(&sections_path, i);
+
+            match &section.title {
+                Some(title) => check_required(violations, field(&section_path, "title"), title, MAX_SECTION_TITLE),
+                None if self.sections.len() > 1 => violations.push(Violation::new(
+                    field(&section_path, "title"),
+                    "is required when there is more than one section",
+                )),
+                None => {}
+            }
+
+            if section.rows.is_empty() {
+                violations.push(Violation::new(field(&section_path, "rows"), "must not be empty"));
+            }
+
+            for (j, row) in section.rows.iter().enumerate() {
+                let row_path = index(&field(&section_path, "rows"), j);
+                check_required(violations, field(&row_path, "id"), &row.id, MAX_ROW_ID);
+                check_required(violations, field(&row_path, "title"), &row.title, MAX_ROW_TITLE);
+                if let Some(description) = &row.description {
+                    check_length(violations, field(&row_path, "description"), description, MAX_ROW_DESCRIPTION);
+                }
+
+                if ids.contains(&row.id) {
+                    violations.push(Violation::new(field(&row_path, "id"), "must be unique"));
+                }
+                ids.push(row.id.clone());
+            }
+        }
+    }
+}
+
+impl Validate for InteractiveHeader {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        match self {
+            InteractiveHeader::Text { text } => {
+                check_required(violations, field(path, "text"), text, MAX_HEADER_TEXT)
+            }
+            InteractiveHeader::Video { video: media } | InteractiveHeader::Image { image: media } => {
+                check_required(violations, field(path, "link"), &media.link, usize::MAX)
+            }
+            InteractiveHeader::Document { document } => {
+                check_required(violations, field(path, "link"), &document.link, usize::MAX)
+            }
+        }
+    }
+}
+
+impl Validate for Interactive {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        check_required(violations, field(path, "body.text"), &self.body.text, MAX_INTERACTIVE_BODY);
+
+        if let Some(header) = &self.header {
+            header.collect_violations(&field(path, "header"), violations);
+        }
+        if let Some(footer) = &self.footer {
+            check_required(violations, field(path, "footer.text"), &footer.text, MAX_FOOTER_TEXT);
+        }
+
+        let action_path = field(path, "action");
+        let action_matches_type = match (&self.r#type, &self.action) {
+            (InteractiveType::Button, InteractiveAction::Buttons(buttons)) => {
+                buttons.collect_violations(&action_path, violations);
+                true
+            }
+            (InteractiveType::List, InteractiveAction::Sections(sections)) => {
+                sections.collect_violations(&action_path, violations);
+                true
+            }
+            (InteractiveType::Flow, InteractiveAction::Flow(flow)) => {
+                let parameters = &flow.parameters;
+                if parameters.flow_action == Some(FlowAction::DataExchange) && parameters.flow_token.is_none() {
+                    violations.push(Violation::new(
+                        field(&action_path, "parameters.flow_token"),
+                        "is required for data_exchange Flows",
+                    ));
+                }
+                true
+            }
+            (InteractiveType::Product, InteractiveAction::Product(_))
+            | (InteractiveType::CatalogMessage, InteractiveAction::Catalog(_))
+            | (InteractiveType::AddressMessage, InteractiveAction::Address(_))
+            | (InteractiveType::ProductList, InteractiveAction::ProductList(_))
+            | (InteractiveType::CtaUrl, InteractiveAction::CtaUrl(_))
+            | (InteractiveType::LocationRequestMessage, InteractiveAction::LocationRequest(_)) => true,
+            _ => false,
+        };
+
+        if !action_matches_type {
+            violations.push(Violation::new(
+                action_path,
+                format!("does not match interactive type {:?}", self.r#type),
+            ));
+        }
+    }
+}
+
+impl Validate for SendInteractiveMessage {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        check_recipient(violations, path, &self.to);
+        self.interactive.collect_violations(&field(path, "interactive"), violations);
+    }
+}
+
+impl Validate for Contact {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        check_required(violations, field(path, "name.formatted_name"), &self.name.formatted_name, usize::MAX);
+
+        for (i, phone) in self.phones.iter().flatten().enumerate() {
+            let phone_path = index(&field(path, "phones"), i);
+            check_required(violations, field(&phone_path, "phone"), &phone.phone, usize::MAX);
+        }
+        for (i, email) in self.emails.iter().flatten().enumerate() {
+            let email_path = index(&field(path, "emails"), i);
+            if !email.email.contains('@') {
+                violations.push(Violation::new(field(&email_path, "email"), "is not an email address"));
+            }
+        }
+    }
+}
+
+impl Validate for SendContactMessage {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        check_recipient(violations, path, &self.to);
+
+        let contacts_path = field(path, "contacts");
+        if self.contacts.is_empty() {
+            violations.push(Violation::new(contacts_path.clone(), "must not be empty"));
+        }
+        for (i, contact) in self.contacts.iter().enumerate() {
+            contact.collect_violations(&index(&contacts_path, i), violations);
+        }
+    }
+}
+
+
+impl Validate for SendReactionMessage {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        check_recipient(violations, path, &self.to);
+        if self.message_id.trim().is_empty() {
+            violations.push(Violation::new(field(path, "message_id"), "must not be empty"));
+        }
+
+        // An empty emoji removes the reaction; anything else must be exactly one emoji.
+        if !self.emoji.is_empty() && !is_single_emoji(&self.emoji) {
+            violations.push(Violation::new(
+                field(path, "emoji"),
+                format!("must be a single emoji, got {:?}", self.emoji),
+            ));
+        }
+    }
+}
+
+impl Validate for OutboundMessage {
+    fn collect_violations(&self, path: &str, violations: &mut Vec<Violation>) {
+        match self {
+            Self::Text(message) => message.collect_violations(path, violations),
+            Self::Media(message) => message.collect_violations(path, violations),
+            Self::Location(message) => message.collect_violations(path, violations),
+            Self::Template(message) => message.collect_violations(path, violations),
+            Self::Interactive(message) => message.collect_violations(path, violations),
+            Self::Contact(message) => message.collect_violations(path, violations),
+            Self::Reaction(message) => message.collect_violations(path, violations),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! Receiving webhook notifications
+//!
+//! [`WebhookHandler`] answers the verification challenge, checks the
+//! `X-Hub-Signature-256` of notifications and walks their messages and
+//! statuses. [`WebhookRouter`] builds on it to dispatch each event to async
+//! handlers: [`WebhookListener`]s that see the whole event, such as the
+//! template cache, the conversation window, the outbox or a dialog, and
+//! handlers for messages of a given type and for statuses. A router can also
+//! download inbound media first, so every handler sees where it was stored.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::conversation_window::ConversationWindow;
+use crate::dialog::Dialog;
+use crate::error::{WhatsAppError, WhatsAppResult};
+use crate::media_download::{MediaDownloadFailure, MediaDownloader};
+use crate::outbox::{Outbox, OutboxWorker};
+use crate::template_cache::TemplateCache;
+
+pub use crate::types::webhook::{WebhookChangeValue, WebhookEvent, WebhookMessage, WebhookMessageType, WebhookStatus};
+
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+
+#[derive(Debug, Clone, Default)]
+pub struct WebhookConfig {
+
+    /// Secret of the Meta app, used to check notification signatures.
+    pub app_secret: Option<String>,
+
+    /// Token entered when subscribing the webhook in the app dashboard.
+    pub verify_token: Option<String>,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct WebhookHandler {
+    config: WebhookConfig,
+}
+
+impl WebhookHandler {
+
+    pub fn new(config: WebhookConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &WebhookConfig {
+        &self.config
+    }
+
+    /// Answers the subscription challenge: returns the challenge to echo when
+    /// `mode` is `subscribe` and `token` matches the verify token.
+    pub fn verify_webhook(&self, mode: &str, token: &str, challenge: &str) -> Option<String> {
+        match &self.config.verify_token {
+            Some(verify_token) if mode == "subscribe" && token == verify_token => Some(challenge.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Checks an `X-Hub-Signature-256` header against the raw body.
+    ///
+    /// Always fails without an app secret, so unsigned notifications are
+    /// never taken for genuine ones.
+    pub fn validate_signature(&self, signature: &str, body: &[u8]) -> bool {
+        let app_secret = match &self.config.app_secret {
+            Some(app_secret) => app_secret,
+            None => return false,
+        };
+        let expected = match signature.strip_prefix("sha256=").and_then(|signature| hex::decode(signature).ok()) {
+            Some(expected) => expected,
+            None => return false,
+        };
+
+        match <Hmac<Sha256> as Mac>::new_from_slice(app_secret.as_bytes()) {
+            Ok(mut mac) => {
+                mac.update(body);
+                mac.verify_slice(&expected).is_ok()
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Checks the signature of a notification and parses it.
+    pub fn parse(&self, body: &[u8], signature: Option<&str>) -> WhatsAppResult<WebhookEvent> {
+        if !self.validate_signature(signature.unwrap_or_default(), body) {
+            return Err(WhatsAppError::AuthenticationError("Invalid webhook signature".to_string()));
+        }
+        Ok(serde_json::from_slice(body)?)
+    }
+
+    /// Calls `callback` with every message, then every status, of the event.
+    pub fn handle_webhook<F>(&self, event: WebhookEvent, mut callback: F)
+    where
+        F: FnMut(Option<WebhookMessage>, Option<WebhookStatus>),
+    {
+        for entry in event.entry {
+            for change in entry.changes {
+                if let WebhookChangeValue::Messages(value) = change.value {
+                    for message in value.messages {
+                        callback(Some(message), None);
+                    }
+                    for status in value.statuses {
+                        callback(None, Some(status));
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+pub fn create_webhook_handler(app_secret: Option<String>, verify_token: Option<String>) -> WebhookHandler {
+    WebhookHandler::new(WebhookConfig {
+        app_secret,
+        verify_token,
+    })
+}
+
+
+/// Receives every event a `WebhookRouter` dispatches.
+pub trait WebhookListener: Send + Sync {
+
+    fn handle_event<'a>(&'a self, event: &'a WebhookEvent) -> BoxFuture<'a, WhatsAppResult<()>>;
+}
+
+impl WebhookListener for TemplateCache {
+    fn handle_event<'a>(&'a self, event: &'a WebhookEvent) -> BoxFuture<'a, WhatsAppResult<()>> {
+        Box::pin(async move {
+            self.handle_webhook_event(event);
+            Ok(())
+        })
+    }
+}
+
+impl WebhookListener for ConversationWindow {
+    fn handle_event<'a>(&'a self, event: &'a WebhookEvent) -> BoxFuture<'a, WhatsAppResult<()>> {
+        Box::pin(async move { self.handle_webhook_event(event) })
+    }
+}
+
+impl<O: Outbox> WebhookListener for OutboxWorker<O> {
+    fn handle_event<'a>(&'a self, event: &'a WebhookEvent) -> BoxFuture<'a, WhatsAppResult<()>> {
+        Box::pin(async move { self.handle_webhook_event(event) })
+    }
+}
+
+/// Runs the dialog; its `DialogEvent`s are dropped and only the first failure
+/// is returned, so call `Dialog::handle_webhook_event` directly to act on them.
+impl<S> WebhookListener for Dialog<S>
+where
+    S: Clone + Eq + std::hash::Hash + Send + Sync + 'static,
+{
+    fn handle_event<'a>(&'a self, event: &'a WebhookEvent) -> BoxFuture<'a, WhatsAppResult<()>> {
+        Box::pin(async move {
+            match self.handle_webhook_event(event).await.failures.into_iter().next() {
+                Some(failure) => Err(failure.error),
+                None => Ok(()),
+            }
+        })
+    }
+}
+
+
+type MessageRoute = Box<dyn Fn(WebhookMessage) -> BoxFuture<'static, WhatsAppResult<()>> + Send + Sync>;
+
+type StatusRoute = Box<dyn Fn(WebhookStatus) -> BoxFuture<'static, WhatsAppResult<()>> + Send + Sync>;
+
+
+/// What went wrong while dispatching an event.
+#[derive(Debug, Default)]
+pub struct DispatchReport {
+
+    /// Errors of listeners and handlers; a failing one does not stop the others.
+    pub errors: Vec<WhatsAppError>,
+
+    /// Media that could not be downloaded; its messages are dispatched without `stored`.
+    pub media_failures: Vec<MediaDownloadFailure>,
+}
+
+impl DispatchReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty() && self.media_failures.is_empty()
+    }
+}
+
+
+/// Dispatches webhook events to listeners and handlers, in the order they
+/// were added: listeners first, then message handlers, then status handlers.
+pub struct WebhookRouter {
+    handler: WebhookHandler,
+    media: Option<MediaDownloader>,
+    listeners: Vec<Arc<dyn WebhookListener>>,
+    message_routes: Vec<(Option<WebhookMessageType>, MessageRoute)>,
+    status_routes: Vec<StatusRoute>,
+}
+
+impl WebhookRouter {
+
+    pub fn new(handler: WebhookHandler) -> Self {
+        Self {
+            handler,
+            media: None,
+            listeners: Vec::new(),
+            message_routes: Vec::new(),
+            status_routes: Vec::new(),
+        }
+    }
+
+    pub fn handler(&self) -> &WebhookHandler {
+        &self.handler
+    }
+
+    /// Downloads the media of inbound messages before dispatching an event,
+    /// attaching where each was stored to its `WebhookMedia::stored`.
+    pub fn download_media(mut self, downloader: MediaDownloader) -> Self {
+        self.media = Some(downloader);
+        self
+    }
+
+    pub fn listener(mut self, listener: Arc<dyn WebhookListener>) -> Self {
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Calls `handler` with every inbound message.
+    pub fn on_message<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(WebhookMessage) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = WhatsAppResult<()>> + Send + 'static,
+    {
+        self.message_routes.push((None, Box::new(move |message| Box::pin(handler(message)))));
+        self
+    }
+
+    /// Calls `handler` with every inbound message of type `kind`.
+    pub fn on_message_type<F, Fut>(mut self, kind: WebhookMessageType, handler: F) -> Self
+    where
+        F: Fn(WebhookMessage) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = WhatsAppResult<()>> + Send + 'static,
+    {
+        self.message_routes.push((Some(kind), Box::new(move |message| Box::pin(handler(message)))));
+        self
+    }
+
+    /// Calls `handler` with every status of an outbound message.
+    pub fn on_status<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(WebhookStatus) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = WhatsAppResult<()>> + Send + 'static,
+    {
+        self.status_routes.push(Box::new(move |status| Box::pin(handler(status))));
+        self
+    }
+
+    /// Checks the signature of a notification, parses it and dispatches it.
+    pub async fn handle(&self, body: &[u8], signature: Option<&str>) -> WhatsAppResult<DispatchReport> {
+        let event = self.handler.parse(body, signature)?;
+        Ok(self.dispatch(event).await)
+    }
+
+    pub async fn dispatch(&self, mut event: WebhookEvent) -> DispatchReport {
+        let mut report = DispatchReport::default();
+
+        if let Some(media) = &self.media {
+            report.media_failures = media.handle_webhook_event(&mut event).await;
+        }
+
+        for listener in &self.listeners {
+            if let Err(error) = listener.handle_event(&event).await {
+                report.errors.push(error);
+            }
+        }
+
+        for change in event.changes() {
+            if let WebhookChangeValue::Messages(value) = &change.value {
+                for message in &value.messages {
+                    for (kind, route) in &self.message_routes {
+                        if kind.as_ref().is_some_and(|kind| *kind != message.r#type) {
+                            continue;
+                        }
+                        if let Err(error) = route(message.clone()).await {
+                            report.errors.push(error);
+                        }
+                    }
+                }
+                for status in &value.statuses {
+                    for route in &self.status_routes {
+                        if let Err(error) = route(status.clone()).await {
+                            report.errors.push(error);
+                        }
+                    }
+                }
+            }
+        }
+
+        report
+    }
+}
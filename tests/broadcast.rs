@@ -0,0 +1,197 @@
+//! Broadcasting a template to many recipients
+
+mod common;
+
+use serde_json::json;
+use whatsapp_cloud_sdk::broadcast::{BroadcastOutcome, BroadcastRecipient, BroadcastState, SkipReason};
+use whatsapp_cloud_sdk::Broadcast;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{api_error, bodies, client, sent, PHONE_NUMBER_ID};
+
+fn messages_path() -> String {
+    format!("/{}/messages", PHONE_NUMBER_ID)
+}
+
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sent("wamid.OUT")))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn broadcast(server: &MockServer) -> Broadcast {
+    Broadcast::new(client(server), "spring_sale", "en_US")
+}
+
+fn recipients(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("1555000{:04}", i)).collect()
+}
+
+fn skip_reason(outcome: &BroadcastOutcome) -> Option<SkipReason> {
+    match outcome {
+        BroadcastOutcome::Skipped(reason) => Some(*reason),
+        _ => None,
+    }
+}
+
+
+#[tokio::test]
+async fn results_follow_input_order() {
+    let server = server().await;
+    let recipients = recipients(20);
+
+    let report = broadcast(&server).concurrency(4).start(recipients.clone()).join().await.unwrap();
+
+    assert_eq!(report.progress.state, BroadcastState::Finished);
+    assert_eq!(report.progress.total, Some(20));
+    assert_eq!(report.progress.sent, 20);
+    assert_eq!(report.results.len(), 20);
+    for (i, result) in report.results.iter().enumerate() {
+        assert_eq!(result.index, i);
+        assert_eq!(result.to, recipients[i]);
+        assert!(matches!(&result.outcome, BroadcastOutcome::Sent { message_id } if message_id == "wamid.OUT"));
+    }
+
+    let sent = bodies(&server, &messages_path()).await;
+    assert_eq!(sent.len(), 20);
+    assert!(sent.iter().all(|body| body["template"]["name"] == "spring_sale"));
+}
+
+#[tokio::test]
+async fn duplicate_recipients_are_skipped() {
+    let server = server().await;
+
+    let report = broadcast(&server)
+        .start(["15551234567", "+1 555 123 4567", "15557654321"])
+        .join()
+        .await
+        .unwrap();
+
+    let reasons: Vec<_> = report.results.iter().map(|result| skip_reason(&result.outcome)).collect();
+    assert_eq!(reasons, [None, Some(SkipReason::Duplicate), None]);
+    assert_eq!(report.progress.skipped, 1);
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 2);
+}
+
+#[tokio::test]
+async fn recipients_past_the_messaging_limit_are_skipped() {
+    let server = server().await;
+
+    let report = broadcast(&server).messaging_limit(2).start(recipients(4)).join().await.unwrap();
+
+    let reasons: Vec<_> = report.results.iter().map(|result| skip_reason(&result.outcome)).collect();
+    assert_eq!(
+        reasons,
+        [None, None, Some(SkipReason::MessagingLimitReached), Some(SkipReason::MessagingLimitReached)]
+    );
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 2);
+}
+
+#[tokio::test]
+async fn phone_number_tier_counts_conversations_already_opened() {
+    let server = server().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{}", PHONE_NUMBER_ID)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": PHONE_NUMBER_ID,
+            "messaging_limit_tier": "TIER_50",
+            "throughput": { "level": "STANDARD" },
+        })))
+        .mount(&server)
+        .await;
+
+    let report = broadcast(&server)
+        .with_phone_number_limits(47)
+        .await
+        .unwrap()
+        .start(recipients(5))
+        .join()
+        .await
+        .unwrap();
+    assert_eq!(report.progress.sent, 3);
+    assert_eq!(report.progress.skipped, 2);
+
+    let report = broadcast(&server)
+        .messaging_limit(1)
+        .with_phone_number_limits(0)
+        .await
+        .unwrap()
+        .start(recipients(3))
+        .join()
+        .await
+        .unwrap();
+    assert_eq!(report.progress.sent, 1);
+}
+
+#[tokio::test]
+async fn paused_broadcast_sends_nothing_until_resumed() {
+    let server = server().await;
+
+    let handle = broadcast(&server).start(recipients(3));
+    handle.pause();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    assert_eq!(handle.progress().state, BroadcastState::Paused);
+    assert!(bodies(&server, &messages_path()).await.is_empty());
+
+    handle.resume();
+    let report = handle.join().await.unwrap();
+    assert_eq!(report.progress.sent, 3);
+}
+
+#[tokio::test]
+async fn cancelled_broadcast_stops_taking_recipients() {
+    let server = server().await;
+    let (sender, receiver) = tokio::sync::mpsc::channel(8);
+
+    let handle = broadcast(&server).start_channel(receiver);
+    let mut progress = handle.subscribe();
+    sender.send(BroadcastRecipient::new("15551234567")).await.unwrap();
+    progress.wait_for(|progress| progress.sent == 1).await.unwrap();
+
+    handle.cancel();
+    let _ = sender.send(BroadcastRecipient::new("15557654321")).await;
+    let report = handle.join().await.unwrap();
+
+    assert_eq!(report.progress.state, BroadcastState::Cancelled);
+    assert_eq!(report.results.len(), 1);
+    assert_eq!(report.results[0].to, "15551234567");
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 1);
+}
+
+#[tokio::test]
+async fn sends_that_may_have_gone_through_are_not_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html>gateway</html>"))
+        .mount(&server)
+        .await;
+
+    let report = broadcast(&server).start(["15551234567"]).join().await.unwrap();
+
+    assert!(matches!(report.results[0].outcome, BroadcastOutcome::Unconfirmed(_)));
+    assert_eq!(report.progress.unconfirmed, 1);
+    assert_eq!(report.progress.completed(), 1);
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 1);
+}
+
+#[tokio::test]
+async fn generic_api_errors_are_not_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(400).set_body_json(api_error(131000, "Something went wrong")))
+        .mount(&server)
+        .await;
+
+    let report = broadcast(&server).start(["15551234567"]).join().await.unwrap();
+
+    assert!(matches!(report.results[0].outcome, BroadcastOutcome::Failed(_)));
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 1);
+}
@@ -0,0 +1,255 @@
+//! Business Manager and WhatsApp Business Account administration endpoints
+
+mod common;
+
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use whatsapp_cloud_sdk::error::WhatsAppError;
+use whatsapp_cloud_sdk::types::*;
+use wiremock::matchers::{body_string_contains, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{business_client, BUSINESS_ID};
+
+fn success() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "success": true }))
+}
+
+#[tokio::test]
+async fn gets_business_info() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{}", BUSINESS_ID)))
+        .and(header("authorization", "Bearer token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": BUSINESS_ID,
+            "name": "Pasta Place",
+            "verification_status": "verified",
+        })))
+        .mount(&server)
+        .await;
+
+    let info = business_client(&server).get_business_info().await.unwrap();
+    assert_eq!(info.name, "Pasta Place");
+    assert_eq!(info.verification_status.as_deref(), Some("verified"));
+}
+
+#[tokio::test]
+async fn creates_client_business() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("/{}/owned_businesses", BUSINESS_ID)))
+        .and(body_string_contains("name=Client+Co"))
+        .and(body_string_contains("timezone_id=1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "CLIENT_ID" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let response = business_client(&server)
+        .create_business(CreateBusiness {
+            name: "Client Co".to_string(),
+            vertical: "RETAIL".to_string(),
+            timezone_id: Some(1),
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.id, "CLIENT_ID");
+}
+
+#[tokio::test]
+async fn lists_owned_and_client_accounts() {
+    let server = MockServer::start().await;
+    let accounts = json!({ "data": [{ "id": "WABA_1", "currency": "USD" }], "paging": { "next": "https://next" } });
+    Mock::given(method("GET"))
+        .and(path(format!("/{}/owned_whatsapp_business_accounts", BUSINESS_ID)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&accounts))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{}/client_whatsapp_business_accounts", BUSINESS_ID)))
+        .and(query_param("limit", "10"))
+        .and(query_param("after", "cursor"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&accounts))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = business_client(&server);
+    let owned = client.get_owned_whatsapp_business_accounts().await.unwrap();
+    assert_eq!(owned.data[0].currency.as_deref(), Some("USD"));
+
+    let params = WhatsAppBusinessAccountParams {
+        limit: Some(10),
+        after: Some("cursor".to_string()),
+    };
+    client
+        .get_whatsapp_business_accounts(GetBusinessAssets::Client, params)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn creates_system_users() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("/{}/system_users", BUSINESS_ID)))
+        .and(body_string_contains("role=ADMIN"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "SYSTEM_USER" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let response = business_client(&server)
+        .create_system_user(CreateSystemUser {
+            name: "bot".to_string(),
+            role: SystemUserRole::Admin,
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.id, "SYSTEM_USER");
+}
+
+#[tokio::test]
+async fn system_user_tokens_carry_appsecret_proof() {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(b"app-secret").unwrap();
+    mac.update(b"token");
+    let proof = hex::encode(mac.finalize().into_bytes());
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/SYSTEM_USER/access_tokens"))
+        .and(body_string_contains(format!("appsecret_proof={}", proof)))
+        .and(body_string_contains("scope=whatsapp_business_messaging%2Cwhatsapp_business_management"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "access_token": "system-token" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = GenerateSystemUserToken {
+        system_user_id: "SYSTEM_USER".to_string(),
+        app_id: "APP_ID".to_string(),
+        scopes: vec!["whatsapp_business_messaging".to_string(), "whatsapp_business_management".to_string()],
+        expires_in_60_days: false,
+    };
+    let token = business_client(&server).generate_system_user_token(params.clone()).await.unwrap();
+    assert_eq!(token.access_token, "system-token");
+
+    let no_scopes = GenerateSystemUserToken { scopes: Vec::new(), ..params.clone() };
+    assert!(matches!(
+        business_client(&server).generate_system_user_token(no_scopes).await,
+        Err(WhatsAppError::ValidationError(_))
+    ));
+
+    let without_secret = whatsapp_cloud_sdk::create_business_client("token", BUSINESS_ID, None).with_base_url(&server.uri());
+    assert!(matches!(
+        without_secret.generate_system_user_token(params).await,
+        Err(WhatsAppError::MissingField(_))
+    ));
+}
+
+#[tokio::test]
+async fn assigns_and_removes_users() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/WABA_1/assigned_users"))
+        .and(body_string_contains("tasks=%5B%22MANAGE%22%5D"))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/WABA_1/assigned_users"))
+        .and(query_param("user", "SYSTEM_USER"))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = business_client(&server);
+    let params = AssignUserToWhatsAppBusiness {
+        user: "SYSTEM_USER".to_string(),
+        tasks: vec![WhatsAppBusinessTask::Manage],
+    };
+    client.assign_user_to_whatsapp_business_account("WABA_1", params).await.unwrap();
+    client.remove_user_from_whatsapp_business_account("WABA_1", "SYSTEM_USER").await.unwrap();
+
+    let no_tasks = AssignUserToWhatsAppBusiness {
+        user: "SYSTEM_USER".to_string(),
+        tasks: Vec::new(),
+    };
+    assert!(client.assign_user_to_whatsapp_business_account("WABA_1", no_tasks).await.is_err());
+}
+
+#[tokio::test]
+async fn lists_phone_numbers_of_an_account() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/WABA_1/phone_numbers"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{ "id": "PHONE_1", "messaging_limit_tier": "TIER_10K" }],
+        })))
+        .mount(&server)
+        .await;
+
+    let numbers = business_client(&server).get_phone_numbers("WABA_1").await.unwrap();
+    assert_eq!(numbers.data[0].messaging_limit_tier.as_deref(), Some("TIER_10K"));
+}
+
+#[tokio::test]
+async fn subscribes_and_unsubscribes_with_a_business_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/WABA_1/subscribed_apps"))
+        .and(header("authorization", "Bearer business-token"))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/WABA_1/subscribed_apps"))
+        .and(header("authorization", "Bearer business-token"))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/WABA_1/subscribed_apps"))
+        .and(header("authorization", "Bearer token"))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = business_client(&server);
+    client
+        .subscribe_app_to_waba("WABA_1", SubscribeApp::default(), Some("business-token"))
+        .await
+        .unwrap();
+    client.unsubscribe_app_from_waba("WABA_1", Some("business-token")).await.unwrap();
+    client.unsubscribe_app_from_waba("WABA_1", None).await.unwrap();
+}
+
+#[tokio::test]
+async fn callback_overrides_need_https_and_a_verify_token() {
+    let server = MockServer::start().await;
+    let client = business_client(&server);
+
+    let insecure = SubscribeApp {
+        override_callback_uri: Some("http://example.com/webhook".to_string()),
+        verify_token: Some("verify".to_string()),
+    };
+    let without_token = SubscribeApp {
+        override_callback_uri: Some("https://example.com/webhook".to_string()),
+        verify_token: None,
+    };
+    for params in [insecure, without_token] {
+        assert!(matches!(
+            client.subscribe_app_to_waba("WABA_1", params, None).await,
+            Err(WhatsAppError::ValidationError(_))
+        ));
+    }
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
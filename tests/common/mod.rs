@@ -0,0 +1,111 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+use whatsapp_cloud_sdk::types::webhook::{WebhookEvent, WebhookMessage};
+use whatsapp_cloud_sdk::{BusinessClient, BusinessClientConfig, ClientConfig, WhatsAppClient};
+use wiremock::MockServer;
+
+pub const PHONE_NUMBER_ID: &str = "1234567890";
+
+pub const WABA_ID: &str = "WABA_ID";
+
+/// A client sending to `server` instead of the Graph API.
+pub fn client(server: &MockServer) -> WhatsAppClient {
+    WhatsAppClient::new(ClientConfig {
+        access_token: "token".to_string(),
+        phone_number_id: PHONE_NUMBER_ID.to_string(),
+        business_account_id: Some(WABA_ID.to_string()),
+        retry_delay_ms: 10,
+        ..Default::default()
+    })
+    .with_base_url(&server.uri())
+}
+
+pub const BUSINESS_ID: &str = "BUSINESS_ID";
+
+/// A business client sending to `server`, with app credentials and a registration PIN.
+pub fn business_client(server: &MockServer) -> BusinessClient {
+    BusinessClient::new(BusinessClientConfig {
+        access_token: "token".to_string(),
+        business_id: BUSINESS_ID.to_string(),
+        app_id: Some("APP_ID".to_string()),
+        app_secret: Some("app-secret".to_string()),
+        registration_pin: Some("123456".to_string()),
+        ..Default::default()
+    })
+    .with_base_url(&server.uri())
+}
+
+/// Wraps one change in the webhook envelope.
+pub fn event(field: &str, value: Value) -> WebhookEvent {
+    serde_json::from_value(json!({
+        "object": "whatsapp_business_account",
+        "entry": [{ "id": "WABA_ID", "changes": [{ "field": field, "value": value }] }],
+    }))
+    .unwrap()
+}
+
+/// A `messages` event carrying `messages`.
+pub fn messages_event(messages: Vec<Value>) -> WebhookEvent {
+    event(
+        "messages",
+        json!({
+            "messaging_product": "whatsapp",
+            "metadata": { "display_phone_number": "15550000000", "phone_number_id": PHONE_NUMBER_ID },
+            "messages": messages,
+        }),
+    )
+}
+
+/// A `messages` event carrying `statuses`.
+pub fn statuses_event(statuses: Vec<Value>) -> WebhookEvent {
+    event(
+        "messages",
+        json!({
+            "messaging_product": "whatsapp",
+            "metadata": { "display_phone_number": "15550000000", "phone_number_id": PHONE_NUMBER_ID },
+            "statuses": statuses,
+        }),
+    )
+}
+
+pub fn text_message(from: &str, id: &str, body: &str) -> Value {
+    json!({ "from": from, "id": id, "timestamp": now().to_string(), "type": "text", "text": { "body": body } })
+}
+
+pub fn message(value: Value) -> WebhookMessage {
+    serde_json::from_value(value).unwrap()
+}
+
+pub fn sent(id: &str) -> Value {
+    json!({
+        "messaging_product": "whatsapp",
+        "contacts": [{ "input": "15551234567", "wa_id": "15551234567" }],
+        "messages": [{ "id": id }],
+    })
+}
+
+pub fn api_error(code: i32, message: &str) -> Value {
+    json!({ "error": { "message": message, "type": "OAuthException", "code": code, "fbtrace_id": "trace" } })
+}
+
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// JSON bodies of the requests `server` received for `path`.
+pub async fn bodies(server: &MockServer, path: &str) -> Vec<Value> {
+    server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|request| request.url.path() == path)
+        .filter_map(|request| serde_json::from_slice(&request.body).ok())
+        .collect()
+}
@@ -0,0 +1,237 @@
+//! Customer service window tracking and how the client enforces it
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use serde_json::json;
+use whatsapp_cloud_sdk::conversation_window::{FallbackTemplate, WindowStore};
+use whatsapp_cloud_sdk::error::{WhatsAppError, WhatsAppResult};
+use whatsapp_cloud_sdk::types::messages::{SendReactionMessage, SendTextMessage};
+use whatsapp_cloud_sdk::{ConversationWindow, PhoneNumber};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{bodies, client, message, messages_event, now, sent, text_message, PHONE_NUMBER_ID};
+
+const USER: &str = "15551234567";
+
+fn messages_path() -> String {
+    format!("/{}/messages", PHONE_NUMBER_ID)
+}
+
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sent("wamid.OUT")))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn text(to: &str) -> SendTextMessage {
+    SendTextMessage {
+        to: to.to_string(),
+        text: "Hello".to_string(),
+        preview_url: None,
+        context: None,
+    }
+}
+
+fn inbound_at(window: &ConversationWindow, from: &str, timestamp: u64) {
+    window
+        .record_message(&message(json!({
+            "from": from,
+            "id": "wamid.IN",
+            "timestamp": timestamp.to_string(),
+            "type": "text",
+            "text": { "body": "Hi" },
+        })))
+        .unwrap();
+}
+
+fn fallback() -> FallbackTemplate {
+    FallbackTemplate {
+        template_name: "reopen_conversation".to_string(),
+        language_code: "en_US".to_string(),
+        components: None,
+    }
+}
+
+
+struct FailingStore;
+
+impl WindowStore for FailingStore {
+    fn last_inbound(&self, _wa_id: &str) -> WhatsAppResult<Option<u64>> {
+        Err(WhatsAppError::StorageError("unavailable".to_string()))
+    }
+
+    fn record_inbound(&self, _wa_id: &str, _timestamp: u64) -> WhatsAppResult<()> {
+        Err(WhatsAppError::StorageError("unavailable".to_string()))
+    }
+}
+
+
+/// A window that is open for the first check only, as one expiring mid-send.
+#[derive(Default)]
+struct ClosingStore {
+    checks: AtomicUsize,
+}
+
+impl WindowStore for ClosingStore {
+    fn last_inbound(&self, _wa_id: &str) -> WhatsAppResult<Option<u64>> {
+        match self.checks.fetch_add(1, Ordering::SeqCst) {
+            0 => Ok(Some(now())),
+            _ => Ok(None),
+        }
+    }
+
+    fn record_inbound(&self, _wa_id: &str, _timestamp: u64) -> WhatsAppResult<()> {
+        Ok(())
+    }
+}
+
+
+#[test]
+fn webhook_events_open_the_window() {
+    let window = ConversationWindow::default().treat_unknown_as_closed(true);
+    assert!(!window.is_open(USER).unwrap());
+
+    window
+        .handle_webhook_event(&messages_event(vec![text_message(USER, "wamid.IN", "Hi")]))
+        .unwrap();
+
+    assert!(window.is_open(USER).unwrap());
+    assert!(window.expires_at(USER).unwrap().unwrap() > now());
+}
+
+#[test]
+fn users_are_keyed_by_wa_id() {
+    let window = ConversationWindow::default().treat_unknown_as_closed(true);
+    inbound_at(&window, USER, now());
+
+    assert!(window.is_open("+1 555 123 4567").unwrap());
+    assert!(window.last_inbound("+1 (555) 123-4567").unwrap().is_some());
+}
+
+#[test]
+fn older_timestamps_do_not_move_the_window_back() {
+    let window = ConversationWindow::default();
+    let latest = now();
+    inbound_at(&window, USER, latest);
+    inbound_at(&window, USER, latest - 3600);
+
+    assert_eq!(window.last_inbound(USER).unwrap(), Some(latest));
+}
+
+#[test]
+fn unknown_users_are_open_unless_treated_as_closed() {
+    assert!(ConversationWindow::default().is_open(USER).unwrap());
+    assert!(ConversationWindow::default().check(USER).is_ok());
+
+    let error = ConversationWindow::default()
+        .treat_unknown_as_closed(true)
+        .check(USER)
+        .unwrap_err();
+    assert!(matches!(error, WhatsAppError::ConversationWindowClosed { last_inbound: None, .. }));
+}
+
+#[test]
+fn store_errors_are_returned() {
+    let window = ConversationWindow::new(FailingStore);
+
+    assert!(matches!(window.is_open(USER), Err(WhatsAppError::StorageError(_))));
+    assert!(matches!(window.check(USER), Err(WhatsAppError::StorageError(_))));
+    assert!(matches!(
+        window.handle_webhook_event(&messages_event(vec![text_message(USER, "wamid.IN", "Hi")])),
+        Err(WhatsAppError::StorageError(_))
+    ));
+}
+
+#[tokio::test]
+async fn closed_window_rejects_free_form_messages() {
+    let server = server().await;
+    let window = ConversationWindow::default();
+    inbound_at(&window, USER, now() - 25 * 60 * 60);
+    let client = client(&server).with_conversation_window(Arc::new(window));
+
+    let error = client.send_text_message(text(USER)).await.unwrap_err();
+
+    assert!(matches!(error, WhatsAppError::ConversationWindowClosed { last_inbound: Some(_), .. }));
+    assert!(bodies(&server, &messages_path()).await.is_empty());
+}
+
+#[tokio::test]
+async fn open_window_and_unknown_users_send_normally() {
+    let server = server().await;
+    let window = ConversationWindow::default();
+    inbound_at(&window, USER, now());
+    let client = client(&server).with_conversation_window(Arc::new(window));
+
+    let response = client.send_text_message(text(USER)).await.unwrap();
+    assert_eq!(response.fallback_template, None);
+
+    let response = client.send_text_message(text("15557654321")).await.unwrap();
+    assert_eq!(response.fallback_template, None);
+
+    let sent = bodies(&server, &messages_path()).await;
+    assert_eq!(sent.len(), 2);
+    assert!(sent.iter().all(|body| body["type"] == "text"));
+}
+
+#[tokio::test]
+async fn fallback_template_is_reported_in_the_response() {
+    let server = server().await;
+    let window = ConversationWindow::default()
+        .with_fallback_template(fallback())
+        .treat_unknown_as_closed(true);
+    let client = client(&server).with_conversation_window(Arc::new(window));
+
+    let response = client.send_text_message(text(USER)).await.unwrap();
+    assert_eq!(response.fallback_template.as_deref(), Some("reopen_conversation"));
+
+    let responses = client.send_long_text(text(USER)).await.unwrap();
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].fallback_template.as_deref(), Some("reopen_conversation"));
+
+    let sent = bodies(&server, &messages_path()).await;
+    assert_eq!(sent.len(), 2);
+    assert!(sent.iter().all(|body| body["type"] == "template"));
+    assert_eq!(sent[0]["template"]["name"], "reopen_conversation");
+}
+
+#[tokio::test]
+async fn long_text_checks_the_window_once() {
+    let server = server().await;
+    let window = ConversationWindow::new(ClosingStore::default())
+        .with_fallback_template(fallback())
+        .treat_unknown_as_closed(true);
+    let client = client(&server).with_conversation_window(Arc::new(window));
+    let message = SendTextMessage { text: "word ".repeat(2000), ..text(USER) };
+
+    let responses = client.send_long_text(message).await.unwrap();
+
+    assert_eq!(responses.len(), 3);
+    let sent = bodies(&server, &messages_path()).await;
+    assert_eq!(sent.len(), 3);
+    assert!(sent.iter().all(|body| body["type"] == "text"));
+}
+
+#[tokio::test]
+async fn reactions_never_fall_back_to_the_template() {
+    let server = server().await;
+    let window = ConversationWindow::default()
+        .with_fallback_template(fallback())
+        .treat_unknown_as_closed(true);
+    let client = client(&server).with_conversation_window(Arc::new(window));
+
+    let error = client
+        .send_reaction_message(SendReactionMessage::new(PhoneNumber::parse(USER).unwrap(), "wamid.IN", "👍"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, WhatsAppError::ConversationWindowClosed { .. }));
+    assert!(bodies(&server, &messages_path()).await.is_empty());
+}
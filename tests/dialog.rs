@@ -0,0 +1,337 @@
+//! Dialogs driven by inbound webhook messages
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use whatsapp_cloud_sdk::dialog::{
+    DialogAction, DialogEvent, DialogInput, DialogOutcome, InMemorySessionStore, Session, SessionStore, StateConfig,
+    Trigger,
+};
+use whatsapp_cloud_sdk::error::{WhatsAppError, WhatsAppResult};
+use whatsapp_cloud_sdk::types::messages::Interactive;
+use whatsapp_cloud_sdk::{create_webhook_handler, Dialog, WebhookRouter};
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{bodies, client, message, messages_event, sent, text_message, PHONE_NUMBER_ID};
+
+const USER: &str = "15551234567";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Step {
+    Start,
+    Size,
+    Confirm,
+}
+
+fn messages_path() -> String {
+    format!("/{}/messages", PHONE_NUMBER_ID)
+}
+
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sent("wamid.OUT")))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn sizes() -> Interactive {
+    Interactive::buttons()
+        .body("Which size?")
+        .reply_button("small", "Small")
+        .reply_button("large", "Large")
+        .build()
+}
+
+/// A pizza order: "order" asks for a size, a size asks to confirm.
+fn pizza(server: &MockServer) -> Dialog<Step> {
+    Dialog::new(client(server), Step::Start)
+        .state(
+            Step::Start,
+            StateConfig::new()
+                .on(Trigger::contains("order"), Step::Size, vec![DialogAction::text("Let's order.")])
+                .otherwise(vec![DialogAction::text("Say \"order\" to start.")]),
+        )
+        .state(
+            Step::Size,
+            StateConfig::new()
+                .on_enter(vec![DialogAction::interactive(sizes())])
+                .on(Trigger::AnyReply, Step::Confirm, vec![DialogAction::store("size")])
+                .end_on(Trigger::text("cancel"), vec![DialogAction::text("Cancelled.")]),
+        )
+        .state(
+            Step::Confirm,
+            StateConfig::new()
+                .on_enter(vec![DialogAction::text("Send the order form.")])
+                .end_on(Trigger::flow_token("order-form"), vec![DialogAction::store("form"), DialogAction::text("Done!")]),
+        )
+}
+
+fn reply(id: &str, title: &str) -> Value {
+    json!({
+        "from": USER,
+        "id": "wamid.REPLY",
+        "timestamp": "1700000000",
+        "type": "interactive",
+        "interactive": { "type": "button_reply", "button_reply": { "id": id, "title": title } },
+    })
+}
+
+fn flow(response_json: &str) -> Value {
+    json!({
+        "from": USER,
+        "id": "wamid.FLOW",
+        "timestamp": "1700000000",
+        "type": "interactive",
+        "interactive": {
+            "type": "nfm_reply",
+            "nfm_reply": { "name": "flow", "body": "Sent", "response_json": response_json },
+        },
+    })
+}
+
+fn text(body: &str) -> Value {
+    text_message(USER, "wamid.TEXT", body)
+}
+
+/// Runs `messages` through `dialog`, expecting none to fail.
+async fn handle(dialog: &Dialog<Step>, messages: Vec<Value>) -> Vec<DialogEvent<Step>> {
+    let report = dialog.handle_webhook_event(&messages_event(messages)).await;
+    assert!(report.is_ok(), "{:?}", report.failures);
+    report.events
+}
+
+/// A store whose writes fail, as a database that went away would.
+struct FailingStore;
+
+impl SessionStore<Step> for FailingStore {
+    fn load(&self, _wa_id: &str) -> WhatsAppResult<Option<Session<Step>>> {
+        Ok(None)
+    }
+
+    fn save(&self, _wa_id: &str, _session: Session<Step>) -> WhatsAppResult<()> {
+        Err(WhatsAppError::StorageError("store is down".to_string()))
+    }
+
+    fn remove(&self, _wa_id: &str) -> WhatsAppResult<()> {
+        Err(WhatsAppError::StorageError("store is down".to_string()))
+    }
+
+    fn sessions(&self) -> WhatsAppResult<Vec<(String, Session<Step>)>> {
+        Ok(Vec::new())
+    }
+}
+
+
+#[test]
+fn triggers_match_their_input() {
+    let text = DialogInput::Text("  ORDER please ".to_string());
+    let reply = DialogInput::Reply { id: "small".to_string(), title: "Small".to_string() };
+    let flow = DialogInput::Flow { flow_token: Some("order-form".to_string()), response_json: "{}".to_string() };
+
+    assert!(Trigger::contains("order").matches(&text));
+    assert!(!Trigger::text("order").matches(&text));
+    assert!(Trigger::text("order please").matches(&text));
+    assert!(Trigger::reply("small").matches(&reply));
+    assert!(!Trigger::reply("small").matches(&text));
+    assert!(Trigger::flow().matches(&flow));
+    assert!(Trigger::flow_token("order-form").matches(&flow));
+    assert!(!Trigger::flow_token("survey").matches(&flow));
+    assert!(Trigger::Any.matches(&DialogInput::Other));
+}
+
+#[test]
+fn inputs_are_read_from_messages() {
+    assert_eq!(DialogInput::from_message(&message(text("Hi"))), DialogInput::Text("Hi".to_string()));
+    assert_eq!(
+        DialogInput::from_message(&message(reply("small", "Small"))),
+        DialogInput::Reply { id: "small".to_string(), title: "Small".to_string() }
+    );
+    assert_eq!(
+        DialogInput::from_message(&message(flow(r#"{"flow_token":"order-form","toppings":"ham"}"#))),
+        DialogInput::Flow {
+            flow_token: Some("order-form".to_string()),
+            response_json: r#"{"flow_token":"order-form","toppings":"ham"}"#.to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn a_conversation_walks_the_states() {
+    let server = server().await;
+    let dialog = pizza(&server);
+
+    let events = handle(&dialog, vec![text("I want to order")]).await;
+    assert_eq!(events[0].outcome, DialogOutcome::Transitioned { from: Step::Start, to: Some(Step::Size) });
+    assert_eq!(dialog.session("+1 555 123 4567").unwrap().unwrap().state, Step::Size);
+
+    handle(&dialog, vec![reply("large", "Large")]).await;
+    let session = dialog.session(USER).unwrap().unwrap();
+    assert_eq!(session.state, Step::Confirm);
+    assert_eq!(session.data["size"], "large");
+
+    let response_json = r#"{"flow_token":"order-form","address":"1 Main St"}"#;
+    let events = handle(&dialog, vec![flow(response_json)]).await;
+    assert_eq!(events[0].outcome, DialogOutcome::Transitioned { from: Step::Confirm, to: None });
+    assert_eq!(events[0].data["size"], "large");
+    assert_eq!(events[0].data["form"], response_json);
+    assert!(dialog.session(USER).unwrap().is_none());
+
+    let sent = bodies(&server, &messages_path()).await;
+    let kinds: Vec<&str> = sent.iter().map(|body| body["type"].as_str().unwrap()).collect();
+    assert_eq!(kinds, ["text", "interactive", "text", "text"]);
+    assert_eq!(sent[0]["text"]["body"], "Let's order.");
+    assert_eq!(sent[1]["interactive"]["action"]["buttons"][0]["reply"]["id"], "small");
+    assert_eq!(sent[3]["text"]["body"], "Done!");
+    assert!(sent.iter().all(|body| body["to"] == USER));
+}
+
+#[tokio::test]
+async fn unmatched_input_keeps_the_state() {
+    let server = server().await;
+    let dialog = pizza(&server);
+
+    let events = handle(&dialog, vec![text("hello")]).await;
+
+    assert_eq!(events[0].outcome, DialogOutcome::Unmatched { state: Step::Start });
+    assert_eq!(dialog.session(USER).unwrap().unwrap().state, Step::Start);
+    let sent = bodies(&server, &messages_path()).await;
+    assert_eq!(sent[0]["text"]["body"], "Say \"order\" to start.");
+}
+
+#[tokio::test]
+async fn ended_dialogs_start_over() {
+    let server = server().await;
+    let dialog = pizza(&server);
+
+    handle(&dialog, vec![text("order")]).await;
+    handle(&dialog, vec![text("Cancel")]).await;
+    assert!(dialog.session(USER).unwrap().is_none());
+
+    let events = handle(&dialog, vec![text("order")]).await;
+    assert_eq!(events[0].outcome, DialogOutcome::Transitioned { from: Step::Start, to: Some(Step::Size) });
+
+    dialog.reset(USER).unwrap();
+    assert!(dialog.session(USER).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn timed_out_states_take_their_timeout_transition() {
+    let server = server().await;
+    let dialog = Dialog::new(client(&server), Step::Start)
+        .state(Step::Start, StateConfig::new().on(Trigger::AnyText, Step::Size, vec![]))
+        .state(
+            Step::Size,
+            StateConfig::new()
+                .on(Trigger::AnyReply, Step::Confirm, vec![])
+                .timeout(Duration::ZERO, Step::Start, vec![DialogAction::text("Still there?")]),
+        );
+
+    handle(&dialog, vec![text("order")]).await;
+    let events = dialog.expire_sessions().await.unwrap().events;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].outcome, DialogOutcome::TimedOut { from: Step::Size, to: Some(Step::Start) });
+    assert_eq!(events[0].input, None);
+    assert_eq!(dialog.session(USER).unwrap().unwrap().state, Step::Start);
+
+    // A message arriving after the timeout reports it first.
+    handle(&dialog, vec![text("order")]).await;
+    let events = handle(&dialog, vec![reply("small", "Small")]).await;
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0].outcome, DialogOutcome::TimedOut { .. }));
+    assert_eq!(events[1].outcome, DialogOutcome::Unmatched { state: Step::Start });
+}
+
+#[tokio::test]
+async fn sessions_are_saved_before_sending() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(400).set_body_json(common::api_error(131026, "Message undeliverable")))
+        .mount(&server)
+        .await;
+    let dialog = Dialog::new(client(&server), Step::Start)
+        .with_store(InMemorySessionStore::new())
+        .state(
+            Step::Start,
+            StateConfig::new().on(
+                Trigger::AnyText,
+                Step::Size,
+                vec![DialogAction::text("Got it."), DialogAction::store("name")],
+            ),
+        );
+
+    assert!(!dialog.handle_webhook_event(&messages_event(vec![text("Ana")])).await.is_ok());
+
+    let session = dialog.session(USER).unwrap().unwrap();
+    assert_eq!(session.state, Step::Size);
+    assert_eq!(session.data["name"], "Ana");
+}
+
+#[tokio::test]
+async fn store_errors_are_returned_before_sending() {
+    let server = server().await;
+    let dialog = pizza(&server).with_store(FailingStore);
+
+    let mut report = dialog.handle_webhook_event(&messages_event(vec![text("order")])).await;
+
+    assert_eq!(report.failures.len(), 1);
+    assert!(matches!(report.failures.remove(0).error, WhatsAppError::StorageError(_)));
+    assert!(bodies(&server, &messages_path()).await.is_empty());
+}
+
+#[tokio::test]
+async fn one_failing_user_does_not_stop_the_others() {
+    const OTHER: &str = "15557654321";
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .and(body_partial_json(json!({ "to": USER })))
+        .respond_with(ResponseTemplate::new(400).set_body_json(common::api_error(131026, "Message undeliverable")))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sent("wamid.OUT")))
+        .mount(&server)
+        .await;
+    let dialog = Dialog::new(client(&server), Step::Start)
+        .state(Step::Start, StateConfig::new().on(Trigger::AnyText, Step::Size, vec![DialogAction::text("Got it.")]))
+        .state(
+            Step::Size,
+            StateConfig::new().timeout(Duration::ZERO, Step::Start, vec![DialogAction::text("Still there?")]),
+        );
+
+    let report = dialog
+        .handle_webhook_event(&messages_event(vec![text("order"), text_message(OTHER, "wamid.OTHER", "order")]))
+        .await;
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].wa_id, USER);
+    assert_eq!(report.events.len(), 1);
+    assert_eq!(report.events[0].wa_id, OTHER);
+
+    let report = dialog.expire_sessions().await.unwrap();
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].wa_id, USER);
+    assert_eq!(report.events.len(), 1);
+    assert_eq!(report.events[0].wa_id, OTHER);
+}
+
+#[tokio::test]
+async fn routers_run_dialogs_as_listeners() {
+    let server = server().await;
+    let dialog = Arc::new(pizza(&server));
+    let failing = Arc::new(pizza(&server).with_store(FailingStore));
+    let router = WebhookRouter::new(create_webhook_handler(None, None)).listener(dialog.clone()).listener(failing);
+
+    let report = router.dispatch(messages_event(vec![text("order")])).await;
+
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(dialog.session(USER).unwrap().unwrap().state, Step::Size);
+}
@@ -0,0 +1,245 @@
+//! Embedded Signup onboarding and its resumable progress
+
+mod common;
+
+use serde_json::json;
+use whatsapp_cloud_sdk::error::WhatsAppError;
+use whatsapp_cloud_sdk::types::*;
+use whatsapp_cloud_sdk::{BusinessClient, BusinessClientConfig};
+use wiremock::matchers::{header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{api_error, business_client, BUSINESS_ID};
+
+fn success() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "success": true }))
+}
+
+async fn mount_exchange(server: &MockServer, times: u64) {
+    Mock::given(method("GET"))
+        .and(path("/oauth/access_token"))
+        .and(query_param("code", "CODE"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "access_token": "business-token" })))
+        .expect(times)
+        .mount(server)
+        .await;
+}
+
+async fn mount_subscribe(server: &MockServer, times: u64) {
+    Mock::given(method("POST"))
+        .and(path("/WABA_1/subscribed_apps"))
+        .and(header("authorization", "Bearer business-token"))
+        .respond_with(success())
+        .expect(times)
+        .mount(server)
+        .await;
+}
+
+async fn mount_credit_line(server: &MockServer, times: u64) {
+    Mock::given(method("GET"))
+        .and(path(format!("/{}/extendedcredits", BUSINESS_ID)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{ "id": "CREDIT_LINE", "legal_entity_name": "Partner" }],
+        })))
+        .expect(times)
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/WABA_1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "WABA_1", "currency": "USD" })))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/CREDIT_LINE/whatsapp_credit_sharing_and_attach"))
+        .and(query_param("waba_currency", "USD"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "allocation_config_id": "ALLOCATION",
+            "waba_id": "WABA_1",
+        })))
+        .expect(times)
+        .mount(server)
+        .await;
+}
+
+async fn mount_register(server: &MockServer, response: ResponseTemplate) {
+    Mock::given(method("POST"))
+        .and(path("/PHONE_1/register"))
+        .and(header("authorization", "Bearer business-token"))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
+
+async fn mount_platform_type(server: &MockServer, platform_type: &str) {
+    Mock::given(method("GET"))
+        .and(path("/PHONE_1"))
+        .and(header("authorization", "Bearer business-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "PHONE_1", "platform_type": platform_type })))
+        .mount(server)
+        .await;
+}
+
+fn progress() -> EmbeddedSignupProgress {
+    EmbeddedSignupProgress::new("CODE", "WABA_1", "PHONE_1")
+}
+
+#[tokio::test]
+async fn completes_every_step() {
+    let server = MockServer::start().await;
+    mount_exchange(&server, 1).await;
+    mount_subscribe(&server, 1).await;
+    mount_register(&server, success()).await;
+    mount_credit_line(&server, 1).await;
+
+    let progress = business_client(&server)
+        .complete_embedded_signup("CODE", "WABA_1", "PHONE_1")
+        .await
+        .unwrap();
+
+    assert!(progress.is_complete());
+    assert_eq!(progress.business_token.as_deref(), Some("business-token"));
+    assert_eq!(progress.credit_line_allocation_id.as_deref(), Some("ALLOCATION"));
+}
+
+#[tokio::test]
+async fn resumes_from_the_failed_step() {
+    let server = MockServer::start().await;
+    mount_exchange(&server, 1).await;
+    mount_subscribe(&server, 1).await;
+    mount_register(&server, success()).await;
+
+    // No credit line yet: the first run stops after registering.
+    let first = Mock::given(method("GET"))
+        .and(path(format!("/{}/extendedcredits", BUSINESS_ID)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": [] })))
+        .up_to_n_times(1)
+        .mount_as_scoped(&server)
+        .await;
+
+    let client = business_client(&server);
+    let mut progress = progress();
+    assert!(client.continue_embedded_signup(&mut progress).await.is_err());
+    assert!(progress.business_token.is_some());
+    assert!(progress.app_subscribed);
+    assert!(progress.phone_number_registered);
+    assert!(progress.credit_line_allocation_id.is_none());
+    assert!(!progress.is_complete());
+    drop(first);
+
+    mount_credit_line(&server, 1).await;
+    client.continue_embedded_signup(&mut progress).await.unwrap();
+    assert!(progress.is_complete());
+}
+
+#[tokio::test]
+async fn a_credit_line_already_shared_is_not_allocated_again() {
+    let server = MockServer::start().await;
+    // The allocation went through but its response was lost.
+    Mock::given(method("GET"))
+        .and(path("/WABA_1"))
+        .and(query_param("fields", "id,primary_funding_id"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "WABA_1", "primary_funding_id": "FUNDING_1" })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/CREDIT_LINE/owning_credit_allocation_configs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [
+                { "id": "OTHER_ALLOCATION", "receiving_credential": { "id": "FUNDING_2" } },
+                { "id": "ALLOCATION", "receiving_credential": { "id": "FUNDING_1" } },
+            ],
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{}/extendedcredits", BUSINESS_ID)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": [{ "id": "CREDIT_LINE" }] })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/CREDIT_LINE/whatsapp_credit_sharing_and_attach"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let mut progress = EmbeddedSignupProgress {
+        business_token: Some("business-token".to_string()),
+        app_subscribed: true,
+        phone_number_registered: true,
+        ..progress()
+    };
+
+    business_client(&server).continue_embedded_signup(&mut progress).await.unwrap();
+
+    assert_eq!(progress.credit_line_allocation_id.as_deref(), Some("ALLOCATION"));
+}
+
+#[tokio::test]
+async fn a_complete_progress_makes_no_requests() {
+    let server = MockServer::start().await;
+    let mut progress = EmbeddedSignupProgress {
+        business_token: Some("business-token".to_string()),
+        app_subscribed: true,
+        phone_number_registered: true,
+        credit_line_allocation_id: Some("ALLOCATION".to_string()),
+        ..progress()
+    };
+
+    business_client(&server).continue_embedded_signup(&mut progress).await.unwrap();
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn an_already_registered_number_counts_as_registered() {
+    let server = MockServer::start().await;
+    mount_exchange(&server, 1).await;
+    mount_subscribe(&server, 1).await;
+    mount_register(&server, ResponseTemplate::new(400).set_body_json(api_error(100, "Phone number already registered"))).await;
+    mount_platform_type(&server, "CLOUD_API").await;
+    mount_credit_line(&server, 1).await;
+
+    let mut progress = progress();
+    business_client(&server).continue_embedded_signup(&mut progress).await.unwrap();
+    assert!(progress.phone_number_registered);
+    assert!(progress.is_complete());
+}
+
+#[tokio::test]
+async fn a_failed_registration_is_reported() {
+    let server = MockServer::start().await;
+    mount_exchange(&server, 1).await;
+    mount_subscribe(&server, 1).await;
+    mount_register(&server, ResponseTemplate::new(400).set_body_json(api_error(100, "Invalid PIN"))).await;
+    mount_platform_type(&server, "ON_PREMISE").await;
+
+    let mut progress = progress();
+    let result = business_client(&server).continue_embedded_signup(&mut progress).await;
+
+    assert!(result.is_err());
+    assert!(progress.app_subscribed);
+    assert!(!progress.phone_number_registered);
+}
+
+#[tokio::test]
+async fn registering_requires_a_pin() {
+    let server = MockServer::start().await;
+    mount_exchange(&server, 1).await;
+    mount_subscribe(&server, 1).await;
+
+    let client = BusinessClient::new(BusinessClientConfig {
+        access_token: "token".to_string(),
+        business_id: BUSINESS_ID.to_string(),
+        app_id: Some("APP_ID".to_string()),
+        app_secret: Some("app-secret".to_string()),
+        ..Default::default()
+    })
+    .with_base_url(&server.uri());
+
+    let mut progress = progress();
+    assert!(matches!(
+        client.continue_embedded_signup(&mut progress).await,
+        Err(WhatsAppError::MissingField(_))
+    ));
+    assert!(progress.app_subscribed);
+    assert!(!progress.phone_number_registered);
+}
@@ -0,0 +1,30 @@
+//! Flow management types
+
+use serde_json::json;
+use whatsapp_cloud_sdk::types::flows::*;
+
+#[test]
+fn unknown_categories_and_statuses_still_parse() {
+    let flow: Flow = serde_json::from_value(json!({
+        "id": "FLOW",
+        "name": "Booking",
+        "status": "ARCHIVED",
+        "categories": ["APPOINTMENT_BOOKING", "OTHER", "SHOPPING"],
+    }))
+    .unwrap();
+
+    assert_eq!(flow.status, FlowStatus::Unknown);
+    assert_eq!(
+        flow.categories,
+        [FlowCategory::AppointmentBooking, FlowCategory::Other, FlowCategory::Other]
+    );
+}
+
+#[test]
+fn categories_serialize_in_api_form() {
+    let categories = [FlowCategory::SignUp, FlowCategory::CustomerSupport, FlowCategory::Other];
+    assert_eq!(
+        serde_json::to_value(categories).unwrap(),
+        json!(["SIGN_UP", "CUSTOMER_SUPPORT", "OTHER"])
+    );
+}
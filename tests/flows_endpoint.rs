@@ -0,0 +1,141 @@
+//! Exercises the Flows data-exchange endpoint with a locally generated key pair.
+
+use hmac::{Hmac, Mac};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use whatsapp_cloud_sdk::error::{WhatsAppError, WhatsAppResult};
+use whatsapp_cloud_sdk::flows::endpoint::*;
+
+struct Booking;
+
+impl FlowHandler for Booking {
+    async fn init(&self, request: FlowDataRequest) -> WhatsAppResult<FlowScreenResponse> {
+        if request.flow_token != "booking-42" {
+            return Err(WhatsAppError::AuthenticationError("Unknown flow token".to_string()));
+        }
+        Ok(FlowScreenResponse::screen("APPOINTMENT", json!({ "slots": ["09:00", "10:00"] })))
+    }
+
+    async fn data_exchange(&self, request: FlowDataRequest) -> WhatsAppResult<FlowScreenResponse> {
+        if request.screen.as_deref() == Some("BROKEN") {
+            return Err(WhatsAppError::StorageError("connection to db.internal:5432 refused".to_string()));
+        }
+        Ok(FlowScreenResponse::close(request.flow_token, json!({ "slot": request.data["slot"] })))
+    }
+}
+
+fn key_pair() -> (RsaPrivateKey, RsaPublicKey) {
+    let private_key = RsaPrivateKey::new(&mut aes_gcm::aead::OsRng, 2048).unwrap();
+    let public_key = RsaPublicKey::from(&private_key);
+    (private_key, public_key)
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+async fn exchange(endpoint: &FlowEndpoint<Booking>, public_key: &RsaPublicKey, payload: Value) -> (u16, Option<Value>) {
+    let (request, session) = EncryptedFlowRequest::encrypt(public_key, &payload).unwrap();
+    let body = serde_json::to_vec(&request).unwrap();
+    let reply = endpoint.handle(&body, Some(&sign("secret", &body))).await;
+
+    let response = if reply.status == 200 {
+        Some(session.decrypt_response(&reply.body).unwrap())
+    } else {
+        None
+    };
+    (reply.status, response)
+}
+
+#[tokio::test]
+async fn round_trip() {
+    let (private_key, public_key) = key_pair();
+    let endpoint = FlowEndpoint::new(private_key, "secret", Booking);
+
+    let (status, response) = exchange(&endpoint, &public_key, json!({ "version": "3.0", "action": "ping" })).await;
+    assert_eq!(status, 200);
+    assert_eq!(response.unwrap(), json!({ "data": { "status": "active" } }));
+
+    let init = json!({ "version": "3.0", "action": "INIT", "flow_token": "booking-42" });
+    let (_, response) = exchange(&endpoint, &public_key, init).await;
+    assert_eq!(response.unwrap()["screen"], "APPOINTMENT");
+
+    let submit = json!({
+        "version": "3.0",
+        "action": "data_exchange",
+        "screen": "APPOINTMENT",
+        "flow_token": "booking-42",
+        "data": { "slot": "10:00" },
+    });
+    let (_, response) = exchange(&endpoint, &public_key, submit).await;
+    assert_eq!(
+        response.unwrap(),
+        json!({
+            "screen": "SUCCESS",
+            "data": { "extension_message_response": { "params": { "flow_token": "booking-42", "slot": "10:00" } } },
+        })
+    );
+
+    let error = json!({
+        "version": "3.0",
+        "action": "data_exchange",
+        "screen": "APPOINTMENT",
+        "flow_token": "booking-42",
+        "data": { "error": "invalid-screen-transition", "error_message": "No such screen" },
+    });
+    let (_, response) = exchange(&endpoint, &public_key, error).await;
+    assert_eq!(response.unwrap(), json!({ "data": { "acknowledged": true } }));
+
+    let unknown_token = json!({ "version": "3.0", "action": "INIT", "flow_token": "other" });
+    let (status, _) = exchange(&endpoint, &public_key, unknown_token).await;
+    assert_eq!(status, STATUS_INVALID_FLOW_TOKEN);
+}
+
+#[tokio::test]
+async fn rejects_bad_signature_and_foreign_key() {
+    let (private_key, _) = key_pair();
+    let (_, foreign_public_key) = key_pair();
+    let endpoint = FlowEndpoint::new(private_key, "secret", Booking);
+
+    let (request, _) = EncryptedFlowRequest::encrypt(&foreign_public_key, &json!({ "action": "ping" })).unwrap();
+    let body = serde_json::to_vec(&request).unwrap();
+
+    let reply = endpoint.handle(&body, Some("sha256=00")).await;
+    assert_eq!(reply.status, STATUS_INVALID_SIGNATURE);
+
+    let reply = endpoint.handle(&body, Some(&sign("secret", &body))).await;
+    assert_eq!(reply.status, STATUS_DECRYPTION_FAILED);
+}
+
+#[tokio::test]
+async fn requires_a_signature_unless_skipped() {
+    let (private_key, public_key) = key_pair();
+    let (request, session) = EncryptedFlowRequest::encrypt(&public_key, &json!({ "version": "3.0", "action": "ping" })).unwrap();
+    let body = serde_json::to_vec(&request).unwrap();
+
+    let endpoint = FlowEndpoint::new(private_key.clone(), "secret", Booking);
+    assert_eq!(endpoint.handle(&body, None).await.status, STATUS_INVALID_SIGNATURE);
+    assert_eq!(endpoint.handle(&body, Some(&sign("other", &body))).await.status, STATUS_INVALID_SIGNATURE);
+
+    let insecure = FlowEndpoint::new(private_key, "secret", Booking).insecure_skip_signature();
+    let reply = insecure.handle(&body, None).await;
+    assert_eq!(reply.status, 200);
+    assert_eq!(session.decrypt_response(&reply.body).unwrap()["data"]["status"], "active");
+}
+
+#[tokio::test]
+async fn internal_errors_are_not_echoed() {
+    let (private_key, public_key) = key_pair();
+    let endpoint = FlowEndpoint::new(private_key, "secret", Booking);
+
+    let broken = json!({ "version": "3.0", "action": "data_exchange", "screen": "BROKEN", "flow_token": "booking-42" });
+    let (request, _) = EncryptedFlowRequest::encrypt(&public_key, &broken).unwrap();
+    let body = serde_json::to_vec(&request).unwrap();
+    let reply = endpoint.handle(&body, Some(&sign("secret", &body))).await;
+
+    assert_eq!(reply.status, 500);
+    assert!(!reply.body.contains("db.internal"), "{}", reply.body);
+}
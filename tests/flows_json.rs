@@ -0,0 +1,265 @@
+//! Flow JSON model and its offline validation
+
+use serde_json::{json, Value};
+use whatsapp_cloud_sdk::flows::json::*;
+use whatsapp_cloud_sdk::Validate;
+
+fn booking() -> Value {
+    json!({
+        "version": "6.0",
+        "data_api_version": "3.0",
+        "routing_model": { "APPOINTMENT": ["DETAILS"], "DETAILS": [] },
+        "screens": [
+            {
+                "id": "APPOINTMENT",
+                "title": "Book",
+                "data": {
+                    "slots": {
+                        "type": "array",
+                        "items": { "type": "object", "properties": { "id": { "type": "string" }, "title": { "type": "string" } } },
+                        "__example__": [{ "id": "1", "title": "09:00" }]
+                    }
+                },
+                "layout": {
+                    "type": "SingleColumnLayout",
+                    "children": [
+                        { "type": "Dropdown", "name": "slot", "label": "Slot", "data-source": "${data.slots}", "required": true },
+                        {
+                            "type": "Footer",
+                            "label": "Continue",
+                            "on-click-action": {
+                                "name": "navigate",
+                                "next": { "type": "screen", "name": "DETAILS" },
+                                "payload": { "slot": "${form.slot}" }
+                            }
+                        }
+                    ]
+                }
+            },
+            {
+                "id": "DETAILS",
+                "terminal": true,
+                "data": { "slot": { "type": "string", "__example__": "1" } },
+                "layout": {
+                    "type": "SingleColumnLayout",
+                    "children": [
+                        { "type": "TextInput", "name": "name", "label": "Name", "input-type": "text" },
+                        { "type": "If", "condition": "${form.name} != ''", "then": [{ "type": "TextBody", "text": "Thanks" }] },
+                        {
+                            "type": "Footer",
+                            "label": "Book",
+                            "on-click-action": {
+                                "name": "complete",
+                                "payload": { "slot": "${data.slot}", "name": "${form.name}", "first": "${screen.APPOINTMENT.form.slot}" }
+                            }
+                        }
+                    ]
+                }
+            }
+        ]
+    })
+}
+
+fn parse(value: Value) -> FlowJson {
+    serde_json::from_value(value).unwrap()
+}
+
+/// `path: message` of every violation of `value`.
+fn violations(value: Value) -> Vec<String> {
+    parse(value)
+        .violations()
+        .into_iter()
+        .map(|violation| format!("{}: {}", violation.path, violation.message))
+        .collect()
+}
+
+fn assert_violation(value: Value, expected: &str) {
+    let found = violations(value);
+    assert!(found.iter().any(|violation| violation == expected), "{:?} not in {:?}", expected, found);
+}
+
+fn set(value: &mut Value, pointer: &str, new: Value) {
+    *value.pointer_mut(pointer).unwrap_or_else(|| panic!("{}", pointer)) = new;
+}
+
+#[test]
+fn a_valid_flow_round_trips() {
+    let flow = parse(booking());
+    assert!(flow.violations().is_empty(), "{:?}", flow.violations());
+    assert_eq!(serde_json::to_value(&flow).unwrap(), booking());
+}
+
+#[test]
+fn bindings_and_literals_are_told_apart() {
+    let flow = parse(booking());
+    let dropdown = match &flow.screens[0].layout.children[0] {
+        FlowComponent::Dropdown(dropdown) => dropdown,
+        other => panic!("unexpected component {:?}", other),
+    };
+    assert_eq!(dropdown.data_source, Dynamic::Binding("${data.slots}".to_string()));
+    assert_eq!(dropdown.required, Some(Dynamic::Value(true)));
+
+    let literal: Dynamic<Vec<DataSourceItem>> = serde_json::from_value(json!([{ "id": "a", "title": "A" }])).unwrap();
+    assert!(matches!(literal, Dynamic::Value(items) if items[0].id == "a"));
+}
+
+#[test]
+fn unknown_components_fail_to_parse() {
+    let mut value = booking();
+    set(&mut value, "/screens/1/layout/children/0/type", json!("TextImput"));
+    assert!(serde_json::from_value::<FlowJson>(value).is_err());
+}
+
+#[test]
+fn version_and_screens_are_required() {
+    let mut value = booking();
+    set(&mut value, "/version", json!(" "));
+    assert_violation(value, "version: must not be empty");
+
+    let mut value = booking();
+    set(&mut value, "/screens", json!([]));
+    assert_eq!(violations(value), ["screens: must contain at least one screen"]);
+}
+
+#[test]
+fn screen_ids_are_checked() {
+    let mut value = booking();
+    set(&mut value, "/screens/1/id", json!("details"));
+    assert_violation(value, "screens[1].id: must consist of upper case letters, digits and underscores");
+
+    let mut value = booking();
+    set(&mut value, "/screens/1/id", json!("SUCCESS"));
+    assert_violation(value, "screens[1].id: SUCCESS is reserved");
+
+    let mut value = booking();
+    set(&mut value, "/screens/1/id", json!("APPOINTMENT"));
+    assert_violation(value, "screens[1].id: duplicate screen id APPOINTMENT");
+}
+
+#[test]
+fn routing_model_is_checked() {
+    let mut value = booking();
+    value.as_object_mut().unwrap().remove("routing_model");
+    assert_violation(value, "routing_model: is required when data_api_version is set");
+
+    let mut value = booking();
+    set(&mut value, "/routing_model", json!({ "APPOINTMENT": ["DETAILS", "PAYMENT"], "REVIEW": [], "DETAILS": ["DETAILS"] }));
+    let found = violations(value);
+    for expected in [
+        "routing_model.APPOINTMENT: routes to unknown screen PAYMENT",
+        "routing_model.REVIEW: unknown screen REVIEW",
+        "routing_model.DETAILS: must not route a screen to itself",
+    ] {
+        assert!(found.iter().any(|violation| violation == expected), "{:?} not in {:?}", expected, found);
+    }
+}
+
+#[test]
+fn navigation_must_follow_the_routing_model() {
+    let mut value = booking();
+    set(&mut value, "/routing_model/APPOINTMENT", json!([]));
+    assert_violation(
+        value,
+        "screens[0].layout.children[1]: route APPOINTMENT -> DETAILS is missing from routing_model",
+    );
+
+    let mut value = booking();
+    set(&mut value, "/screens/0/layout/children/1/on-click-action/next/name", json!("PAYMENT"));
+    assert_violation(value, "screens[0].layout.children[1]: navigates to unknown screen PAYMENT");
+}
+
+#[test]
+fn every_screen_must_be_reachable() {
+    let mut value = booking();
+    value.as_object_mut().unwrap().remove("routing_model");
+    value.as_object_mut().unwrap().remove("data_api_version");
+    set(&mut value, "/screens/0/layout/children/1/on-click-action", json!({ "name": "complete" }));
+    value["screens"][0]["terminal"] = json!(true);
+    assert_violation(value, "screens[1]: screen DETAILS is unreachable");
+
+    let mut value = booking();
+    set(&mut value, "/routing_model/DETAILS", json!(["APPOINTMENT"]));
+    assert_violation(value, "routing_model: has no entry screen; every screen is routed to from another");
+}
+
+#[test]
+fn terminal_screens_need_one_footer() {
+    let mut value = booking();
+    set(&mut value, "/screens/1/terminal", json!(false));
+    let found = violations(value);
+    assert!(found.contains(&"screens: must contain a terminal screen".to_string()), "{:?}", found);
+    assert!(found.contains(&"screens[1].layout.children[2]: complete is only allowed on terminal screens".to_string()));
+
+    let mut value = booking();
+    value["screens"][1]["layout"]["children"].as_array_mut().unwrap().pop();
+    assert_violation(value, "screens[1].layout: terminal screens require a Footer");
+
+    let mut value = booking();
+    let footer = value["screens"][1]["layout"]["children"][2].clone();
+    value["screens"][1]["layout"]["children"].as_array_mut().unwrap().push(footer);
+    assert_violation(value, "screens[1].layout: must contain at most one Footer");
+}
+
+#[test]
+fn data_fields_need_examples() {
+    let mut value = booking();
+    value["screens"][1]["data"]["slot"].as_object_mut().unwrap().remove("__example__");
+    assert_violation(value, "screens[1].data.slot: requires an __example__ value");
+}
+
+#[test]
+fn inputs_and_data_sources_are_checked() {
+    let mut value = booking();
+    set(&mut value, "/screens/1/layout/children/0/name", json!(""));
+    assert_violation(value, "screens[1].layout: input names must not be empty");
+
+    let mut value = booking();
+    let input = value["screens"][1]["layout"]["children"][0].clone();
+    value["screens"][1]["layout"]["children"].as_array_mut().unwrap().insert(0, input);
+    assert_violation(value, "screens[1].layout: duplicate input name name");
+
+    let mut value = booking();
+    set(&mut value, "/screens/0/layout/children/0/data-source", json!([]));
+    assert_violation(value, "screens[0].layout.children[0].data-source: must not be empty");
+
+    let mut value = booking();
+    set(
+        &mut value,
+        "/screens/0/layout/children/0/data-source",
+        json!([{ "id": "a", "title": "A" }, { "id": "a", "title": "B" }]),
+    );
+    assert_violation(value, "screens[0].layout.children[0].data-source: duplicate id a");
+}
+
+#[test]
+fn data_exchange_needs_a_data_api_version() {
+    let mut value = booking();
+    value.as_object_mut().unwrap().remove("data_api_version");
+    value["screens"][0]["layout"]["children"][0]["on-select-action"] = json!({ "name": "data_exchange" });
+    assert_violation(value, "screens[0].layout.children[0]: data_exchange requires data_api_version");
+}
+
+#[test]
+fn binding_expressions_must_resolve() {
+    let footer = "/screens/1/layout/children/2/on-click-action/payload";
+    let cases = [
+        (json!({ "x": "${data.missing}" }), "data.missing refers to data field missing which screen DETAILS does not declare"),
+        (json!({ "x": "${form.missing}" }), "form.missing refers to input missing which is not on screen DETAILS"),
+        (json!({ "x": "${screen.PAYMENT.form.card}" }), "screen.PAYMENT.form.card refers to unknown screen PAYMENT"),
+        (json!({ "x": "${screen.APPOINTMENT.form.day}" }), "screen.APPOINTMENT.form.day refers to input day which is not on screen APPOINTMENT"),
+        (json!({ "x": "${screen.APPOINTMENT.data.day}" }), "screen.APPOINTMENT.data.day refers to data field day which screen APPOINTMENT does not declare"),
+    ];
+    for (payload, message) in cases {
+        let mut value = booking();
+        set(&mut value, footer, payload);
+        assert_violation(value, &format!("screens[1].layout.children[2]: {}", message));
+    }
+
+    // Expressions in nested branches are checked against the enclosing screen.
+    let mut value = booking();
+    set(&mut value, "/screens/1/layout/children/1/then/0/text", json!("${form.nickname}"));
+    assert_violation(
+        value,
+        "screens[1].layout.children[1].then[0]: form.nickname refers to input nickname which is not on screen DETAILS",
+    );
+}
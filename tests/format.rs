@@ -0,0 +1,129 @@
+//! WhatsApp text formatting: escaping, the builder and Markdown/HTML conversion
+
+use whatsapp_cloud_sdk::util::format::{escape, html_to_whatsapp, markdown_to_whatsapp, to_plain_text, ESCAPE_CHAR};
+use whatsapp_cloud_sdk::util::FormattedText;
+
+#[test]
+fn escaping_round_trips_through_plain_text() {
+    let inputs = [
+        "*not bold* and _not italic_",
+        "~gone~ `code` ```block```",
+        "> not a quote\n>> nor this",
+        "2 * 3 * 4 = 24",
+        "snake_case_name",
+        "",
+    ];
+    for input in inputs {
+        let escaped = escape(input);
+        assert_eq!(to_plain_text(&escaped), input, "{:?}", input);
+        assert_eq!(escaped.replace(ESCAPE_CHAR, ""), input, "{:?}", input);
+    }
+}
+
+#[test]
+fn escaping_breaks_every_marker_pair() {
+    let escaped = escape("*a* _b_ ~c~ `d`");
+    for marker in ['*', '_', '~', '`'] {
+        let pair = format!("{}{}", marker, ESCAPE_CHAR);
+        assert_eq!(escaped.matches(&pair).count(), 2, "{}", marker);
+    }
+    assert!(escape("> quoted").starts_with(ESCAPE_CHAR));
+    assert_eq!(escape("plain text"), "plain text");
+}
+
+#[test]
+fn builder_escapes_content_but_not_its_own_markup() {
+    let text = FormattedText::new()
+        .text("Order ")
+        .bold("A*1")
+        .text(" is ")
+        .italic(" ready ")
+        .build();
+    assert_eq!(text, format!("Order *A*{}1* is  _ready_ ", ESCAPE_CHAR));
+
+    let raw = FormattedText::new().raw("*kept*").strikethrough("old").build();
+    assert_eq!(raw, "*kept*~old~");
+}
+
+#[test]
+fn builder_starts_blocks_on_new_lines() {
+    let text = FormattedText::new()
+        .text("Summary")
+        .bulleted_list(["one", "two"])
+        .numbered_list(["first"])
+        .quote("said\nthis")
+        .line_break()
+        .monospace("let `x`")
+        .build();
+    assert_eq!(
+        text,
+        format!("Summary\n- one\n- two\n1. first\n> said\n> this\n```let `{}x`{}```", ESCAPE_CHAR, ESCAPE_CHAR)
+    );
+}
+
+#[test]
+fn converts_markdown_emphasis_and_links() {
+    let cases = [
+        ("**bold** and __bold__", "*bold* and *bold*"),
+        ("*italic* and _italic_", "_italic_ and _italic_"),
+        ("~~struck~~", "~struck~"),
+        ("`**code**`", "`**code**`"),
+        ("**bold _and italic_**", "*bold _and italic_*"),
+        ("[docs](https://example.com)", "docs (https://example.com)"),
+        ("[https://example.com](https://example.com)", "https://example.com"),
+        ("snake_case_name", "snake_case_name"),
+        ("2 * 3 * 4", "2 * 3 * 4"),
+    ];
+    for (markdown, expected) in cases {
+        assert_eq!(markdown_to_whatsapp(markdown), expected, "{}", markdown);
+    }
+}
+
+#[test]
+fn converts_markdown_blocks() {
+    let markdown = "# Title\n\n* one\n+ two\n> **quoted**\n```rust\nlet **x** = 1;\n```";
+    assert_eq!(
+        markdown_to_whatsapp(markdown),
+        "*Title*\n\n- one\n- two\n> *quoted*\n```\nlet **x** = 1;\n```"
+    );
+}
+
+#[test]
+fn converts_html_inline_tags() {
+    let cases = [
+        ("<b>bold</b> <strong>strong</strong>", "*bold* *strong*"),
+        ("<i>it</i> <em>em</em> <s>s</s> <del>del</del>", "_it_ _em_ ~s~ ~del~"),
+        ("<code>x</code>", "`x`"),
+        ("<b>bold </b>text", "*bold* text"),
+        ("<a href=\"https://example.com\">site</a>", "site (https://example.com)"),
+        ("<a href='https://example.com'>https://example.com</a>", "https://example.com"),
+        ("<span>a &amp; b &lt;c&gt;</span>", "a & b <c>"),
+        ("line<br>break", "line\nbreak"),
+    ];
+    for (html, expected) in cases {
+        assert_eq!(html_to_whatsapp(html), expected, "{}", html);
+    }
+}
+
+#[test]
+fn converts_html_blocks() {
+    let html = "<h2>Title</h2><p>First</p><p>Second</p><ul><li>a</li><li>b</li></ul><ol><li>x</li><li>y</li></ol>";
+    assert_eq!(html_to_whatsapp(html), "*Title*\n\nFirst\n\nSecond\n\n- a\n- b\n\n1. x\n2. y");
+
+    assert_eq!(html_to_whatsapp("<blockquote>quoted</blockquote>"), "> quoted");
+    assert_eq!(html_to_whatsapp("<pre>let  *x*;</pre>"), "```let  *x*;```");
+}
+
+#[test]
+fn html_text_is_escaped() {
+    let converted = html_to_whatsapp("<p>*not bold*</p>");
+    assert_eq!(to_plain_text(&converted), "*not bold*");
+    assert!(converted.contains(ESCAPE_CHAR));
+}
+
+#[test]
+fn plain_text_strips_markup() {
+    assert_eq!(to_plain_text("*bold* _it_ ~s~ `c`"), "bold it s c");
+    assert_eq!(to_plain_text("> quote\n```block```"), "quote\nblock");
+    assert_eq!(to_plain_text("2 * 3 * 4 and snake_case_name"), "2 * 3 * 4 and snake_case_name");
+}
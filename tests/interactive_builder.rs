@@ -0,0 +1,140 @@
+//! Interactive message builders beyond their serialized form
+
+use serde_json::json;
+use whatsapp_cloud_sdk::types::messages::*;
+use whatsapp_cloud_sdk::{PhoneNumber, Validate};
+
+fn reply_ids(interactive: &Interactive) -> Vec<String> {
+    match &interactive.action {
+        InteractiveAction::Buttons(buttons) => buttons
+            .buttons
+            .iter()
+            .map(|button| button.reply.as_ref().unwrap().id.clone())
+            .collect(),
+        other => panic!("unexpected action {:?}", other),
+    }
+}
+
+fn flow_parameters(interactive: Interactive) -> InteractiveFlowParameters {
+    match interactive.action {
+        InteractiveAction::Flow(flow) => flow.parameters,
+        other => panic!("unexpected action {:?}", other),
+    }
+}
+
+#[test]
+fn buttons_keep_their_order() {
+    let one = Interactive::buttons().body("Pick").reply_button("a", "A").build();
+    assert_eq!(reply_ids(&one), ["a"]);
+
+    let three = Interactive::buttons()
+        .reply_button("a", "A")
+        .body("Pick")
+        .reply_button("b", "B")
+        .reply_button("c", "C")
+        .build();
+    assert_eq!(three.r#type, InteractiveType::Button);
+    assert_eq!(reply_ids(&three), ["a", "b", "c"]);
+    assert!(three.violations().is_empty());
+}
+
+#[test]
+fn headers_and_footers_are_set() {
+    let interactive = Interactive::buttons()
+        .header_document("https://example.com/menu.pdf", Some("menu.pdf"))
+        .body("Pick")
+        .footer("Thanks")
+        .reply_button("a", "A")
+        .build();
+
+    assert_eq!(
+        serde_json::to_value(&interactive.header).unwrap(),
+        json!({ "type": "document", "document": { "link": "https://example.com/menu.pdf", "filename": "menu.pdf" } })
+    );
+    assert_eq!(interactive.footer.unwrap().text, "Thanks");
+
+    let video = Interactive::cta_url("Watch", "https://example.com")
+        .header_video("https://example.com/clip.mp4")
+        .header_text("Replaced")
+        .body("New episode")
+        .build();
+    assert!(matches!(video.header, Some(InteractiveHeader::Text { .. })));
+}
+
+#[test]
+fn list_rows_go_to_the_current_section() {
+    let interactive = Interactive::list("Menu")
+        .body("Pick a dish")
+        .row("soup", "Soup", None)
+        .section("Mains")
+        .row("pasta", "Pasta", Some("Fresh"))
+        .row("pizza", "Pizza", None)
+        .build();
+
+    let sections = match interactive.action {
+        InteractiveAction::Sections(sections) => sections,
+        other => panic!("unexpected action {:?}", other),
+    };
+    assert_eq!(sections.button, "Menu");
+    assert_eq!(sections.sections.len(), 2);
+    assert_eq!(sections.sections[0].title, None);
+    assert_eq!(sections.sections[0].rows.len(), 1);
+    assert_eq!(sections.sections[1].title.as_deref(), Some("Mains"));
+    assert_eq!(sections.sections[1].rows[0].description.as_deref(), Some("Fresh"));
+}
+
+#[test]
+fn flows_default_their_token_and_version() {
+    let parameters = flow_parameters(Interactive::flow("FLOW", "Open").body("Start").build());
+    assert_eq!(parameters.flow_token, None);
+    assert_eq!(parameters.flow_message_version, FLOW_MESSAGE_VERSION);
+    assert!(parameters.flow_action.is_none());
+    assert!(parameters.mode.is_none());
+
+    let parameters = flow_parameters(
+        Interactive::flow("FLOW", "Open")
+            .flow_message_version("4")
+            .navigate("START", None)
+            .data_exchange()
+            .draft()
+            .body("Start")
+            .build(),
+    );
+    assert_eq!(parameters.flow_message_version, "4");
+    assert_eq!(parameters.flow_action, Some(FlowAction::DataExchange));
+    assert!(parameters.flow_action_payload.is_none());
+    assert_eq!(parameters.mode, Some(FlowMode::Draft));
+}
+
+#[test]
+fn catalog_thumbnail_is_optional() {
+    let interactive = Interactive::catalog().body("Browse").build();
+    assert_eq!(
+        serde_json::to_value(&interactive.action).unwrap(),
+        json!({ "name": "catalog_message" })
+    );
+}
+
+#[test]
+fn product_lists_carry_their_header() {
+    let interactive = Interactive::product_list("CATALOG", "Sale")
+        .body("Picked for you")
+        .section("Shoes", ["SKU-1"])
+        .section("Hats", Vec::<String>::new())
+        .build();
+    assert!(matches!(interactive.header, Some(InteractiveHeader::Text { ref text }) if text == "Sale"));
+    match interactive.action {
+        InteractiveAction::ProductList(list) => assert_eq!(list.sections.len(), 2),
+        other => panic!("unexpected action {:?}", other),
+    }
+}
+
+#[test]
+fn send_to_addresses_a_phone_number() {
+    let message = Interactive::location_request()
+        .body("Where are you?")
+        .send_to(PhoneNumber::parse("+1 (555) 123-4567").unwrap());
+    assert_eq!(message.to, "15551234567");
+    assert!(message.context.is_none());
+    assert!(message.validate().is_ok());
+}
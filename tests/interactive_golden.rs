@@ -0,0 +1,182 @@
+//! Golden tests for interactive message serialization
+//!
+//! Each fixture in `tests/golden/interactive` is the `interactive` object the
+//! Cloud API documents for one message type. Messages built in code must
+//! serialize to the fixture exactly, and each fixture must deserialize to the
+//! matching action variant and serialize back unchanged.
+
+use serde_json::{json, Value};
+use whatsapp_cloud_sdk::types::messages::*;
+
+fn golden(name: &str) -> Value {
+    let path = format!("{}/tests/golden/interactive/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+    let contents = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    serde_json::from_str(&contents).unwrap()
+}
+
+fn assert_golden(name: &str, interactive: Interactive) {
+    assert_eq!(serde_json::to_value(&interactive).unwrap(), golden(name), "{}", name);
+}
+
+fn round_trip(name: &str) -> Interactive {
+    let expected = golden(name);
+    let interactive: Interactive = serde_json::from_value(expected.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&interactive).unwrap(), expected, "{}", name);
+    interactive
+}
+
+#[test]
+fn button() {
+    assert_golden(
+        "button",
+        Interactive::buttons()
+            .header_image("https://example.com/menu.jpg")
+            .body("Would you like to proceed?")
+            .footer("Reply below")
+            .reply_button("yes", "Yes")
+            .reply_button("no", "No")
+            .build(),
+    );
+    assert!(matches!(round_trip("button").action, InteractiveAction::Buttons(_)));
+}
+
+#[test]
+fn list() {
+    assert_golden(
+        "list",
+        Interactive::list("View menu")
+            .header_text("Menu")
+            .body("Pick a dish")
+            .section("Mains")
+            .row("pasta", "Pasta", Some("Fresh tagliatelle"))
+            .row("pizza", "Pizza", None)
+            .build(),
+    );
+    assert!(matches!(round_trip("list").action, InteractiveAction::Sections(_)));
+}
+
+#[test]
+fn product() {
+    assert_golden(
+        "product",
+        Interactive::product("367025965434465", "SKU-1").body("Our bestseller").build(),
+    );
+    assert!(matches!(round_trip("product").action, InteractiveAction::Product(_)));
+}
+
+#[test]
+fn product_list() {
+    assert_golden(
+        "product_list",
+        Interactive::product_list("367025965434465", "Summer sale")
+            .body("Picked for you")
+            .section("Shoes", ["SKU-1", "SKU-2"])
+            .build(),
+    );
+    assert!(matches!(round_trip("product_list").action, InteractiveAction::ProductList(_)));
+}
+
+#[test]
+fn catalog_message() {
+    assert_golden(
+        "catalog_message",
+        Interactive::catalog()
+            .body("Browse our catalog")
+            .footer("Free delivery")
+            .thumbnail("SKU-1")
+            .build(),
+    );
+    assert!(matches!(round_trip("catalog_message").action, InteractiveAction::Catalog(_)));
+}
+
+#[test]
+fn cta_url() {
+    assert_golden(
+        "cta_url",
+        Interactive::cta_url("Track", "https://example.com/track/42")
+            .header_text("Order status")
+            .body("Track your order online.")
+            .build(),
+    );
+
+    let message: SendInteractiveMessage = SendInteractiveCtaUrlButtonMessage {
+        to: "15551234567".to_string(),
+        body: "Track your order online.".to_string(),
+        button: InteractiveCtaUrlButton {
+            display_text: "Track".to_string(),
+            url: "https://example.com/track/42".to_string(),
+        },
+        header_text: Some("Order status".to_string()),
+        footer_text: None,
+        context: None,
+    }
+    .into();
+    assert_golden("cta_url", message.interactive);
+
+    assert!(matches!(round_trip("cta_url").action, InteractiveAction::CtaUrl(_)));
+}
+
+#[test]
+fn flow() {
+    assert_golden(
+        "flow",
+        Interactive::flow("1234567890", "Book now")
+            .flow_token("booking-42")
+            .navigate("APPOINTMENT", Some(json!({ "clinic": "north" })))
+            .body("Book an appointment")
+            .build(),
+    );
+    assert!(matches!(round_trip("flow").action, InteractiveAction::Flow(_)));
+}
+
+#[test]
+#[allow(deprecated)]
+fn location_request_message() {
+    assert_golden(
+        "location_request_message",
+        Interactive::location_request().body("Where should we deliver?").build(),
+    );
+
+    let message: SendInteractiveMessage = SendInteractiveLocationRequestParams {
+        to: "15551234567".to_string(),
+        body: "Where should we deliver?".to_string(),
+        button_text: None,
+        footer_text: None,
+        context: Some(MessageContext::new("wamid.IN")),
+    }
+    .into();
+    assert_eq!(message.context, Some(MessageContext::new("wamid.IN")));
+    assert_golden("location_request_message", message.interactive);
+
+    assert!(matches!(
+        round_trip("location_request_message").action,
+        InteractiveAction::LocationRequest(_)
+    ));
+}
+
+#[test]
+fn address_message() {
+    let message: SendInteractiveMessage = SendAddressMessage {
+        to: "919876543210".to_string(),
+        body: "Please confirm your delivery address".to_string(),
+        parameters: InteractiveAddressParameters {
+            country: "IN".to_string(),
+            values: Some(json!({ "name": "Asha", "city": "Pune" })),
+            saved_addresses: None,
+            validation_errors: None,
+        },
+        context: None,
+    }
+    .into();
+    assert_golden("address_message", message.interactive);
+    assert!(matches!(round_trip("address_message").action, InteractiveAction::Address(_)));
+}
+
+#[test]
+fn unknown_action_is_rejected() {
+    let action = serde_json::from_value::<InteractiveAction>(json!({ "name": "voice_call" }));
+    assert!(action.is_err());
+
+    let action = serde_json::from_value::<InteractiveAction>(json!({ "catalog_id": "1" }));
+    assert!(action.is_err());
+}
@@ -0,0 +1,248 @@
+//! Downloading inbound media to sinks
+
+mod common;
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use whatsapp_cloud_sdk::media_download::{FileSystemMediaSink, InMemoryMediaSink};
+use whatsapp_cloud_sdk::types::webhook::{MediaChecksum, StoredMedia, WebhookChangeValue, WebhookEvent};
+use whatsapp_cloud_sdk::{create_webhook_handler, MediaDownloader, WebhookRouter};
+use wiremock::matchers::{method, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{client, messages_event};
+
+const PHOTO: &[u8] = b"not really a jpeg, but bytes all the same";
+
+/// Serves `body` for every media id, from the URL `retrieve_media_url` returns.
+async fn server(body: &[u8]) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path_regex("^/download$"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(body.to_vec()))
+        .mount(&server)
+        .await;
+    serve_media_url(&server, &format!("{}/download", server.uri())).await;
+    server
+}
+
+async fn serve_media_url(server: &MockServer, url: &str) {
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "MEDIA_ID",
+            "url": url,
+            "messaging_product": "whatsapp",
+        })))
+        .mount(server)
+        .await;
+}
+
+/// Serves `chunks` chunks of `size` bytes once, with no Content-Length
+/// announcing the size up front.
+async fn chunked_server(chunks: usize, size: usize) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = [0u8; 4096];
+        let _ = stream.read(&mut request).await;
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n").await;
+        for _ in 0..chunks {
+            let _ = stream.write_all(format!("{:x}\r\n", size).as_bytes()).await;
+            let _ = stream.write_all(&vec![b'x'; size]).await;
+            let _ = stream.write_all(b"\r\n").await;
+        }
+        let _ = stream.write_all(b"0\r\n\r\n").await;
+    });
+    format!("http://{}/download", address)
+}
+
+fn image(media_id: &str, sha256: Option<String>) -> Value {
+    json!({
+        "from": "15551234567",
+        "id": "wamid.IMAGE",
+        "timestamp": "1700000000",
+        "type": "image",
+        "image": { "id": media_id, "mime_type": "image/jpeg", "sha256": sha256 },
+    })
+}
+
+fn document(media_id: &str, mime_type: &str, filename: &str) -> Value {
+    json!({
+        "from": "15551234567",
+        "id": "wamid.DOCUMENT",
+        "timestamp": "1700000000",
+        "type": "document",
+        "document": { "id": media_id, "mime_type": mime_type, "filename": filename },
+    })
+}
+
+fn stored(event: &WebhookEvent) -> Vec<Option<StoredMedia>> {
+    event
+        .changes()
+        .flat_map(|change| match &change.value {
+            WebhookChangeValue::Messages(value) => value.messages.clone(),
+            _ => Vec::new(),
+        })
+        .map(|message| message.media().and_then(|media| media.stored.clone()))
+        .collect()
+}
+
+fn files(directory: &Path) -> Vec<String> {
+    let mut files: Vec<String> = std::fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    files
+}
+
+
+#[tokio::test]
+async fn media_is_written_to_the_directory_and_checked() {
+    let server = server(PHOTO).await;
+    let directory = tempfile::tempdir().unwrap();
+    let sink = Arc::new(FileSystemMediaSink::new(directory.path()).unwrap());
+    let downloader = MediaDownloader::new(client(&server), sink);
+    let sha256 = hex::encode(Sha256::digest(PHOTO));
+
+    let mut event = messages_event(vec![image("MEDIA_ID", Some(sha256.clone()))]);
+    assert!(downloader.handle_webhook_event(&mut event).await.is_empty());
+
+    let stored = stored(&event).remove(0).unwrap();
+    assert_eq!(stored.size, PHOTO.len() as u64);
+    assert_eq!(stored.sha256, sha256);
+    assert_eq!(stored.checksum, MediaChecksum::Verified);
+    assert_eq!(Path::new(&stored.location), directory.path().join("MEDIA_ID.jpg"));
+    assert_eq!(std::fs::read(&stored.location).unwrap(), PHOTO);
+    assert_eq!(files(directory.path()), ["MEDIA_ID.jpg"]);
+}
+
+#[tokio::test]
+async fn checksums_are_compared_in_hex_and_base64() {
+    let server = server(PHOTO).await;
+    let sink = Arc::new(InMemoryMediaSink::new());
+    let downloader = MediaDownloader::new(client(&server), sink.clone());
+    let digest = Sha256::digest(PHOTO);
+
+    let mut event = messages_event(vec![
+        image("HEX", Some(hex::encode_upper(digest))),
+        image("BASE64", Some(BASE64.encode(digest))),
+        image("WRONG", Some(BASE64.encode(Sha256::digest(b"other bytes")))),
+        image("NONE", None),
+    ]);
+    assert!(downloader.handle_webhook_event(&mut event).await.is_empty());
+
+    let checksums: Vec<MediaChecksum> = stored(&event).into_iter().map(|stored| stored.unwrap().checksum).collect();
+    assert_eq!(checksums[0], MediaChecksum::Verified);
+    assert_eq!(checksums[1], MediaChecksum::Verified);
+    assert!(matches!(checksums[2], MediaChecksum::Mismatch { .. }));
+    assert_eq!(checksums[3], MediaChecksum::Unavailable);
+    assert_eq!(sink.get("BASE64").unwrap(), PHOTO);
+}
+
+#[tokio::test]
+async fn file_names_cannot_leave_the_directory() {
+    let server = server(PHOTO).await;
+    let directory = tempfile::tempdir().unwrap();
+    let media = directory.path().join("media");
+    let downloader = MediaDownloader::new(client(&server), Arc::new(FileSystemMediaSink::new(&media).unwrap()));
+
+    let mut event = messages_event(vec![
+        document("../../escaped", "application/pdf", "../../report.PDF"),
+        document("voice/../1", "audio/ogg; codecs=opus", "note"),
+        document("script", "text/plain", "run.s h"),
+    ]);
+    assert!(downloader.handle_webhook_event(&mut event).await.is_empty());
+
+    assert_eq!(files(&media), ["escaped.pdf", "script", "voice1.ogg"]);
+    assert_eq!(files(directory.path()), ["media"]);
+}
+
+#[tokio::test]
+async fn media_over_the_max_size_is_refused() {
+    let server = server(PHOTO).await;
+    let directory = tempfile::tempdir().unwrap();
+    let sink = Arc::new(FileSystemMediaSink::new(directory.path()).unwrap());
+    let downloader = MediaDownloader::new(client(&server), sink).max_size(PHOTO.len() as u64 - 1);
+
+    let mut event = messages_event(vec![image("MEDIA_ID", None)]);
+    let failures = downloader.handle_webhook_event(&mut event).await;
+
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].media_id, "MEDIA_ID");
+    assert_eq!(failures[0].message_id, "wamid.IMAGE");
+    assert_eq!(stored(&event), [None]);
+    assert!(files(directory.path()).is_empty());
+}
+
+#[tokio::test]
+async fn downloads_growing_past_the_max_size_are_aborted() {
+    let server = MockServer::start().await;
+    serve_media_url(&server, &chunked_server(4, 600).await).await;
+    let directory = tempfile::tempdir().unwrap();
+    let sink = Arc::new(FileSystemMediaSink::new(directory.path()).unwrap());
+    let downloader = MediaDownloader::new(client(&server), sink).max_size(1000);
+
+    let mut event = messages_event(vec![image("MEDIA_ID", None)]);
+    let failures = downloader.handle_webhook_event(&mut event).await;
+
+    assert_eq!(failures.len(), 1);
+    assert_eq!(stored(&event), [None]);
+    assert!(files(directory.path()).is_empty());
+}
+
+#[tokio::test]
+async fn stickers_are_skipped_unless_asked_for() {
+    let server = server(PHOTO).await;
+    let sink = Arc::new(InMemoryMediaSink::new());
+    let sticker = json!({
+        "from": "15551234567",
+        "id": "wamid.STICKER",
+        "timestamp": "1700000000",
+        "type": "sticker",
+        "sticker": { "id": "STICKER_ID", "mime_type": "image/webp", "animated": false },
+    });
+
+    let mut event = messages_event(vec![sticker.clone()]);
+    MediaDownloader::new(client(&server), sink.clone()).handle_webhook_event(&mut event).await;
+    assert_eq!(stored(&event), [None]);
+
+    let mut event = messages_event(vec![sticker]);
+    MediaDownloader::new(client(&server), sink.clone()).stickers(true).handle_webhook_event(&mut event).await;
+    assert_eq!(stored(&event)[0].as_ref().unwrap().location, "STICKER_ID");
+}
+
+#[tokio::test]
+async fn routers_download_media_before_dispatching() {
+    let server = server(PHOTO).await;
+    let sink = Arc::new(InMemoryMediaSink::new());
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let handled = Arc::clone(&seen);
+    let router = WebhookRouter::new(create_webhook_handler(None, None))
+        .download_media(MediaDownloader::new(client(&server), sink.clone()).max_size(PHOTO.len() as u64))
+        .on_message(move |message| {
+            let handled = Arc::clone(&handled);
+            async move {
+                handled.lock().unwrap().push(message.media().and_then(|media| media.stored.clone()));
+                Ok(())
+            }
+        });
+
+    let report = router.dispatch(messages_event(vec![image("MEDIA_ID", None)])).await;
+    assert!(report.is_ok());
+    assert_eq!(seen.lock().unwrap()[0].as_ref().unwrap().location, "MEDIA_ID");
+    assert_eq!(sink.get("MEDIA_ID").unwrap(), PHOTO);
+
+    let router = WebhookRouter::new(create_webhook_handler(None, None))
+        .download_media(MediaDownloader::new(client(&server), sink).max_size(8));
+    let report = router.dispatch(messages_event(vec![image("LARGE_ID", None)])).await;
+    assert!(!report.is_ok());
+    assert_eq!(report.media_failures.len(), 1);
+}
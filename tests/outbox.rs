@@ -0,0 +1,328 @@
+//! Outbox stores and the worker sending their messages once
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
+use whatsapp_cloud_sdk::outbox::{InMemoryOutbox, OutboxStatus};
+use whatsapp_cloud_sdk::types::messages::{OutboundMessage, SendTextMessage};
+use whatsapp_cloud_sdk::{Outbox, OutboxWorker};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{bodies, client, sent, statuses_event, PHONE_NUMBER_ID};
+
+fn messages_path() -> String {
+    format!("/{}/messages", PHONE_NUMBER_ID)
+}
+
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sent("wamid.OUT")))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn text(body: &str) -> SendTextMessage {
+    SendTextMessage {
+        to: "15551234567".to_string(),
+        text: body.to_string(),
+        preview_url: None,
+        context: None,
+    }
+}
+
+fn message(body: &str) -> OutboundMessage {
+    text(body).into()
+}
+
+/// Leaves `key` `Sending`, as a crash right after handing it to the API would.
+fn stranded<O: Outbox>(outbox: &O, key: &str) {
+    outbox.enqueue(key, &message("Your order shipped")).unwrap();
+    assert!(outbox.claim(key).unwrap());
+    std::thread::sleep(Duration::from_millis(5));
+}
+
+fn delivered(key: &str, message_id: &str) -> whatsapp_cloud_sdk::types::webhook::WebhookEvent {
+    statuses_event(vec![json!({
+        "id": message_id,
+        "status": "delivered",
+        "timestamp": "1700000000",
+        "recipient_id": "15551234567",
+        "biz_opaque_callback_data": key,
+    })])
+}
+
+/// The contract every store must keep.
+fn check_store<O: Outbox>(outbox: &O) {
+    let first = outbox.enqueue("order-1", &message("first")).unwrap();
+    assert_eq!(first.status, OutboxStatus::Pending);
+    let replayed = outbox.enqueue("order-1", &message("second")).unwrap();
+    assert!(matches!(replayed.message, OutboundMessage::Text(ref text) if text.text == "first"));
+
+    assert_eq!(outbox.due(10).unwrap().len(), 1);
+    assert!(outbox.claim("order-1").unwrap());
+    assert!(!outbox.claim("order-1").unwrap());
+    assert!(outbox.due(10).unwrap().is_empty());
+
+    outbox.mark_sent("order-1", "wamid.1").unwrap();
+    outbox.mark_sent("order-1", "wamid.2").unwrap();
+    let entry = outbox.get("order-1").unwrap().unwrap();
+    assert_eq!(entry.status, OutboxStatus::Sent);
+    assert_eq!(entry.message_id.as_deref(), Some("wamid.1"));
+    assert_eq!(entry.attempts, 1);
+
+    // A settled entry is neither timed out nor requeued.
+    outbox.mark_unconfirmed("order-1", "timeout").unwrap();
+    assert!(!outbox.requeue("order-1").unwrap());
+    assert_eq!(outbox.get("order-1").unwrap().unwrap().status, OutboxStatus::Sent);
+
+    stranded(outbox, "order-2");
+    let stale = outbox.stale_sending(u64::MAX).unwrap();
+    assert_eq!(stale.len(), 1);
+    assert_eq!(stale[0].key, "order-2");
+    assert!(outbox.stale_sending(0).unwrap().is_empty());
+
+    outbox.mark_unconfirmed("order-2", "timeout").unwrap();
+    assert_eq!(outbox.unconfirmed(10).unwrap()[0].key, "order-2");
+    assert!(outbox.due(10).unwrap().is_empty());
+
+    assert!(outbox.requeue("order-2").unwrap());
+    let entry = outbox.get("order-2").unwrap().unwrap();
+    assert_eq!(entry.status, OutboxStatus::Pending);
+    assert_eq!(entry.last_error.as_deref(), Some("timeout"));
+    assert_eq!(outbox.due(10).unwrap().len(), 1);
+
+    assert!(outbox.get("missing").unwrap().is_none());
+}
+
+
+#[test]
+fn in_memory_outbox_keeps_the_store_contract() {
+    check_store(&InMemoryOutbox::new());
+}
+
+#[test]
+fn concurrent_claims_succeed_once() {
+    let outbox = Arc::new(InMemoryOutbox::new());
+    outbox.enqueue("order-1", &message("Hi")).unwrap();
+
+    let claims: Vec<bool> = (0..8)
+        .map(|_| {
+            let outbox = Arc::clone(&outbox);
+            std::thread::spawn(move || outbox.claim("order-1").unwrap())
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|thread| thread.join().unwrap())
+        .collect();
+
+    assert_eq!(claims.iter().filter(|claimed| **claimed).count(), 1);
+    assert_eq!(outbox.get("order-1").unwrap().unwrap().attempts, 1);
+}
+
+#[tokio::test]
+async fn replaying_a_key_sends_once() {
+    let server = server().await;
+    let worker = OutboxWorker::new(client(&server), Arc::new(InMemoryOutbox::new()));
+
+    let first = worker.send("order-1", text("Your order shipped")).await.unwrap();
+    let replayed = worker.send("order-1", text("Your order shipped")).await.unwrap();
+
+    assert_eq!(first.status, OutboxStatus::Sent);
+    assert_eq!(replayed.message_id.as_deref(), Some("wamid.OUT"));
+    assert_eq!(replayed.attempts, 1);
+    assert_eq!(worker.dispatch_pending().await.unwrap(), 0);
+
+    let sent = bodies(&server, &messages_path()).await;
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0]["biz_opaque_callback_data"], "order-1");
+}
+
+#[tokio::test]
+async fn invalid_messages_are_not_stored() {
+    let server = server().await;
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let worker = OutboxWorker::new(client(&server), Arc::clone(&outbox));
+
+    assert!(worker.enqueue("order-1", text("")).is_err());
+    assert!(outbox.get("order-1").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn timed_out_entries_wait_for_an_explicit_resend() {
+    let server = server().await;
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let worker = OutboxWorker::new(client(&server), Arc::clone(&outbox)).confirmation_timeout(Duration::ZERO);
+    stranded(&*outbox, "order-1");
+
+    assert_eq!(worker.dispatch_pending().await.unwrap(), 0);
+    assert_eq!(outbox.get("order-1").unwrap().unwrap().status, OutboxStatus::Unconfirmed);
+    assert_eq!(worker.dispatch_pending().await.unwrap(), 0);
+    assert!(bodies(&server, &messages_path()).await.is_empty());
+
+    let entry = worker.resend("order-1").await.unwrap();
+    assert_eq!(entry.status, OutboxStatus::Sent);
+    assert_eq!(entry.attempts, 2);
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 1);
+}
+
+#[tokio::test]
+async fn status_webhooks_settle_timed_out_entries() {
+    let server = server().await;
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let worker = OutboxWorker::new(client(&server), Arc::clone(&outbox)).confirmation_timeout(Duration::ZERO);
+    stranded(&*outbox, "order-1");
+    worker.dispatch_pending().await.unwrap();
+
+    worker.handle_webhook_event(&delivered("order-1", "wamid.LATE")).unwrap();
+
+    let entry = outbox.get("order-1").unwrap().unwrap();
+    assert_eq!(entry.status, OutboxStatus::Sent);
+    assert_eq!(entry.message_id.as_deref(), Some("wamid.LATE"));
+    assert_eq!(worker.resend("order-1").await.unwrap().attempts, 1);
+    assert!(bodies(&server, &messages_path()).await.is_empty());
+}
+
+#[tokio::test]
+async fn timed_out_entries_are_resent_when_opted_in() {
+    let server = server().await;
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let worker = OutboxWorker::new(client(&server), Arc::clone(&outbox))
+        .confirmation_timeout(Duration::ZERO)
+        .resend_unconfirmed(true);
+    stranded(&*outbox, "order-1");
+
+    assert_eq!(worker.dispatch_pending().await.unwrap(), 1);
+    assert_eq!(outbox.get("order-1").unwrap().unwrap().status, OutboxStatus::Sent);
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 1);
+}
+
+#[tokio::test]
+async fn permanent_failures_are_not_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(400).set_body_json(common::api_error(131026, "Message undeliverable")))
+        .mount(&server)
+        .await;
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let worker = OutboxWorker::new(client(&server), Arc::clone(&outbox));
+
+    let entry = worker.send("order-1", text("Hi")).await.unwrap();
+
+    assert_eq!(entry.status, OutboxStatus::Failed);
+    assert!(entry.last_error.is_some());
+    assert_eq!(worker.dispatch_pending().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn failed_status_webhooks_fail_the_entry() {
+    let server = server().await;
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let worker = OutboxWorker::new(client(&server), Arc::clone(&outbox));
+    stranded(&*outbox, "order-1");
+
+    let failed = statuses_event(vec![json!({
+        "id": "wamid.OUT",
+        "status": "failed",
+        "timestamp": "1700000000",
+        "recipient_id": "15551234567",
+        "biz_opaque_callback_data": "order-1",
+        "errors": [{ "code": 131026, "title": "Message undeliverable" }],
+    })]);
+    worker.handle_webhook_event(&failed).unwrap();
+
+    let entry = outbox.get("order-1").unwrap().unwrap();
+    assert_eq!(entry.status, OutboxStatus::Failed);
+    assert_eq!(entry.last_error.as_deref(), Some("Message undeliverable (131026)"));
+    assert_eq!(entry.message_id, None);
+}
+
+#[tokio::test]
+async fn unreadable_responses_leave_the_entry_unconfirmed() {
+    for body in [json!("<html>gateway</html>"), json!({ "messaging_product": "whatsapp", "contacts": [], "messages": [] })] {
+        let server = MockServer::start().await;
+        let response = match body.as_str() {
+            Some(text) => ResponseTemplate::new(200).set_body_string(text),
+            None => ResponseTemplate::new(200).set_body_json(&body),
+        };
+        Mock::given(method("POST")).and(path(messages_path())).respond_with(response).mount(&server).await;
+        let outbox = Arc::new(InMemoryOutbox::new());
+        let worker = OutboxWorker::new(client(&server), Arc::clone(&outbox)).confirmation_timeout(Duration::ZERO);
+
+        let entry = worker.send("order-1", text("Hi")).await.unwrap();
+        assert_eq!(entry.status, OutboxStatus::Sending, "{}", body);
+        assert_eq!(entry.message_id, None);
+
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(worker.dispatch_pending().await.unwrap(), 0);
+        assert_eq!(outbox.get("order-1").unwrap().unwrap().status, OutboxStatus::Unconfirmed);
+        assert_eq!(bodies(&server, &messages_path()).await.len(), 1);
+    }
+}
+
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use whatsapp_cloud_sdk::outbox::SqliteOutbox;
+
+    #[test]
+    fn sqlite_outbox_keeps_the_store_contract() {
+        check_store(&SqliteOutbox::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn entries_survive_reopening() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("outbox.db");
+
+        let outbox = SqliteOutbox::open(&path).unwrap();
+        stranded(&outbox, "order-1");
+        outbox.mark_unconfirmed("order-1", "timeout").unwrap();
+        drop(outbox);
+
+        let entry = SqliteOutbox::open(&path).unwrap().get("order-1").unwrap().unwrap();
+        assert_eq!(entry.status, OutboxStatus::Unconfirmed);
+        assert_eq!(entry.attempts, 1);
+        assert!(matches!(entry.message, OutboundMessage::Text(_)));
+    }
+
+    #[test]
+    fn concurrent_claims_across_connections_succeed_once() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("outbox.db");
+        SqliteOutbox::open(&path).unwrap().enqueue("order-1", &message("Hi")).unwrap();
+
+        let claims: Vec<bool> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || SqliteOutbox::open(&path).unwrap().claim("order-1").unwrap())
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect();
+
+        assert_eq!(claims.iter().filter(|claimed| **claimed).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn worker_sends_from_sqlite() {
+        let server = server().await;
+        let worker = OutboxWorker::new(client(&server), Arc::new(SqliteOutbox::open_in_memory().unwrap()));
+
+        worker.enqueue("order-1", text("Hi")).unwrap();
+        assert_eq!(worker.dispatch_pending().await.unwrap(), 1);
+
+        let entry = worker.outbox().get("order-1").unwrap().unwrap();
+        assert_eq!(entry.status, OutboxStatus::Sent);
+        assert_eq!(entry.message_id.as_deref(), Some("wamid.OUT"));
+    }
+}
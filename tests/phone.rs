@@ -0,0 +1,85 @@
+//! Phone number normalization to `wa_id` form
+
+use whatsapp_cloud_sdk::types::SendReactionMessage;
+use whatsapp_cloud_sdk::util::phone::{is_valid, normalize};
+use whatsapp_cloud_sdk::PhoneNumber;
+
+#[test]
+fn normalizes_international_formats() {
+    let cases = [
+        ("+1 (555) 123-4567", "15551234567"),
+        ("15551234567", "15551234567"),
+        ("00 44 20 7946 0958", "442079460958"),
+        ("+44 7911.123.456", "447911123456"),
+        ("+49 30/901820", "4930901820"),
+        ("+91 98765 43210", "919876543210"),
+        ("+55 11 91234 5678", "5511912345678"),
+        ("+39 06 6982 1234", "390669821234"),
+        ("+971 50 123 4567", "971501234567"),
+        ("+1\u{a0}555\u{a0}123\u{a0}4567", "15551234567"),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(normalize(input, None).unwrap(), expected, "{}", input);
+    }
+}
+
+#[test]
+fn normalizes_national_formats_with_a_region() {
+    let cases = [
+        ("020 7946 0958", "GB", "442079460958"),
+        ("(555) 123-4567", "US", "15551234567"),
+        ("1 555 123 4567", "US", "15551234567"),
+        ("8 912 345 67 89", "RU", "79123456789"),
+        ("06 6982 1234", "IT", "390669821234"),
+        ("030 901820", "de", "4930901820"),
+        ("+33 6 12 34 56 78", "GB", "33612345678"),
+    ];
+    for (input, region, expected) in cases {
+        assert_eq!(normalize(input, Some(region)).unwrap(), expected, "{} in {}", input, region);
+    }
+}
+
+#[test]
+fn accepts_mexican_wa_ids_with_the_legacy_mobile_prefix() {
+    assert_eq!(normalize("5215512345678", None).unwrap(), "5215512345678");
+    assert_eq!(normalize("+52 55 1234 5678", None).unwrap(), "525512345678");
+    assert!(!is_valid("5225512345678"));
+}
+
+#[test]
+fn rejects_invalid_numbers() {
+    for input in ["", "+", "abc", "+1 555 123 456", "+1 555 123 45678", "+999 1234567", "+44 20 7946 0958 ext 1"] {
+        assert!(!is_valid(input), "{}", input);
+    }
+    assert!(normalize("020 7946 0958", Some("ZZ")).is_err());
+}
+
+#[test]
+fn splits_country_code_and_national_number() {
+    let number = PhoneNumber::parse("+44 20 7946 0958").unwrap();
+    assert_eq!(number.country_code(), "44");
+    assert_eq!(number.national_number(), "2079460958");
+    assert_eq!(number.region(), Some("GB"));
+    assert_eq!(number.to_e164(), "+442079460958");
+    assert_eq!(number.to_string(), "+442079460958");
+
+    let number = PhoneNumber::parse("+1 242 555 0100").unwrap();
+    assert_eq!(number.country_code(), "1");
+    assert_eq!(number.region(), Some("US"));
+}
+
+#[test]
+fn serializes_as_a_wa_id() {
+    let number = PhoneNumber::parse("+1 555 123 4567").unwrap();
+    assert_eq!(serde_json::to_value(&number).unwrap(), "15551234567");
+    let parsed: PhoneNumber = serde_json::from_value(serde_json::json!("+1 (555) 123-4567")).unwrap();
+    assert_eq!(parsed, number);
+    assert!(serde_json::from_value::<PhoneNumber>(serde_json::json!("12")).is_err());
+}
+
+#[test]
+fn message_constructors_take_phone_numbers() {
+    let number = PhoneNumber::parse("+52 1 55 1234 5678").unwrap();
+    let reaction = SendReactionMessage::new(number, "wamid.1", "👍");
+    assert_eq!(reaction.to, "5215512345678");
+}
@@ -0,0 +1,262 @@
+//! Business profile and phone number management endpoints
+
+mod common;
+
+use serde_json::json;
+use whatsapp_cloud_sdk::types::*;
+use wiremock::matchers::{body_json, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{client, PHONE_NUMBER_ID};
+
+fn success() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "success": true }))
+}
+
+#[tokio::test]
+async fn gets_business_profile() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{}/whatsapp_business_profile", PHONE_NUMBER_ID)))
+        .and(query_param("fields", "about,address,description,email,profile_picture_url,websites,vertical"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{
+                "about": "Fresh pasta daily",
+                "websites": ["https://example.com"],
+                "vertical": "RESTAURANT",
+                "messaging_product": "whatsapp",
+            }],
+        })))
+        .mount(&server)
+        .await;
+
+    let profile = client(&server).get_business_profile().await.unwrap();
+    assert_eq!(profile.about.as_deref(), Some("Fresh pasta daily"));
+    assert_eq!(profile.vertical, Some(BusinessVertical::Restaurant));
+}
+
+#[tokio::test]
+async fn empty_business_profile_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": [] })))
+        .mount(&server)
+        .await;
+
+    assert!(client(&server).get_business_profile().await.is_err());
+}
+
+#[tokio::test]
+async fn updates_only_set_profile_fields() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("/{}/whatsapp_business_profile", PHONE_NUMBER_ID)))
+        .and(body_json(json!({ "about": "Open late", "messaging_product": "whatsapp" })))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let profile = BusinessProfile {
+        about: Some("Open late".to_string()),
+        ..Default::default()
+    };
+    assert!(client(&server).update_business_profile(profile).await.unwrap().success);
+}
+
+#[tokio::test]
+async fn lists_phone_numbers() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/WABA_ID/phone_numbers"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{
+                "id": PHONE_NUMBER_ID,
+                "display_phone_number": "+1 555-000-0000",
+                "quality_rating": "GREEN",
+                "throughput": { "level": "STANDARD" },
+                "messaging_limit_tier": "TIER_1K",
+                "code_verification_status": "VERIFIED",
+                "platform_type": "CLOUD_API",
+            }],
+            "paging": { "cursors": { "before": "a", "after": "b" } },
+        })))
+        .mount(&server)
+        .await;
+
+    let numbers = client(&server).get_phone_numbers().await.unwrap();
+    let number = &numbers.data[0];
+    assert_eq!(number.quality_rating, Some(QualityRating::Green));
+    assert_eq!(number.throughput.as_ref().unwrap().level, ThroughputLevel::Standard);
+    assert_eq!(number.code_verification_status, Some(CodeVerificationStatus::Verified));
+    assert_eq!(numbers.paging.unwrap().cursors.unwrap().after, "b");
+}
+
+#[tokio::test]
+async fn phone_numbers_need_a_business_account() {
+    let server = MockServer::start().await;
+    let client = whatsapp_cloud_sdk::create_client("token", PHONE_NUMBER_ID, None).with_base_url(&server.uri());
+
+    assert!(matches!(
+        client.get_phone_numbers().await,
+        Err(whatsapp_cloud_sdk::error::WhatsAppError::MissingField(_))
+    ));
+}
+
+#[tokio::test]
+async fn unknown_ratings_do_not_fail_parsing() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{}", PHONE_NUMBER_ID)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": PHONE_NUMBER_ID,
+            "quality_rating": "PURPLE",
+            "throughput": { "level": "ULTRA" },
+        })))
+        .mount(&server)
+        .await;
+
+    let number = client(&server).get_phone_number().await.unwrap();
+    assert_eq!(number.quality_rating, Some(QualityRating::Unknown));
+    assert_eq!(number.throughput.unwrap().level, ThroughputLevel::Unknown);
+}
+
+#[tokio::test]
+async fn registers_and_deregisters() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("/{}/register", PHONE_NUMBER_ID)))
+        .and(body_json(json!({ "messaging_product": "whatsapp", "pin": "123456" })))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("/{}/deregister", PHONE_NUMBER_ID)))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    let params = RegisterPhoneNumber {
+        pin: "123456".to_string(),
+        data_localization_region: None,
+    };
+    assert!(client.register_phone_number(params).await.unwrap().success);
+    assert!(client.deregister_phone_number(DeregisterPhoneNumber::default()).await.unwrap().success);
+}
+
+#[tokio::test]
+async fn requests_and_verifies_codes() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("/{}/request_code", PHONE_NUMBER_ID)))
+        .and(body_json(json!({ "code_method": "SMS", "language": "en_US" })))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("/{}/verify_code", PHONE_NUMBER_ID)))
+        .and(body_json(json!({ "code": "654321" })))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    client
+        .request_verification_code(RequestVerificationCode {
+            code_method: CodeMethod::Sms,
+            language: "en_US".to_string(),
+        })
+        .await
+        .unwrap();
+    client
+        .verify_code(VerifyCode {
+            code: "654321".to_string(),
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn api_errors_are_decoded() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(common::api_error(100, "Invalid parameter")))
+        .mount(&server)
+        .await;
+
+    let error = client(&server).get_phone_number().await.unwrap_err();
+    assert_eq!(error.code(), Some(100));
+}
+
+#[tokio::test]
+async fn sets_two_step_verification_pin() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("/{}", PHONE_NUMBER_ID)))
+        .and(body_json(json!({ "pin": "246810" })))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    assert!(client(&server).set_two_step_verification_pin("246810").await.unwrap().success);
+}
+
+#[tokio::test]
+async fn malformed_pins_are_rejected_before_sending() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(success()).expect(0).mount(&server).await;
+
+    let client = client(&server);
+    for pin in ["12345", "1234567", "12a456", "", "١٢٣٤٥٦"] {
+        assert!(client.set_two_step_verification_pin(pin).await.is_err(), "{}", pin);
+        let params = RegisterPhoneNumber {
+            pin: pin.to_string(),
+            data_localization_region: None,
+        };
+        assert!(client.register_phone_number(params).await.is_err(), "{}", pin);
+    }
+}
+
+#[tokio::test]
+async fn requests_display_name_change() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("/{}", PHONE_NUMBER_ID)))
+        .and(query_param("new_display_name", "Pasta Place"))
+        .respond_with(success())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    client.request_display_name_change("Pasta Place").await.unwrap();
+    assert!(client.request_display_name_change("  ").await.is_err());
+}
+
+#[tokio::test]
+async fn gets_phone_number_status() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{}", PHONE_NUMBER_ID)))
+        .and(query_param("fields", "id,verified_name,name_status,new_name_status,code_verification_status"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": PHONE_NUMBER_ID,
+            "verified_name": "Pasta Place",
+            "name_status": "APPROVED",
+            "new_name_status": "PENDING_REVIEW",
+            "code_verification_status": "EXPIRED",
+        })))
+        .mount(&server)
+        .await;
+
+    let status = client(&server).get_phone_number_status().await.unwrap();
+    assert_eq!(status.name_status, Some(NameStatus::Approved));
+    assert_eq!(status.new_name_status, Some(NameStatus::PendingReview));
+    assert_eq!(status.code_verification_status, Some(CodeVerificationStatus::Expired));
+}
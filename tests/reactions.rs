@@ -0,0 +1,127 @@
+//! Sending reactions and reading the reactions users send
+
+mod common;
+
+use serde_json::json;
+use whatsapp_cloud_sdk::error::WhatsAppError;
+use whatsapp_cloud_sdk::types::messages::SendReactionMessage;
+use whatsapp_cloud_sdk::types::webhook::ReactionEvent;
+use whatsapp_cloud_sdk::util::is_single_emoji;
+use whatsapp_cloud_sdk::{PhoneNumber, Validate};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{bodies, client, message, sent, PHONE_NUMBER_ID};
+
+fn messages_path() -> String {
+    format!("/{}/messages", PHONE_NUMBER_ID)
+}
+
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sent("wamid.OUT")))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn reaction(emoji: &str) -> SendReactionMessage {
+    SendReactionMessage::new(PhoneNumber::parse("15551234567").unwrap(), "wamid.IN", emoji)
+}
+
+
+#[test]
+fn single_emoji_are_recognized() {
+    for emoji in ["👍", "❤️", "👍🏽", "👩‍💻", "🇧🇷", "1️⃣", "©️", "🫶"] {
+        assert!(is_single_emoji(emoji), "{}", emoji);
+    }
+    for text in ["", "a", "ok", "1", "#", "é", "👍👍", "🇧", "🇧🇷🇵🇹", " ", "-"] {
+        assert!(!is_single_emoji(text), "{:?}", text);
+    }
+}
+
+#[test]
+fn reactions_must_be_one_emoji_or_empty() {
+    assert!(reaction("👍").violations().is_empty());
+    assert!(reaction("").violations().is_empty());
+    assert!(reaction("").is_removal());
+
+    for emoji in ["a", "👍👍", " "] {
+        let violations = reaction(emoji).violations();
+        assert_eq!(violations.len(), 1, "{:?}", emoji);
+        assert_eq!(violations[0].path, "emoji");
+    }
+}
+
+#[tokio::test]
+async fn react_and_unreact_send_the_reaction_payload() {
+    let server = server().await;
+    let client = client(&server);
+
+    client.react("+1 555 123 4567", "wamid.IN", "🎉").await.unwrap();
+    client.unreact("+1 555 123 4567", "wamid.IN").await.unwrap();
+
+    let sent = bodies(&server, &messages_path()).await;
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0]["to"], "15551234567");
+    assert_eq!(sent[0]["type"], "reaction");
+    assert_eq!(sent[0]["reaction"], json!({ "message_id": "wamid.IN", "emoji": "🎉" }));
+    assert_eq!(sent[1]["reaction"], json!({ "message_id": "wamid.IN", "emoji": "" }));
+}
+
+#[tokio::test]
+async fn invalid_reactions_are_not_sent() {
+    let server = server().await;
+    let client = client(&server);
+
+    let error = client.react("15551234567", "wamid.IN", "yes").await.unwrap_err();
+    assert!(matches!(error, WhatsAppError::InvalidMessage(_)));
+
+    assert!(client.react("not a number", "wamid.IN", "👍").await.is_err());
+    assert!(bodies(&server, &messages_path()).await.is_empty());
+}
+
+#[test]
+fn reaction_events_are_read_from_messages() {
+    let reacted = message(json!({
+        "from": "15551234567",
+        "id": "wamid.REACTION",
+        "timestamp": "1700000000",
+        "type": "reaction",
+        "reaction": { "message_id": "wamid.OUT", "emoji": "❤️" },
+    }));
+    let event = reacted.reaction_event().unwrap();
+    assert_eq!(
+        event,
+        ReactionEvent::Reacted {
+            from: "15551234567".to_string(),
+            message_id: "wamid.OUT".to_string(),
+            emoji: "❤️".to_string(),
+        }
+    );
+    assert_eq!(event.from(), "15551234567");
+    assert_eq!(event.message_id(), "wamid.OUT");
+    assert_eq!(event.emoji(), Some("❤️"));
+
+    let removed = message(json!({
+        "from": "15551234567",
+        "id": "wamid.REACTION",
+        "timestamp": "1700000000",
+        "type": "reaction",
+        "reaction": { "message_id": "wamid.OUT" },
+    }));
+    let event = removed.reaction_event().unwrap();
+    assert!(matches!(event, ReactionEvent::Removed { .. }));
+    assert_eq!(event.emoji(), None);
+
+    let text = message(json!({
+        "from": "15551234567",
+        "id": "wamid.TEXT",
+        "timestamp": "1700000000",
+        "type": "text",
+        "text": { "body": "Hi" },
+    }));
+    assert_eq!(text.reaction_event(), None);
+}
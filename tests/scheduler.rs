@@ -0,0 +1,379 @@
+//! Scheduled template messages, job stores and quiet hours
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use whatsapp_cloud_sdk::error::{WhatsAppError, WhatsAppResult};
+use whatsapp_cloud_sdk::scheduler::{InMemoryJobStore, JobStatus, QuietHours, ScheduledJob};
+use whatsapp_cloud_sdk::types::messages::SendTemplateMessage;
+use whatsapp_cloud_sdk::{JobStore, Scheduler};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{bodies, client, sent, PHONE_NUMBER_ID};
+
+fn messages_path() -> String {
+    format!("/{}/messages", PHONE_NUMBER_ID)
+}
+
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sent("wamid.OUT")))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn reminder(to: &str) -> SendTemplateMessage {
+    SendTemplateMessage {
+        to: to.to_string(),
+        template_name: "appointment_reminder".to_string(),
+        language_code: "en_US".to_string(),
+        components: None,
+        context: None,
+    }
+}
+
+fn job(id: &str, due_at: DateTime<Utc>) -> ScheduledJob {
+    ScheduledJob {
+        id: id.to_string(),
+        message: reminder("15551234567"),
+        due_at,
+        quiet_hours: None,
+        status: JobStatus::Scheduled,
+        attempts: 0,
+        last_error: None,
+        message_id: None,
+        created_at: Utc::now(),
+        claimed_at: None,
+    }
+}
+
+fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+}
+
+fn local(timezone: Tz, year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    timezone
+        .from_local_datetime(&NaiveDate::from_ymd_opt(year, month, day).unwrap().and_time(time(hour, minute)))
+        .earliest()
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+/// The contract every store must keep.
+fn check_store<S: JobStore>(store: &S) {
+    let now = Utc::now();
+    let later = store.insert(&job("later", now - chrono::Duration::minutes(1))).unwrap();
+    store.insert(&job("earlier", now - chrono::Duration::minutes(2))).unwrap();
+    store.insert(&job("future", now + chrono::Duration::hours(1))).unwrap();
+
+    let replayed = store.insert(&job("later", now + chrono::Duration::days(1))).unwrap();
+    assert_eq!(replayed.due_at, later.due_at);
+
+    let due: Vec<String> = store.due(now, 10).unwrap().into_iter().map(|job| job.id).collect();
+    assert_eq!(due, ["earlier", "later"]);
+    assert_eq!(store.next_due_at().unwrap().unwrap().timestamp_millis(), (now - chrono::Duration::minutes(2)).timestamp_millis());
+
+    let claimed_at = now - chrono::Duration::minutes(10);
+    assert!(store.claim("earlier", claimed_at).unwrap());
+    assert!(!store.claim("earlier", now).unwrap());
+    let claimed = store.get("earlier").unwrap().unwrap();
+    assert_eq!(claimed.status, JobStatus::Sending);
+    assert_eq!(claimed.claimed_at.unwrap().timestamp_millis(), claimed_at.timestamp_millis());
+
+    // Only claims older than the lease are taken back.
+    assert!(store.claim("later", now).unwrap());
+    assert_eq!(store.reset_interrupted(now - chrono::Duration::minutes(5)).unwrap(), 1);
+    assert_eq!(store.get("earlier").unwrap().unwrap().status, JobStatus::Scheduled);
+    assert_eq!(store.get("later").unwrap().unwrap().status, JobStatus::Sending);
+
+    assert!(store.cancel("future").unwrap());
+    assert!(!store.cancel("future").unwrap());
+    assert!(!store.cancel("later").unwrap());
+    assert!(!store.claim("future", now).unwrap());
+
+    let mut sent = store.get("later").unwrap().unwrap();
+    sent.status = JobStatus::Sent;
+    sent.attempts = 1;
+    sent.message_id = Some("wamid.1".to_string());
+    store.update(&sent).unwrap();
+    let stored = store.get("later").unwrap().unwrap();
+    assert_eq!(stored.status, JobStatus::Sent);
+    assert_eq!(stored.message_id.as_deref(), Some("wamid.1"));
+
+    let quiet_hours = QuietHours::new(time(21, 0), time(8, 0), chrono_tz::Europe::Berlin);
+    assert_eq!(store.quiet_hours("15551234567").unwrap(), None);
+    store.set_quiet_hours("15551234567", Some(quiet_hours)).unwrap();
+    assert_eq!(store.quiet_hours("15551234567").unwrap(), Some(quiet_hours));
+    store.set_quiet_hours("15551234567", None).unwrap();
+    assert_eq!(store.quiet_hours("15551234567").unwrap(), None);
+}
+
+
+#[test]
+fn quiet_hours_within_a_day() {
+    let quiet_hours = QuietHours::new(time(12, 0), time(14, 0), chrono_tz::UTC);
+
+    assert_eq!(quiet_hours.next_allowed(utc(2024, 6, 1, 11, 59)), utc(2024, 6, 1, 11, 59));
+    assert_eq!(quiet_hours.next_allowed(utc(2024, 6, 1, 12, 0)), utc(2024, 6, 1, 14, 0));
+    assert_eq!(quiet_hours.next_allowed(utc(2024, 6, 1, 14, 0)), utc(2024, 6, 1, 14, 0));
+}
+
+#[test]
+fn quiet_hours_across_midnight() {
+    let berlin = chrono_tz::Europe::Berlin;
+    let quiet_hours = QuietHours::new(time(21, 0), time(8, 0), berlin);
+
+    assert!(!quiet_hours.contains(local(berlin, 2024, 6, 1, 20, 59)));
+    assert_eq!(quiet_hours.next_allowed(local(berlin, 2024, 6, 1, 23, 0)), local(berlin, 2024, 6, 2, 8, 0));
+    assert_eq!(quiet_hours.next_allowed(local(berlin, 2024, 6, 2, 2, 0)), local(berlin, 2024, 6, 2, 8, 0));
+    assert_eq!(quiet_hours.next_allowed(local(berlin, 2024, 6, 2, 12, 0)), local(berlin, 2024, 6, 2, 12, 0));
+}
+
+#[test]
+fn quiet_hours_ending_in_a_skipped_hour_end_after_the_gap() {
+    // On 2024-03-10 New York clocks jump from 02:00 to 03:00.
+    let quiet_hours = QuietHours::new(time(22, 0), time(2, 30), chrono_tz::America::New_York);
+
+    assert_eq!(quiet_hours.next_allowed(utc(2024, 3, 10, 4, 0)), utc(2024, 3, 10, 7, 0));
+    assert_eq!(quiet_hours.next_allowed(utc(2024, 3, 10, 6, 30)), utc(2024, 3, 10, 7, 0));
+}
+
+#[test]
+fn quiet_hours_ending_in_a_repeated_hour_never_end_in_the_past() {
+    // On 2024-11-03 New York clocks go back from 02:00 EDT to 01:00 EST.
+    let quiet_hours = QuietHours::new(time(0, 0), time(1, 30), chrono_tz::America::New_York);
+
+    // 00:30 EDT ends at the first 01:30, EDT.
+    assert_eq!(quiet_hours.next_allowed(utc(2024, 11, 3, 4, 30)), utc(2024, 11, 3, 5, 30));
+    // 01:15 EST, after the first 01:30 passed, ends at the second one.
+    assert_eq!(quiet_hours.next_allowed(utc(2024, 11, 3, 6, 15)), utc(2024, 11, 3, 6, 30));
+}
+
+#[test]
+fn in_memory_store_keeps_the_store_contract() {
+    check_store(&InMemoryJobStore::new());
+}
+
+#[tokio::test]
+async fn jobs_are_moved_out_of_stored_quiet_hours() {
+    let server = server().await;
+    let store = Arc::new(InMemoryJobStore::new());
+    let scheduler = Scheduler::new(client(&server), Arc::clone(&store));
+    let quiet_hours = QuietHours::new(time(21, 0), time(8, 0), chrono_tz::UTC);
+
+    scheduler.set_quiet_hours("+1 555 123 4567", quiet_hours).unwrap();
+    assert_eq!(store.quiet_hours("15551234567").unwrap(), Some(quiet_hours));
+
+    let due_at = Utc::now() + chrono::Duration::days(1);
+    let night = due_at.date_naive().and_time(time(23, 0)).and_utc();
+    let job = scheduler.schedule("reminder-1", reminder("15551234567"), night).unwrap();
+    assert_eq!(job.due_at, (night.date_naive() + chrono::Days::new(1)).and_time(time(8, 0)).and_utc());
+    assert_eq!(job.quiet_hours, Some(quiet_hours));
+
+    scheduler.clear_quiet_hours("15551234567").unwrap();
+    let job = scheduler.schedule("reminder-2", reminder("15551234567"), night).unwrap();
+    assert_eq!(job.due_at, night);
+}
+
+#[tokio::test]
+async fn due_jobs_are_sent_once() {
+    let server = server().await;
+    let store = Arc::new(InMemoryJobStore::new());
+    let scheduler = Scheduler::new(client(&server), Arc::clone(&store));
+
+    scheduler.schedule("reminder-1", reminder("15551234567"), Utc::now()).unwrap();
+    scheduler.schedule("reminder-2", reminder("15551234567"), Utc::now() + chrono::Duration::hours(1)).unwrap();
+    scheduler.fire_due().await.unwrap();
+    scheduler.fire_due().await.unwrap();
+
+    let job = scheduler.job("reminder-1").unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Sent);
+    assert_eq!(job.message_id.as_deref(), Some("wamid.OUT"));
+    assert_eq!(scheduler.job("reminder-2").unwrap().unwrap().status, JobStatus::Scheduled);
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 1);
+}
+
+#[tokio::test]
+async fn only_jobs_past_their_lease_are_sent_again() {
+    let server = server().await;
+    let store = Arc::new(InMemoryJobStore::new());
+    let scheduler = Scheduler::new(client(&server), Arc::clone(&store)).lease_timeout(Duration::from_secs(60));
+
+    // One job is being sent by another scheduler, the other was left by one that stopped.
+    store.insert(&job("in-flight", Utc::now())).unwrap();
+    store.insert(&job("interrupted", Utc::now())).unwrap();
+    assert!(store.claim("in-flight", Utc::now()).unwrap());
+    assert!(store.claim("interrupted", Utc::now() - chrono::Duration::minutes(2)).unwrap());
+
+    scheduler.fire_due().await.unwrap();
+
+    assert_eq!(store.get("in-flight").unwrap().unwrap().status, JobStatus::Sending);
+    assert_eq!(store.get("interrupted").unwrap().unwrap().status, JobStatus::Sent);
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 1);
+}
+
+#[tokio::test]
+async fn cancelled_jobs_are_not_sent() {
+    let server = server().await;
+    let scheduler = Scheduler::new(client(&server), Arc::new(InMemoryJobStore::new()));
+
+    scheduler.schedule("reminder-1", reminder("15551234567"), Utc::now()).unwrap();
+    assert!(scheduler.cancel("reminder-1").unwrap());
+    scheduler.fire_due().await.unwrap();
+
+    assert_eq!(scheduler.job("reminder-1").unwrap().unwrap().status, JobStatus::Cancelled);
+    assert!(bodies(&server, &messages_path()).await.is_empty());
+}
+
+#[tokio::test]
+async fn sends_that_may_have_gone_through_are_not_rescheduled() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html>gateway</html>"))
+        .mount(&server)
+        .await;
+    let scheduler = Scheduler::new(client(&server), Arc::new(InMemoryJobStore::new()));
+
+    scheduler.schedule("reminder-1", reminder("15551234567"), Utc::now()).unwrap();
+    scheduler.fire_due().await.unwrap();
+    scheduler.fire_due().await.unwrap();
+
+    let job = scheduler.job("reminder-1").unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Unconfirmed);
+    assert!(job.last_error.is_some());
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 1);
+}
+
+/// An in-memory store whose first read of due jobs fails.
+struct FlakyStore {
+    store: InMemoryJobStore,
+    failed: std::sync::atomic::AtomicBool,
+}
+
+impl JobStore for FlakyStore {
+    fn insert(&self, job: &ScheduledJob) -> WhatsAppResult<ScheduledJob> {
+        self.store.insert(job)
+    }
+
+    fn get(&self, id: &str) -> WhatsAppResult<Option<ScheduledJob>> {
+        self.store.get(id)
+    }
+
+    fn due(&self, until: DateTime<Utc>, limit: usize) -> WhatsAppResult<Vec<ScheduledJob>> {
+        if !self.failed.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            return Err(WhatsAppError::StorageError("store is down".to_string()));
+        }
+        self.store.due(until, limit)
+    }
+
+    fn next_due_at(&self) -> WhatsAppResult<Option<DateTime<Utc>>> {
+        self.store.next_due_at()
+    }
+
+    fn claim(&self, id: &str, now: DateTime<Utc>) -> WhatsAppResult<bool> {
+        self.store.claim(id, now)
+    }
+
+    fn update(&self, job: &ScheduledJob) -> WhatsAppResult<()> {
+        self.store.update(job)
+    }
+
+    fn cancel(&self, id: &str) -> WhatsAppResult<bool> {
+        self.store.cancel(id)
+    }
+
+    fn reset_interrupted(&self, claimed_before: DateTime<Utc>) -> WhatsAppResult<usize> {
+        self.store.reset_interrupted(claimed_before)
+    }
+
+    fn quiet_hours(&self, wa_id: &str) -> WhatsAppResult<Option<QuietHours>> {
+        self.store.quiet_hours(wa_id)
+    }
+
+    fn set_quiet_hours(&self, wa_id: &str, quiet_hours: Option<QuietHours>) -> WhatsAppResult<()> {
+        self.store.set_quiet_hours(wa_id, quiet_hours)
+    }
+}
+
+#[tokio::test]
+async fn running_schedulers_outlast_storage_errors() {
+    let server = server().await;
+    let store = Arc::new(FlakyStore { store: InMemoryJobStore::new(), failed: Default::default() });
+    let scheduler = Arc::new(Scheduler::new(client(&server), Arc::clone(&store)));
+    scheduler.schedule("reminder-1", reminder("15551234567"), Utc::now()).unwrap();
+
+    let task = scheduler.start();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while scheduler.job("reminder-1").unwrap().unwrap().status != JobStatus::Sent {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    assert!(!task.is_finished());
+    task.abort();
+    assert_eq!(bodies(&server, &messages_path()).await.len(), 1);
+}
+
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use whatsapp_cloud_sdk::scheduler::SqliteJobStore;
+
+    #[test]
+    fn sqlite_store_keeps_the_store_contract() {
+        check_store(&SqliteJobStore::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn jobs_and_quiet_hours_survive_reopening() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("jobs.db");
+        let quiet_hours = QuietHours::new(time(22, 0), time(7, 0), chrono_tz::Asia::Kolkata);
+        let claimed_at = Utc::now();
+
+        let store = SqliteJobStore::open(&path).unwrap();
+        let mut scheduled = job("reminder-1", Utc::now());
+        scheduled.quiet_hours = Some(quiet_hours);
+        store.insert(&scheduled).unwrap();
+        store.claim("reminder-1", claimed_at).unwrap();
+        store.set_quiet_hours("15551234567", Some(quiet_hours)).unwrap();
+        drop(store);
+
+        let store = SqliteJobStore::open(&path).unwrap();
+        let job = store.get("reminder-1").unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Sending);
+        assert_eq!(job.quiet_hours, Some(quiet_hours));
+        assert_eq!(job.claimed_at.unwrap().timestamp_millis(), claimed_at.timestamp_millis());
+        assert_eq!(job.message.template_name, "appointment_reminder");
+        assert_eq!(store.quiet_hours("15551234567").unwrap(), Some(quiet_hours));
+    }
+
+    #[tokio::test]
+    async fn scheduler_sends_from_sqlite() {
+        let server = server().await;
+        let scheduler = Scheduler::new(client(&server), Arc::new(SqliteJobStore::open_in_memory().unwrap()));
+
+        scheduler.schedule("reminder-1", reminder("15551234567"), Utc::now()).unwrap();
+        scheduler.fire_due().await.unwrap();
+
+        let job = scheduler.job("reminder-1").unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Sent);
+        assert_eq!(job.attempts, 1);
+    }
+}
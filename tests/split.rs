@@ -0,0 +1,160 @@
+//! Splitting long text into message-sized parts
+
+mod common;
+
+use unicode_segmentation::UnicodeSegmentation;
+use whatsapp_cloud_sdk::error::WhatsAppError;
+use whatsapp_cloud_sdk::types::messages::SendTextMessage;
+use whatsapp_cloud_sdk::util::split_text;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{api_error, client, sent, PHONE_NUMBER_ID};
+
+fn assert_within(parts: &[String], max_chars: usize) {
+    for part in parts {
+        assert!(part.chars().count() <= max_chars, "{:?} is longer than {}", part, max_chars);
+    }
+}
+
+#[test]
+fn short_text_is_one_part() {
+    assert_eq!(split_text("  hello  ", 20), ["hello"]);
+    assert!(split_text("   ", 20).is_empty());
+}
+
+/// Boundaries in the later half of the allowed length are preferred, so parts
+/// are not cut short by an early line break.
+#[test]
+fn prefers_paragraphs_then_lines_then_sentences_then_words() {
+    let text = "First paragraph here.\n\nSecond one.";
+    assert_eq!(split_text(text, 25), ["First paragraph here.", "Second one."]);
+
+    let text = "first line here\nsecond line";
+    assert_eq!(split_text(text, 20), ["first line here", "second line"]);
+
+    let text = "One sentence. Another sentence";
+    assert_eq!(split_text(text, 20), ["One sentence.", "Another sentence"]);
+
+    let text = "alpha beta gamma delta";
+    assert_eq!(split_text(text, 12), ["alpha beta", "gamma delta"]);
+}
+
+#[test]
+fn parts_never_exceed_the_limit() {
+    let text = "word ".repeat(500) + &"x".repeat(300);
+    for max_chars in [8, 13, 50, 4096] {
+        let parts = split_text(&text, max_chars);
+        assert_within(&parts, max_chars);
+        let joined: String = parts.concat().split_whitespace().collect();
+        let original: String = text.split_whitespace().collect();
+        assert_eq!(joined, original);
+    }
+}
+
+#[test]
+fn urls_are_kept_whole() {
+    let url = "https://example.com/a/long/path?with=query";
+    let text = format!("see {} for details", url);
+    let parts = split_text(&text, 45);
+    assert_within(&parts, 45);
+    assert!(parts.iter().any(|part| part.contains(url)), "{:?}", parts);
+}
+
+#[test]
+fn urls_longer_than_the_limit_are_cut() {
+    let url = format!("https://example.com/{}", "a".repeat(60));
+    let parts = split_text(&url, 30);
+    assert_within(&parts, 30);
+    assert_eq!(parts.concat(), url);
+}
+
+#[test]
+fn spans_are_closed_and_reopened() {
+    let text = "*bold words that go on for quite a while*";
+    let parts = split_text(text, 24);
+    assert_within(&parts, 24);
+    assert!(parts.len() > 1);
+    for part in &parts {
+        assert!(part.starts_with('*') && part.ends_with('*'), "{:?}", part);
+    }
+}
+
+#[test]
+fn code_fences_are_closed_and_reopened() {
+    let code = (1..=8).map(|i| format!("let x{} = {};", i, i)).collect::<Vec<_>>().join("\n");
+    let text = format!("```\n{}\n```", code);
+    let parts = split_text(&text, 50);
+    assert_within(&parts, 50);
+    assert!(parts.len() > 1);
+    for part in &parts {
+        assert!(part.starts_with("```\n"), "{:?}", part);
+        assert!(part.ends_with("\n```"), "{:?}", part);
+    }
+}
+
+#[test]
+fn grapheme_clusters_are_never_cut() {
+    let family = "👨‍👩‍👧‍👦";
+    let flag = "🇳🇱";
+    let text = format!("{}{}", family.repeat(10), flag.repeat(10));
+    let parts = split_text(&text, 16);
+    assert_within(&parts, 16);
+    for part in &parts {
+        assert!(part.graphemes(true).all(|g| g == family || g == flag), "{:?}", part);
+    }
+    assert_eq!(parts.concat(), text);
+}
+
+#[test]
+fn long_text_splits_in_linear_time() {
+    let text = "lorem ipsum dolor sit amet ".repeat(40_000);
+    let started = std::time::Instant::now();
+    let parts = split_text(&text, 4096);
+    assert_within(&parts, 4096);
+    assert!(parts.len() >= text.len() / 4096);
+    assert!(started.elapsed() < std::time::Duration::from_secs(10), "took {:?}", started.elapsed());
+}
+
+#[test]
+fn early_boundaries_do_not_make_short_parts() {
+    let text = "a\nline that keeps going and going";
+    let parts = split_text(text, 20);
+    assert_within(&parts, 20);
+    assert!(parts[0].chars().count() > 10, "{:?}", parts);
+}
+
+#[tokio::test]
+async fn a_failed_part_reports_the_parts_already_sent() {
+    let server = MockServer::start().await;
+    let messages = format!("/{}/messages", PHONE_NUMBER_ID);
+    Mock::given(method("POST"))
+        .and(path(messages.as_str()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sent("wamid.FIRST")))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(messages.as_str()))
+        .respond_with(ResponseTemplate::new(400).set_body_json(api_error(131026, "Message undeliverable")))
+        .mount(&server)
+        .await;
+    let message = SendTextMessage {
+        to: "15551234567".to_string(),
+        text: "word ".repeat(2000),
+        preview_url: None,
+        context: None,
+    };
+
+    let error = client(&server).send_long_text(message).await.unwrap_err();
+
+    match error {
+        WhatsAppError::PartiallySent { message_ids, error } => {
+            assert_eq!(message_ids, ["wamid.FIRST"]);
+            assert_eq!(error.code(), Some(131026));
+            assert!(!WhatsAppError::PartiallySent { message_ids, error }.is_retryable());
+        }
+        other => panic!("expected a partial send, got {:?}", other),
+    }
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
@@ -0,0 +1,205 @@
+//! Template cache and the template webhook fields that keep it current
+
+mod common;
+
+use serde_json::json;
+use whatsapp_cloud_sdk::template_cache::TemplateCache;
+use whatsapp_cloud_sdk::types::templates::*;
+use whatsapp_cloud_sdk::types::webhook::{WebhookChangeValue, WebhookEvent};
+use whatsapp_cloud_sdk::WhatsAppClient;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::event;
+
+fn template(id: &str, name: &str, language: &str, status: &str) -> Template {
+    serde_json::from_value(json!({
+        "id": id,
+        "name": name,
+        "language": language,
+        "status": status,
+        "category": "MARKETING",
+        "components": [],
+    }))
+    .unwrap()
+}
+
+fn status_update(event_name: &str, reason: &str) -> WebhookEvent {
+    event(
+        "message_template_status_update",
+        json!({
+            "event": event_name,
+            "message_template_id": 41,
+            "message_template_name": "order_update",
+            "message_template_language": "en_US",
+            "reason": reason,
+        }),
+    )
+}
+
+#[test]
+fn parses_status_update() {
+    let event = status_update("PAUSED", "NONE");
+    let change = event.changes().next().unwrap();
+    match &change.value {
+        WebhookChangeValue::MessageTemplateStatusUpdate(update) => {
+            assert_eq!(update.event, TemplateStatus::Paused);
+            assert_eq!(update.message_template_id, 41);
+            assert_eq!(update.message_template_name, "order_update");
+        }
+        other => panic!("unexpected change {:?}", other),
+    }
+}
+
+#[test]
+fn parses_quality_update() {
+    let event = event(
+        "message_template_quality_update",
+        json!({
+            "previous_quality_score": "GREEN",
+            "new_quality_score": "YELLOW",
+            "message_template_id": 41,
+            "message_template_name": "order_update",
+            "message_template_language": "en_US",
+        }),
+    );
+    let change = event.changes().next().unwrap();
+    match &change.value {
+        WebhookChangeValue::MessageTemplateQualityUpdate(update) => {
+            assert_eq!(update.previous_quality_score, TemplateQualityScore::Green);
+            assert_eq!(update.new_quality_score, TemplateQualityScore::Yellow);
+        }
+        other => panic!("unexpected change {:?}", other),
+    }
+}
+
+#[test]
+fn parses_category_update() {
+    let event = event(
+        "template_category_update",
+        json!({
+            "message_template_id": 41,
+            "message_template_name": "order_update",
+            "message_template_language": "en_US",
+            "previous_category": "UTILITY",
+            "new_category": "MARKETING",
+        }),
+    );
+    let change = event.changes().next().unwrap();
+    match &change.value {
+        WebhookChangeValue::TemplateCategoryUpdate(update) => {
+            assert_eq!(update.previous_category, TemplateCategory::Utility);
+            assert_eq!(update.new_category, TemplateCategory::Marketing);
+            assert_eq!(update.correct_category, None);
+        }
+        other => panic!("unexpected change {:?}", other),
+    }
+}
+
+#[test]
+fn unknown_fields_are_kept_raw() {
+    let event = event("account_alerts", json!({ "alert": 1 }));
+    assert!(matches!(event.changes().next().unwrap().value, WebhookChangeValue::Other(_)));
+}
+
+#[test]
+fn webhooks_update_cached_templates() {
+    let cache = TemplateCache::new();
+    cache.insert(&template("41", "order_update", "en_US", "APPROVED"));
+    assert!(cache.ensure_usable("order_update", "en_US").is_ok());
+
+    cache.handle_webhook_event(&status_update("PAUSED", "NONE"));
+    let cached = cache.get("order_update", "en_US").unwrap();
+    assert_eq!(cached.status, TemplateStatus::Paused);
+    assert_eq!(cached.reason, None);
+    assert!(cache.ensure_usable("order_update", "en_US").is_err());
+
+    cache.handle_webhook_event(&status_update("REJECTED", "INCORRECT_CATEGORY"));
+    let error = cache.ensure_usable("order_update", "en_US").unwrap_err().to_string();
+    assert!(error.contains("INCORRECT_CATEGORY"), "{}", error);
+
+    cache.handle_webhook_event(&event(
+        "message_template_quality_update",
+        json!({
+            "previous_quality_score": "GREEN",
+            "new_quality_score": "RED",
+            "message_template_id": 41,
+            "message_template_name": "order_update",
+            "message_template_language": "en_US",
+        }),
+    ));
+    assert_eq!(cache.get("order_update", "en_US").unwrap().quality_score, Some(TemplateQualityScore::Red));
+}
+
+#[test]
+fn webhooks_insert_unknown_templates() {
+    let cache = TemplateCache::new();
+    cache.handle_webhook_event(&status_update("APPROVED", "NONE"));
+
+    let cached = cache.get("order_update", "en_US").unwrap();
+    assert_eq!(cached.id, "41");
+    assert_eq!(cached.status, TemplateStatus::Approved);
+    assert_eq!(cached.category, None);
+}
+
+#[test]
+fn uncached_templates_are_usable() {
+    assert!(TemplateCache::new().ensure_usable("welcome", "en_US").is_ok());
+}
+
+#[test]
+fn removes_templates() {
+    let cache = TemplateCache::new();
+    cache.insert_all(&[
+        template("1", "welcome", "en_US", "APPROVED"),
+        template("2", "welcome", "es", "APPROVED"),
+        template("3", "goodbye", "en_US", "APPROVED"),
+    ]);
+
+    assert_eq!(cache.remove_by_id("2").unwrap().language, "es");
+    assert!(cache.get("welcome", "en_US").is_some());
+
+    cache.remove_all("welcome");
+    assert!(cache.get("welcome", "en_US").is_none());
+    assert!(cache.get("goodbye", "en_US").is_some());
+}
+
+async fn delete(hsm_id: Option<&str>) -> WhatsAppClient {
+    let server = MockServer::start().await;
+    Mock::given(method("DELETE"))
+        .and(path("/WABA_ID/message_templates"))
+        .and(query_param("name", "welcome"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "success": true })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = common::client(&server);
+    client.template_cache().insert_all(&[
+        template("1", "welcome", "en_US", "APPROVED"),
+        template("2", "welcome", "es", "APPROVED"),
+    ]);
+
+    client
+        .delete_template(DeleteTemplate {
+            name: "welcome".to_string(),
+            hsm_id: hsm_id.map(str::to_string),
+        })
+        .await
+        .unwrap();
+    client
+}
+
+#[tokio::test]
+async fn deleting_one_language_keeps_the_others_cached() {
+    let client = delete(Some("2")).await;
+    assert!(client.template_cache().get("welcome", "en_US").is_some());
+    assert!(client.template_cache().get("welcome", "es").is_none());
+}
+
+#[tokio::test]
+async fn deleting_by_name_removes_every_language() {
+    let client = delete(None).await;
+    assert!(client.template_cache().get("welcome", "en_US").is_none());
+    assert!(client.template_cache().get("welcome", "es").is_none());
+}
@@ -0,0 +1,119 @@
+//! Typing indicators kept alive by `TypingGuard`
+
+mod common;
+
+use std::time::Duration;
+
+use serde_json::json;
+use whatsapp_cloud_sdk::typing::{TypingGuard, TYPING_REFRESH_INTERVAL};
+use whatsapp_cloud_sdk::types::messages::SendTextMessage;
+use whatsapp_cloud_sdk::WhatsAppClient;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use common::{bodies, client, message, sent, text_message, PHONE_NUMBER_ID};
+
+const USER: &str = "15551234567";
+
+fn messages_path() -> String {
+    format!("/{}/messages", PHONE_NUMBER_ID)
+}
+
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .and(body_partial_json(json!({ "status": "read" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "success": true })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(messages_path()))
+        .and(body_partial_json(json!({ "type": "text" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sent("wamid.OUT")))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn read_receipts(server: &MockServer) -> usize {
+    bodies(server, &messages_path())
+        .await
+        .iter()
+        .filter(|body| body["status"] == "read")
+        .count()
+}
+
+/// Lets `intervals` refresh periods pass on a paused clock, giving the
+/// refreshes due after each a moment of real time to reach the server.
+async fn elapse(server: &MockServer, intervals: u32) -> usize {
+    for _ in 0..intervals {
+        tokio::time::pause();
+        tokio::time::sleep(TYPING_REFRESH_INTERVAL).await;
+        tokio::time::resume();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    read_receipts(server).await
+}
+
+async fn start(client: &WhatsAppClient) -> TypingGuard {
+    client
+        .start_typing(&message(text_message(USER, "wamid.IN", "Hi")))
+        .await
+        .unwrap()
+}
+
+
+#[tokio::test]
+async fn guard_refreshes_the_indicator() {
+    let server = server().await;
+    let client = client(&server);
+
+    let guard = start(&client).await;
+    assert_eq!(guard.to(), USER);
+
+    assert!(elapse(&server, 3).await >= 2);
+    drop(guard);
+}
+
+#[tokio::test]
+async fn dropping_the_guard_stops_refreshing() {
+    let server = server().await;
+    let client = client(&server);
+
+    drop(start(&client).await);
+
+    assert_eq!(elapse(&server, 3).await, 1);
+}
+
+#[tokio::test]
+async fn sending_to_the_user_stops_refreshing() {
+    let server = server().await;
+    let client = client(&server);
+
+    let _guard = start(&client).await;
+    client
+        .send_text_message(SendTextMessage {
+            to: "+1 555 123 4567".to_string(),
+            text: "Here you go".to_string(),
+            preview_url: None,
+            context: None,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(elapse(&server, 3).await, 1);
+}
+
+#[tokio::test]
+async fn dropping_a_replaced_guard_keeps_the_new_one() {
+    let server = server().await;
+    let client = client(&server);
+
+    let first = start(&client).await;
+    let second = start(&client).await;
+    drop(first);
+
+    assert!(elapse(&server, 3).await >= 3);
+    drop(second);
+}
@@ -0,0 +1,277 @@
+//! Client-side validation of outgoing messages
+
+use serde_json::{json, Value};
+use whatsapp_cloud_sdk::error::WhatsAppError;
+use whatsapp_cloud_sdk::types::messages::*;
+use whatsapp_cloud_sdk::Validate;
+
+const TO: &str = "15551234567";
+
+fn paths(message: &impl Validate) -> Vec<String> {
+    message.violations().into_iter().map(|violation| violation.path).collect()
+}
+
+fn text(to: &str, text: &str) -> SendTextMessage {
+    SendTextMessage {
+        to: to.to_string(),
+        text: text.to_string(),
+        preview_url: None,
+        context: None,
+    }
+}
+
+fn media(media_type: MediaType) -> SendMediaMessage {
+    SendMediaMessage {
+        to: TO.to_string(),
+        media_type,
+        media_id: Some("MEDIA".to_string()),
+        media_url: None,
+        caption: None,
+        filename: None,
+        context: None,
+    }
+}
+
+fn interactive(value: Value) -> SendInteractiveMessage {
+    SendInteractiveMessage {
+        to: TO.to_string(),
+        interactive: serde_json::from_value(value).unwrap(),
+        context: None,
+    }
+}
+
+fn buttons(buttons: Value) -> SendInteractiveMessage {
+    interactive(json!({
+        "type": "button",
+        "body": { "text": "Pick one" },
+        "action": { "buttons": buttons },
+    }))
+}
+
+fn reply(id: &str, title: &str) -> Value {
+    json!({ "type": "reply", "reply": { "id": id, "title": title } })
+}
+
+fn list(sections: Value) -> SendInteractiveMessage {
+    interactive(json!({
+        "type": "list",
+        "body": { "text": "Pick one" },
+        "action": { "button": "Menu", "sections": sections },
+    }))
+}
+
+fn row(id: &str) -> Value {
+    json!({ "id": id, "title": "Row" })
+}
+
+#[test]
+fn valid_messages_pass() {
+    text(TO, "Hello").validate().unwrap();
+    media(MediaType::Image).validate().unwrap();
+    buttons(json!([reply("yes", "Yes"), reply("no", "No")])).validate().unwrap();
+    list(json!([{ "rows": [row("a"), row("b")] }])).validate().unwrap();
+}
+
+#[test]
+fn all_violations_are_reported_together() {
+    let message = text("12", " ");
+    assert_eq!(paths(&message), ["to", "text"]);
+
+    match message.validate() {
+        Err(WhatsAppError::InvalidMessage(violations)) => assert_eq!(violations.len(), 2),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn text_length_is_counted_in_characters() {
+    assert!(text(TO, &"é".repeat(4096)).validate().is_ok());
+    let violations = text(TO, &"é".repeat(4097)).violations();
+    assert_eq!(violations[0].path, "text");
+    assert_eq!(violations[0].message, "must be at most 4096 characters, got 4097");
+}
+
+#[test]
+fn media_checks_source_caption_and_filename() {
+    let both = SendMediaMessage {
+        media_url: Some("https://example.com/a.jpg".to_string()),
+        ..media(MediaType::Image)
+    };
+    let neither = SendMediaMessage {
+        media_id: None,
+        ..media(MediaType::Image)
+    };
+    assert_eq!(paths(&both), ["media_id"]);
+    assert_eq!(paths(&neither), ["media_id"]);
+
+    let audio_caption = SendMediaMessage {
+        caption: Some("listen".to_string()),
+        ..media(MediaType::Audio)
+    };
+    let long_caption = SendMediaMessage {
+        caption: Some("x".repeat(1025)),
+        ..media(MediaType::Video)
+    };
+    assert_eq!(paths(&audio_caption), ["caption"]);
+    assert_eq!(paths(&long_caption), ["caption"]);
+
+    let image_filename = SendMediaMessage {
+        filename: Some("a.jpg".to_string()),
+        ..media(MediaType::Image)
+    };
+    let document_filename = SendMediaMessage {
+        filename: Some("a.pdf".to_string()),
+        ..media(MediaType::Document)
+    };
+    assert_eq!(paths(&image_filename), ["filename"]);
+    assert!(paths(&document_filename).is_empty());
+}
+
+#[test]
+fn location_coordinates_must_be_in_range() {
+    let location = SendLocationMessage {
+        to: TO.to_string(),
+        latitude: 91.0,
+        longitude: -181.0,
+        name: None,
+        address: None,
+        context: None,
+    };
+    assert_eq!(paths(&location), ["latitude", "longitude"]);
+}
+
+#[test]
+fn templates_need_a_name_and_language() {
+    let template = SendTemplateMessage {
+        to: TO.to_string(),
+        template_name: String::new(),
+        language_code: "x".repeat(17),
+        components: None,
+        context: None,
+    };
+    assert_eq!(paths(&template), ["template_name", "language_code"]);
+}
+
+#[test]
+fn reply_buttons_are_checked() {
+    assert_eq!(paths(&buttons(json!([]))), ["interactive.action.buttons"]);
+    assert_eq!(
+        paths(&buttons(json!([reply("a", "A"), reply("b", "B"), reply("c", "C"), reply("d", "D")]))),
+        ["interactive.action.buttons"]
+    );
+    assert_eq!(
+        paths(&buttons(json!([reply("a", "A"), reply("a", &"B".repeat(21))]))),
+        ["interactive.action.buttons[1].reply.title", "interactive.action.buttons[1].reply.id"]
+    );
+    assert_eq!(
+        paths(&buttons(json!([{ "type": "url", "url": "https://example.com", "title": "Open" }]))),
+        ["interactive.action.buttons[0].type", "interactive.action.buttons[0].reply"]
+    );
+}
+
+#[test]
+fn list_sections_are_checked() {
+    assert_eq!(paths(&list(json!([]))), ["interactive.action.sections"]);
+
+    let eleven_rows: Vec<Value> = (0..11).map(|i| row(&i.to_string())).collect();
+    assert_eq!(
+        paths(&list(json!([{ "title": "All", "rows": eleven_rows }]))),
+        ["interactive.action.sections"]
+    );
+
+    assert_eq!(
+        paths(&list(json!([{ "rows": [row("a")] }, { "title": "Two", "rows": [] }]))),
+        ["interactive.action.sections[0].title", "interactive.action.sections[1].rows"]
+    );
+
+    let bad_row = json!({ "id": "a", "title": "", "description": "d".repeat(73) });
+    assert_eq!(
+        paths(&list(json!([{ "rows": [bad_row, row("a")] }]))),
+        [
+            "interactive.action.sections[0].rows[0].title",
+            "interactive.action.sections[0].rows[0].description",
+            "interactive.action.sections[0].rows[1].id",
+        ]
+    );
+}
+
+#[test]
+fn interactive_body_header_footer_and_action_are_checked() {
+    let message = interactive(json!({
+        "type": "list",
+        "body": { "text": "" },
+        "header": { "type": "text", "text": "h".repeat(61) },
+        "footer": { "text": "f".repeat(61) },
+        "action": { "buttons": [reply("a", "A")] },
+    }));
+    assert_eq!(
+        paths(&message),
+        ["interactive.body.text", "interactive.header.text", "interactive.footer.text", "interactive.action"]
+    );
+
+    let image = interactive(json!({
+        "type": "button",
+        "body": { "text": "Body" },
+        "header": { "type": "image", "image": { "link": "" } },
+        "action": { "buttons": [reply("a", "A")] },
+    }));
+    assert_eq!(paths(&image), ["interactive.header.link"]);
+}
+
+#[test]
+fn contacts_are_checked() {
+    let contact: Contact = serde_json::from_value(json!({
+        "name": { "formatted_name": "", "first_name": "Ada" },
+        "phones": [{ "phone": "", "type": "CELL" }],
+        "emails": [{ "email": "ada.example.com", "type": "WORK" }],
+    }))
+    .unwrap();
+    let message = SendContactMessage {
+        to: TO.to_string(),
+        contacts: vec![contact],
+        context: None,
+    };
+    assert_eq!(
+        paths(&message),
+        ["contacts[0].name.formatted_name", "contacts[0].phones[0].phone", "contacts[0].emails[0].email"]
+    );
+
+    let empty = SendContactMessage {
+        contacts: Vec::new(),
+        ..message
+    };
+    assert_eq!(paths(&empty), ["contacts"]);
+}
+
+#[test]
+fn reactions_need_a_message_id() {
+    let reaction = SendReactionMessage {
+        to: TO.to_string(),
+        message_id: " ".to_string(),
+        emoji: String::new(),
+    };
+    assert_eq!(paths(&reaction), ["message_id"]);
+}
+
+#[test]
+fn outbound_messages_validate_their_content() {
+    let message = OutboundMessage::Text(text(TO, ""));
+    assert_eq!(paths(&message), ["text"]);
+}
+
+#[test]
+fn data_exchange_flows_need_a_token() {
+    let flow = Interactive::flow("FLOW", "Open").data_exchange().body("Start");
+    let message = |interactive: Interactive| SendInteractiveMessage {
+        to: TO.to_string(),
+        interactive,
+        context: None,
+    };
+
+    assert_eq!(
+        paths(&message(flow.clone().build())),
+        ["interactive.action.parameters.flow_token"]
+    );
+    assert!(paths(&message(flow.flow_token("booking-42").build())).is_empty());
+    assert!(paths(&message(Interactive::flow("FLOW", "Open").body("Start").build())).is_empty());
+}
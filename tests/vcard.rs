@@ -0,0 +1,144 @@
+//! vCard export and import of contact cards
+
+use serde_json::{json, Value};
+use whatsapp_cloud_sdk::types::messages::Contact;
+use whatsapp_cloud_sdk::util::{parse_vcards, to_vcards, VCardVersion};
+
+fn contact(value: Value) -> Contact {
+    serde_json::from_value(value).unwrap()
+}
+
+fn full_contact() -> Contact {
+    contact(json!({
+        "name": {
+            "formatted_name": "Dr. Ana María López, PhD",
+            "first_name": "Ana",
+            "middle_name": "María",
+            "last_name": "López",
+            "prefix": "Dr.",
+            "suffix": "PhD",
+        },
+        "org": { "company": "Acme; Sons", "department": "R&D", "title": "Head of research" },
+        "phones": [
+            { "phone": "+1 555 123 4567", "type": "CELL", "wa_id": "15551234567" },
+            { "phone": "+1 555 000 0000", "type": "WORK" },
+        ],
+        "emails": [{ "email": "ana@example.com", "type": "WORK" }],
+        "addresses": [{
+            "street": "1 Main St",
+            "city": "Springfield",
+            "state": "IL",
+            "zip": "62701",
+            "country": "United States",
+            "type": "HOME",
+        }],
+        "urls": [{ "url": "https://example.com/a,b;c", "type": "WORK" }],
+        "birthday": "1990-04-12",
+    }))
+}
+
+fn lines(card: &str) -> Vec<&str> {
+    card.split("\r\n").filter(|line| !line.is_empty()).collect()
+}
+
+fn value(contact: &Contact) -> Value {
+    serde_json::to_value(contact).unwrap()
+}
+
+
+#[test]
+fn round_trips_in_both_versions() {
+    let contact = full_contact();
+    for version in [VCardVersion::V3, VCardVersion::V4] {
+        let card = contact.to_vcard(version);
+        let parsed = Contact::from_vcard(&card).unwrap();
+        assert_eq!(value(&parsed), value(&contact), "{}", version);
+    }
+}
+
+#[test]
+fn special_characters_are_escaped() {
+    let card = full_contact().to_vcard(VCardVersion::V3);
+    let written = lines(&card);
+
+    assert!(written.contains(&"FN:Dr. Ana María López\\, PhD"));
+    assert!(written.contains(&"ORG:Acme\\; Sons;R&D"));
+    assert!(written.contains(&"URL;TYPE=WORK:https://example.com/a\\,b\\;c"));
+
+    let note = contact(json!({ "name": { "formatted_name": "Back\\slash\nNew line", "first_name": "B" } }));
+    let card = note.to_vcard(VCardVersion::V4);
+    assert!(lines(&card).contains(&"FN:Back\\\\slash\\nNew line"));
+    assert_eq!(Contact::from_vcard(&card).unwrap().name.formatted_name, "Back\\slash\nNew line");
+}
+
+#[test]
+fn long_lines_are_folded_and_unfolded() {
+    let name = "Ünïcödé ".repeat(20);
+    let contact = contact(json!({ "name": { "formatted_name": name.trim(), "first_name": "U" } }));
+    let card = contact.to_vcard(VCardVersion::V4);
+
+    for line in card.split("\r\n") {
+        assert!(line.len() <= 75, "{:?}", line);
+    }
+    assert!(card.contains("\r\n "));
+
+    let parsed = Contact::from_vcard(&card).unwrap();
+    assert_eq!(parsed.name.formatted_name, name.trim());
+}
+
+#[test]
+fn folded_input_with_tabs_and_lf_is_read() {
+    let text = "BEGIN:VCARD\nVERSION:3.0\nFN:Jane\n  Doe\nN:Doe;Jane;;;\nEMAIL;TYPE=INTERNET,HOME:ja\n\tne@example.com\nEND:VCARD\n";
+    let parsed = Contact::from_vcard(text).unwrap();
+
+    assert_eq!(parsed.name.formatted_name, "Jane Doe");
+    let emails = parsed.emails.unwrap();
+    assert_eq!(emails[0].email, "jane@example.com");
+    assert_eq!(emails[0].r#type, "HOME");
+}
+
+#[test]
+fn birthday_format_depends_on_the_version() {
+    let contact = full_contact();
+
+    assert!(lines(&contact.to_vcard(VCardVersion::V3)).contains(&"BDAY:1990-04-12"));
+    assert!(lines(&contact.to_vcard(VCardVersion::V4)).contains(&"BDAY:19900412"));
+
+    let text = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:A\r\nBDAY:19900412T000000\r\nEND:VCARD\r\n";
+    assert_eq!(Contact::from_vcard(text).unwrap().birthday.as_deref(), Some("1990-04-12"));
+
+    let text = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:A\r\nBDAY:--0412\r\nEND:VCARD\r\n";
+    assert_eq!(Contact::from_vcard(text).unwrap().birthday, None);
+}
+
+#[test]
+fn waid_round_trips_on_tel_lines() {
+    let card = full_contact().to_vcard(VCardVersion::V3);
+    assert!(lines(&card).contains(&"TEL;TYPE=CELL;waid=15551234567:+1 555 123 4567"));
+
+    let card = full_contact().to_vcard(VCardVersion::V4);
+    assert!(lines(&card).contains(&"TEL;TYPE=cell;VALUE=text;waid=15551234567:+1 555 123 4567"));
+
+    let phones = Contact::from_vcard(&card).unwrap().phones.unwrap();
+    assert_eq!(phones[0].wa_id.as_deref(), Some("15551234567"));
+    assert_eq!(phones[1].wa_id, None);
+}
+
+#[test]
+fn several_cards_are_written_and_read() {
+    let contacts = vec![full_contact(), contact(json!({ "name": { "formatted_name": "Bob", "first_name": "Bob" } }))];
+    let text = to_vcards(&contacts, VCardVersion::V3);
+
+    let parsed = parse_vcards(&text).unwrap();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[1].name.formatted_name, "Bob");
+    assert!(Contact::from_vcard(&text).is_err());
+}
+
+#[test]
+fn malformed_cards_are_rejected() {
+    assert!(Contact::from_vcard("").is_err());
+    assert!(Contact::from_vcard("BEGIN:VCARD\r\nFN:A\r\n").is_err());
+    assert!(Contact::from_vcard("FN:A\r\n").is_err());
+    assert!(Contact::from_vcard("BEGIN:VCARD\r\nVERSION:3.0\r\nEND:VCARD\r\n").is_err());
+}
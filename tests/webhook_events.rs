@@ -0,0 +1,61 @@
+//! Parsing of inbound message webhooks
+
+mod common;
+
+use serde_json::json;
+use whatsapp_cloud_sdk::types::webhook::*;
+
+use common::{message, messages_event, statuses_event};
+
+#[test]
+fn parses_button_and_list_replies() {
+    let button = message(json!({
+        "from": "15551234567", "id": "wamid.1", "timestamp": "1700000000", "type": "interactive",
+        "interactive": { "type": "button_reply", "button_reply": { "id": "yes", "title": "Yes" } },
+    }));
+    assert!(matches!(
+        button.interactive,
+        Some(WebhookInteractive::ButtonReply { ref button_reply }) if button_reply.id == "yes"
+    ));
+
+    let list = message(json!({
+        "from": "15551234567", "id": "wamid.2", "timestamp": "1700000000", "type": "interactive",
+        "interactive": { "type": "list_reply", "list_reply": { "id": "pasta", "title": "Pasta", "description": "Fresh" } },
+    }));
+    assert!(matches!(
+        list.interactive,
+        Some(WebhookInteractive::ListReply { ref list_reply }) if list_reply.description.as_deref() == Some("Fresh")
+    ));
+}
+
+#[test]
+fn unknown_interactive_types_do_not_drop_the_event() {
+    let event = messages_event(vec![json!({
+        "from": "15551234567", "id": "wamid.1", "timestamp": "1700000000", "type": "interactive",
+        "interactive": { "type": "payment_reply", "payment_reply": { "id": "p1" } },
+    })]);
+
+    let change = event.changes().next().unwrap();
+    match &change.value {
+        WebhookChangeValue::Messages(value) => {
+            assert!(matches!(value.messages[0].interactive, Some(WebhookInteractive::Unknown)));
+        }
+        other => panic!("unexpected change {:?}", other),
+    }
+}
+
+#[test]
+fn parses_statuses() {
+    let event = statuses_event(vec![json!({
+        "id": "wamid.1", "status": "delivered", "timestamp": "1700000000", "recipient_id": "15551234567",
+        "biz_opaque_callback_data": "order-7",
+    })]);
+
+    let change = event.changes().next().unwrap();
+    match &change.value {
+        WebhookChangeValue::Messages(value) => {
+            assert_eq!(value.statuses[0].biz_opaque_callback_data.as_deref(), Some("order-7"));
+        }
+        other => panic!("unexpected change {:?}", other),
+    }
+}